@@ -1,25 +1,111 @@
 use alloc::vec::Vec;
+use core::{fmt, str::FromStr};
 
 use bech32::segwit;
 use thiserror::Error;
 
+use crate::{hash::double_sha256, policy::Policy};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Network {
     Mainnet,
+    /// Testnet3.
     Testnet,
+    /// Testnet4 (BIP94).
+    Testnet4,
+    /// The default public signet.
+    Signet,
     Regtest,
 }
 
+impl Network {
+    /// Every network, in the order ambiguous address encodings resolve to.
+    pub const ALL: [Network; 5] = [
+        Network::Mainnet,
+        Network::Testnet,
+        Network::Testnet4,
+        Network::Signet,
+        Network::Regtest,
+    ];
+
+    /// Lowercase name, as accepted by [`FromStr`].
+    pub const fn name(self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+            Network::Testnet4 => "testnet4",
+            Network::Signet => "signet",
+            Network::Regtest => "regtest",
+        }
+    }
+
+    /// Human-readable part of SegWit addresses. The public test chains share `tb`.
+    pub const fn bech32_hrp(self) -> &'static str {
+        match self {
+            Network::Mainnet => "bc",
+            Network::Testnet | Network::Testnet4 | Network::Signet => "tb",
+            Network::Regtest => "bcrt",
+        }
+    }
+
+    /// Base58check version bytes of P2PKH and P2SH addresses. Every chain but
+    /// mainnet uses the testnet ones.
+    pub const fn base58_prefixes(self) -> (u8, u8) {
+        match self {
+            Network::Mainnet => (0x00, 0x05),
+            _ => (0x6f, 0xc4),
+        }
+    }
+
+    /// Relay policy of a Bitcoin Core node on this chain. Core uses the same dust,
+    /// datacarrier and relay fee defaults everywhere.
+    pub const fn default_policy(self) -> Policy {
+        Policy::CORE_V30
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Network {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Network::ALL
+            .into_iter()
+            .find(|network| network.name() == s)
+            .ok_or(AddressError::UnknownNetwork)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AddressType {
+    P2PKH,
+    P2SH,
     P2WPKH,
+    P2WSH,
     P2TR,
+    /// Pay-to-anchor: the keyless `OP_1 <0x4e73>` output used for CPFP fee bumping.
+    P2A,
+    /// Witness version 1..=16 program with no defined meaning yet, spendable by anyone
+    /// until a soft fork assigns one. The version is carried along.
+    WitnessUnknown(u8),
 }
 
+/// Witness program of the pay-to-anchor output.
+pub const ANCHOR_PROGRAM: [u8; 2] = [0x4e, 0x73];
+
+/// scriptPubKey of the pay-to-anchor output: `OP_1 <0x4e73>`.
+pub const ANCHOR_SCRIPT_PUBKEY: [u8; 4] = [0x51, 0x02, 0x4e, 0x73];
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParsedAddress {
     pub network: Network,
     pub address_type: AddressType,
+    /// Witness program, or the 20-byte key or script hash of a base58 address.
     pub witness_program: Vec<u8>,
 }
 
@@ -27,55 +113,87 @@ pub struct ParsedAddress {
 pub enum AddressError {
     #[error("invalid bech32/bech32m address")]
     InvalidBech32,
-    #[error("unsupported witness version: {0}")]
-    UnsupportedWitnessVersion(u8),
+    #[error("invalid base58check address")]
+    InvalidBase58,
     #[error("invalid witness program length")]
     InvalidProgramLength,
     #[error("unsupported address type")]
     UnsupportedAddressType,
     #[error("network mismatch")]
     NetworkMismatch,
+    #[error("unknown network")]
+    UnknownNetwork,
 }
 
-/// Parse a bech32/bech32m SegWit address (P2WPKH or P2TR).
+/// Base58 digits in value order.
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Parse a bech32/bech32m SegWit address (P2WPKH, P2WSH or P2TR) or a base58check
+/// legacy address (P2PKH or P2SH).
 ///
-/// If the HRP is `tb`, the network is treated as Testnet (covers former Signet usage).
+/// The `tb` HRP and the base58 testnet prefixes are shared by several chains and
+/// parse as Testnet; use [`parse_address_for_network`] to resolve them to another.
 pub fn parse_address(addr: &str) -> Result<ParsedAddress, AddressError> {
     parse_address_for_network(addr, None)
 }
 
-/// Parse a SegWit address while optionally constraining the expected network.
+/// Parse an address while optionally constraining the expected network. An
+/// encoding shared with the expected network parses as that network.
 pub fn parse_address_for_network(
     addr: &str,
     expected: Option<Network>,
 ) -> Result<ParsedAddress, AddressError> {
-    let (hrp_value, version_fe, program) =
-        segwit::decode(addr).map_err(|_| AddressError::InvalidBech32)?;
-    let version = version_fe.to_u8();
-
-    let network = match hrp_value.as_str() {
-        "bc" => Network::Mainnet,
-        "tb" => Network::Testnet,
-        "bcrt" => Network::Regtest,
-        _ => return Err(AddressError::NetworkMismatch),
+    let parsed = match segwit::decode(addr) {
+        Ok((hrp_value, version_fe, program)) => {
+            parse_segwit(hrp_value.as_str(), version_fe.to_u8(), program, expected)?
+        }
+        Err(_) if has_segwit_hrp(addr) => return Err(AddressError::InvalidBech32),
+        Err(_) => parse_base58(addr, expected)?,
     };
 
     if let Some(expected_net) = expected {
-        if expected_net != network {
+        if expected_net != parsed.network {
             return Err(AddressError::NetworkMismatch);
         }
     }
 
+    Ok(parsed)
+}
+
+fn has_segwit_hrp(addr: &str) -> bool {
+    let lower = addr.to_ascii_lowercase();
+    ["bc1", "tb1", "bcrt1"]
+        .iter()
+        .any(|prefix| lower.starts_with(prefix))
+}
+
+/// `expected` if its addresses use this encoding, else the first network that does.
+fn resolve_network(expected: Option<Network>, uses: impl Fn(Network) -> bool) -> Option<Network> {
+    match expected {
+        Some(network) if uses(network) => Some(network),
+        _ => Network::ALL.into_iter().find(|&network| uses(network)),
+    }
+}
+
+fn parse_segwit(
+    hrp: &str,
+    version: u8,
+    program: Vec<u8>,
+    expected: Option<Network>,
+) -> Result<ParsedAddress, AddressError> {
+    let network = resolve_network(expected, |network| network.bech32_hrp() == hrp)
+        .ok_or(AddressError::NetworkMismatch)?;
+
     let address_type = match version {
         0 => match program.len() {
             20 => AddressType::P2WPKH,
+            32 => AddressType::P2WSH,
             _ => return Err(AddressError::InvalidProgramLength),
         },
-        1 => match program.len() {
-            32 => AddressType::P2TR,
-            _ => return Err(AddressError::InvalidProgramLength),
-        },
-        v => return Err(AddressError::UnsupportedWitnessVersion(v)),
+        1 if program.len() == 32 => AddressType::P2TR,
+        1 if program == ANCHOR_PROGRAM => AddressType::P2A,
+        // bech32 decoding already bounds versions to 16 and programs to 2..=40 bytes.
+        v => AddressType::WitnessUnknown(v),
     };
 
     Ok(ParsedAddress {
@@ -85,22 +203,97 @@ pub fn parse_address_for_network(
     })
 }
 
+fn parse_base58(addr: &str, expected: Option<Network>) -> Result<ParsedAddress, AddressError> {
+    let payload = decode_base58check(addr).ok_or(AddressError::InvalidBase58)?;
+    let (&version, hash) = payload.split_first().ok_or(AddressError::InvalidBase58)?;
+    if hash.len() != 20 {
+        return Err(AddressError::InvalidProgramLength);
+    }
+
+    let uses = |network: Network| {
+        let (p2pkh, p2sh) = network.base58_prefixes();
+        version == p2pkh || version == p2sh
+    };
+    let network = resolve_network(expected, uses).ok_or(AddressError::UnsupportedAddressType)?;
+    let address_type = if version == network.base58_prefixes().0 {
+        AddressType::P2PKH
+    } else {
+        AddressType::P2SH
+    };
+
+    Ok(ParsedAddress {
+        network,
+        address_type,
+        witness_program: hash.to_vec(),
+    })
+}
+
+/// Decode base58check, returning the payload (version byte included) if the
+/// checksum matches.
+pub(crate) fn decode_base58check(s: &str) -> Option<Vec<u8>> {
+    // Little-endian base-256 digits of the number being decoded.
+    let mut digits: Vec<u8> = Vec::with_capacity(s.len());
+    for c in s.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|&a| a == c)? as u32;
+        for digit in digits.iter_mut() {
+            carry += *digit as u32 * 58;
+            *digit = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            digits.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    // Each leading '1' encodes a leading zero byte.
+    let zeros = s.bytes().take_while(|&c| c == b'1').count();
+    digits.resize(digits.len() + zeros, 0);
+    digits.reverse();
+
+    if digits.len() < 4 {
+        return None;
+    }
+    let (payload, checksum) = digits.split_at(digits.len() - 4);
+    (double_sha256(payload)[..4] == *checksum).then(|| payload.to_vec())
+}
+
 /// Convert a parsed address to its scriptPubKey representation.
 pub fn address_to_script_pubkey(parsed: &ParsedAddress) -> Vec<u8> {
-    let mut script = Vec::with_capacity(2 + parsed.witness_program.len());
+    let program = &parsed.witness_program;
+    let mut script = Vec::with_capacity(5 + program.len());
     match parsed.address_type {
-        AddressType::P2WPKH => script.push(0x00), // OP_0
-        AddressType::P2TR => script.push(0x51),   // OP_1
+        AddressType::P2PKH => {
+            // OP_DUP OP_HASH160 <20> OP_EQUALVERIFY OP_CHECKSIG
+            script.extend_from_slice(&[0x76, 0xa9, program.len() as u8]);
+            script.extend_from_slice(program);
+            script.extend_from_slice(&[0x88, 0xac]);
+        }
+        AddressType::P2SH => {
+            // OP_HASH160 <20> OP_EQUAL
+            script.extend_from_slice(&[0xa9, program.len() as u8]);
+            script.extend_from_slice(program);
+            script.push(0x87);
+        }
+        AddressType::P2WPKH | AddressType::P2WSH => {
+            script.extend_from_slice(&[0x00, program.len() as u8]); // OP_0
+            script.extend_from_slice(program);
+        }
+        AddressType::P2TR | AddressType::P2A => {
+            script.extend_from_slice(&[0x51, program.len() as u8]); // OP_1
+            script.extend_from_slice(program);
+        }
+        AddressType::WitnessUnknown(version) => {
+            script.extend_from_slice(&[0x50 + version, program.len() as u8]); // OP_n
+            script.extend_from_slice(program);
+        }
     }
-    script.push(parsed.witness_program.len() as u8);
-    script.extend_from_slice(&parsed.witness_program);
     script
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use bech32::{hrp, segwit};
+    use bech32::{hrp, segwit, Fe32};
 
     #[test]
     fn parses_mainnet_p2wpkh() {
@@ -141,6 +334,24 @@ mod tests {
         assert_eq!(parsed.witness_program, program);
     }
 
+    #[test]
+    fn test_chains_share_the_tb_hrp() {
+        let addr = segwit::encode(hrp::TB, segwit::VERSION_0, &[0x11u8; 20]).unwrap();
+        for network in [Network::Testnet4, Network::Signet] {
+            let parsed = parse_address_for_network(&addr, Some(network)).unwrap();
+            assert_eq!(parsed.network, network);
+            assert_eq!(network.name().parse::<Network>(), Ok(network));
+        }
+        assert_eq!(
+            parse_address_for_network(&addr, Some(Network::Regtest)).unwrap_err(),
+            AddressError::NetworkMismatch
+        );
+        assert_eq!(
+            "testnet3".parse::<Network>(),
+            Err(AddressError::UnknownNetwork)
+        );
+    }
+
     #[test]
     fn rejects_wrong_expected_network() {
         let program = vec![0x22u8; 20];
@@ -150,11 +361,72 @@ mod tests {
     }
 
     #[test]
-    fn rejects_legacy_addresses() {
+    fn parses_base58_addresses() {
+        let p2pkh = parse_address("1BoatSLRHtKNngkdXEeobR76b53LETtpyT").unwrap();
+        assert_eq!(p2pkh.network, Network::Mainnet);
+        assert_eq!(p2pkh.address_type, AddressType::P2PKH);
+        assert_eq!(
+            address_to_script_pubkey(&p2pkh),
+            hex::decode("76a9147680adec8eabcabac676be9e83854ade0bd22cdb88ac").unwrap()
+        );
+
+        let p2sh = parse_address("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy").unwrap();
+        assert_eq!(p2sh.address_type, AddressType::P2SH);
         assert_eq!(
-            parse_address("1BoatSLRHtKNngkdXEeobR76b53LETtpyT").unwrap_err(),
-            AddressError::InvalidBech32
+            address_to_script_pubkey(&p2sh),
+            hex::decode("a914b472a266d0bd89c13706a4132ccfb16f7c3b9fcb87").unwrap()
         );
+
+        let testnet = "mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn";
+        assert_eq!(parse_address(testnet).unwrap().network, Network::Testnet);
+        for network in [Network::Testnet4, Network::Signet, Network::Regtest] {
+            let parsed = parse_address_for_network(testnet, Some(network)).unwrap();
+            assert_eq!(parsed.network, network);
+        }
+        assert_eq!(
+            parse_address_for_network(testnet, Some(Network::Mainnet)).unwrap_err(),
+            AddressError::NetworkMismatch
+        );
+
+        // Last character changed: checksum no longer matches.
+        assert_eq!(
+            parse_address("1BoatSLRHtKNngkdXEeobR76b53LETtpyU").unwrap_err(),
+            AddressError::InvalidBase58
+        );
+    }
+
+    #[test]
+    fn parses_anchor_and_future_witness_versions() {
+        let anchor = parse_address("bc1pfeessrawgf").unwrap();
+        assert_eq!(anchor.address_type, AddressType::P2A);
+        assert_eq!(address_to_script_pubkey(&anchor), ANCHOR_SCRIPT_PUBKEY);
+
+        let program = vec![0xabu8; 40];
+        let addr = segwit::encode(hrp::TB, Fe32::try_from(2u8).unwrap(), &program).unwrap();
+        let parsed = parse_address(&addr).unwrap();
+        assert_eq!(parsed.address_type, AddressType::WitnessUnknown(2));
+        let spk = address_to_script_pubkey(&parsed);
+        assert_eq!(&spk[..2], &[0x52, 40]);
+        assert_eq!(&spk[2..], program.as_slice());
+
+        let v16 = segwit::encode(hrp::BC, Fe32::try_from(16u8).unwrap(), &[0x01, 0x02]).unwrap();
+        let parsed = parse_address(&v16).unwrap();
+        assert_eq!(parsed.address_type, AddressType::WitnessUnknown(16));
+        assert_eq!(
+            address_to_script_pubkey(&parsed),
+            vec![0x60, 0x02, 0x01, 0x02]
+        );
+    }
+
+    #[test]
+    fn parses_p2wsh() {
+        let program = vec![0x99u8; 32];
+        let addr = segwit::encode(hrp::BC, segwit::VERSION_0, &program).unwrap();
+        let parsed = parse_address(&addr).unwrap();
+        assert_eq!(parsed.address_type, AddressType::P2WSH);
+        let spk = address_to_script_pubkey(&parsed);
+        assert_eq!(&spk[..2], &[0x00, 0x20]);
+        assert_eq!(&spk[2..], program.as_slice());
     }
 
     #[test]
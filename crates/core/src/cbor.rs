@@ -4,7 +4,7 @@
 //! - Unsigned integers (major type 0)
 //! - Arrays (major type 4)
 
-use alloc::{vec, vec::Vec};
+use alloc::vec::Vec;
 
 /// CBOR major type 0: unsigned integer
 const MAJOR_TYPE_UINT: u8 = 0x00;
@@ -56,29 +56,58 @@ pub fn encode_cbor_uint(value: u64) -> Vec<u8> {
     encode_cbor_uint_with_major_type(MAJOR_TYPE_UINT, value)
 }
 
+/// Encode a u64 as a CBOR unsigned integer into a fixed buffer, returning the
+/// number of bytes written.
+///
+/// Allocation-free variant of [`encode_cbor_uint`] for hot mining loops.
+///
+/// # Examples
+///
+/// ```
+/// use zeldhash_miner_core::cbor::encode_cbor_uint_into;
+///
+/// let mut buf = [0u8; 9];
+/// assert_eq!(encode_cbor_uint_into(256, &mut buf), 3);
+/// assert_eq!(&buf[..3], &[0x19, 0x01, 0x00]);
+/// ```
+#[inline]
+pub fn encode_cbor_uint_into(value: u64, out: &mut [u8; 9]) -> usize {
+    encode_cbor_uint_with_major_type_into(MAJOR_TYPE_UINT, value, out)
+}
+
 /// Internal helper: encode a CBOR value with a given major type.
 fn encode_cbor_uint_with_major_type(major_type: u8, value: u64) -> Vec<u8> {
+    let mut buf = [0u8; 9];
+    let len = encode_cbor_uint_with_major_type_into(major_type, value, &mut buf);
+    buf[..len].to_vec()
+}
+
+#[inline]
+fn encode_cbor_uint_with_major_type_into(major_type: u8, value: u64, out: &mut [u8; 9]) -> usize {
     match value {
         0..=23 => {
-            vec![major_type | (value as u8)]
+            out[0] = major_type | (value as u8);
+            1
         }
         24..=255 => {
-            vec![major_type | 24, value as u8]
+            out[0] = major_type | 24;
+            out[1] = value as u8;
+            2
         }
         256..=65535 => {
-            let mut buf = vec![major_type | 25];
-            buf.extend_from_slice(&(value as u16).to_be_bytes());
-            buf
+            out[0] = major_type | 25;
+            out[1..3].copy_from_slice(&(value as u16).to_be_bytes());
+            3
         }
         65536..=0xFFFF_FFFF => {
-            let mut buf = vec![major_type | 26];
-            buf.extend_from_slice(&(value as u32).to_be_bytes());
-            buf
+            out[0] = major_type | 26;
+            out[1..5].copy_from_slice(&(value as u32).to_be_bytes());
+            5
         }
         _ => {
-            let mut buf = vec![major_type | 27];
-            buf.extend_from_slice(&value.to_be_bytes());
-            buf
+            out[0] = major_type | 27;
+            out[1..9].copy_from_slice(&value.to_be_bytes());
+            9
         }
     }
 }
@@ -167,6 +196,27 @@ mod tests {
         assert_eq!(encode_cbor_uint(256), vec![0x19, 0x01, 0x00]);
     }
 
+    #[test]
+    fn encode_into_matches_vec_encoding() {
+        let mut buf = [0u8; 9];
+        for value in [
+            0u64,
+            23,
+            24,
+            255,
+            256,
+            65535,
+            65536,
+            0xFFFF_FFFF,
+            1 << 32,
+            u64::MAX,
+        ] {
+            let len = encode_cbor_uint_into(value, &mut buf);
+            assert_eq!(&buf[..len], encode_cbor_uint(value).as_slice());
+            assert_eq!(len, cbor_uint_length(value));
+        }
+    }
+
     #[test]
     fn encodes_cbor_uint_boundaries() {
         // 0-23: single byte
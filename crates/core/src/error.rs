@@ -1,4 +1,6 @@
-use crate::{address::AddressError, fees::FeeError, psbt::PsbtError, tx::TxError};
+use crate::{
+    address::AddressError, fees::FeeError, policy::PolicyError, psbt::PsbtError, tx::TxError,
+};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -11,6 +13,8 @@ pub enum ZeldError {
     Tx(#[from] TxError),
     #[error("psbt error: {0}")]
     Psbt(#[from] PsbtError),
+    #[error("policy error: {0}")]
+    Policy(#[from] PolicyError),
 }
 
 pub type Result<T> = core::result::Result<T, ZeldError>;
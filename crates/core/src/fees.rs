@@ -1,4 +1,7 @@
-use crate::tx::{encode_varint, TxInput, TxOutput};
+use alloc::vec::Vec;
+use core::{fmt, str::FromStr};
+
+use crate::tx::{encode_varint, is_witness_program, TxInput, TxOutput, WitnessHint};
 use thiserror::Error;
 
 // Typical witness sizes to keep fees tight while remaining realistic.
@@ -6,19 +9,142 @@ use thiserror::Error;
 const P2WPKH_WITNESS_TYPICAL: usize = 1 /* stack items */ + 1 /* sig len */ + 72 /* sig */ +
     1 /* pk len */ + 33 /* pk */;
 const P2TR_WITNESS_TYPICAL: usize = 1 /* stack items */ + 1 /* sig len */ + 64 /* sig */;
+/// Signature push in a P2WSH multisig witness (DER signature with sighash byte).
+const ECDSA_SIG_PUSH: usize = 1 + 72;
+/// Signature push in a tapscript witness (Schnorr signature, default sighash).
+const SCHNORR_SIG_PUSH: usize = 1 + 64;
+/// Standard P2WSH `OP_CHECKMULTISIG` allows at most 20 keys.
+const MAX_MULTISIG_KEYS: u8 = 20;
+/// Deepest leaf a taproot control block can commit to.
+const MAX_TAPROOT_DEPTH: u8 = 128;
 
 #[derive(Debug, Error, PartialEq, Eq)]
 pub enum FeeError {
     #[error("insufficient funds for outputs and fee")]
     InsufficientFunds,
+    #[error("inputs[{index}] has an unrecognized script type; provide a witness size hint")]
+    UnknownWitnessSize { index: usize },
+    #[error("inputs[{index}] has an invalid witness size hint")]
+    InvalidWitnessHint { index: usize },
+    #[error("inputs[{index}] is not SegWit; signing its scriptSig would change the txid")]
+    NotSegwit { index: usize },
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FeeRateError {
+    #[error("fee rate must be a decimal number of sat/vB")]
+    Invalid,
+    #[error("fee rate supports at most 3 decimal places of sat/vB")]
+    TooPrecise,
+    #[error("fee rate is too large")]
+    Overflow,
+}
+
+/// Fee rate in satoshis per 1000 virtual bytes (sat/kvB), the unit of Bitcoin Core's
+/// `CFeeRate`, so rates such as 1.5 or 2.345 sat/vB are exact.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FeeRate(u64);
+
+impl FeeRate {
+    pub const ZERO: FeeRate = FeeRate(0);
+
+    pub const fn from_sat_per_kvb(sat_per_kvb: u64) -> Self {
+        FeeRate(sat_per_kvb)
+    }
+
+    /// Whole sat/vB rate; `None` if it does not fit in sat/kvB.
+    pub const fn from_sat_per_vb(sat_per_vb: u64) -> Option<Self> {
+        match sat_per_vb.checked_mul(1000) {
+            Some(sat_per_kvb) => Some(FeeRate(sat_per_kvb)),
+            None => None,
+        }
+    }
+
+    /// Rate paid by `fee` over `vsize` vbytes, truncated like `CFeeRate(fee, size)`.
+    pub fn from_fee_and_vsize(fee: u64, vsize: usize) -> Self {
+        if vsize == 0 {
+            return FeeRate::ZERO;
+        }
+        let sat_per_kvb = fee as u128 * 1000 / vsize as u128;
+        FeeRate(sat_per_kvb.min(u64::MAX as u128) as u64)
+    }
+
+    pub const fn to_sat_per_kvb(self) -> u64 {
+        self.0
+    }
+
+    /// Fee for `vsize` vbytes, rounded up to the next satoshi like `CFeeRate::GetFee`.
+    pub fn fee_for_vsize(self, vsize: usize) -> u64 {
+        let fee = (self.0 as u128 * vsize as u128).div_ceil(1000);
+        fee.min(u64::MAX as u128) as u64
+    }
+}
+
+impl fmt::Display for FeeRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:03} sat/vB", self.0 / 1000, self.0 % 1000)
+    }
+}
+
+/// Parses a decimal sat/vB amount such as `"1.5"`, with the 3-decimal precision
+/// Bitcoin Core accepts for `fee_rate` arguments.
+impl FromStr for FeeRate {
+    type Err = FeeRateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (whole, frac) = s.split_once('.').unwrap_or((s, ""));
+        let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if whole.is_empty() || !is_digits(whole) || !is_digits(frac) || s.ends_with('.') {
+            return Err(FeeRateError::Invalid);
+        }
+        // Trailing zeros add no precision.
+        let frac = frac.trim_end_matches('0');
+        if frac.len() > 3 {
+            return Err(FeeRateError::TooPrecise);
+        }
+
+        let mut sat_per_kvb: u64 = 0;
+        for digit in whole.bytes().chain(frac.bytes()) {
+            sat_per_kvb = sat_per_kvb
+                .checked_mul(10)
+                .and_then(|v| v.checked_add((digit - b'0') as u64))
+                .ok_or(FeeRateError::Overflow)?;
+        }
+        for _ in frac.len()..3 {
+            sat_per_kvb = sat_per_kvb.checked_mul(10).ok_or(FeeRateError::Overflow)?;
+        }
+        Ok(FeeRate(sat_per_kvb))
+    }
 }
 
 /// Estimate virtual size (vbytes) for a SegWit transaction with a fixed-size OP_RETURN.
-pub fn calculate_vsize(inputs: &[TxInput], outputs: &[TxOutput], op_return_size: usize) -> usize {
+pub fn calculate_vsize(
+    inputs: &[TxInput],
+    outputs: &[TxOutput],
+    op_return_size: usize,
+) -> Result<usize, FeeError> {
+    Ok((calculate_weight(inputs, outputs, op_return_size)? + 3) / 4)
+}
+
+/// Estimate weight units for a SegWit transaction with a fixed-size OP_RETURN.
+///
+/// Fails when an input's witness size can be neither derived from its scriptPubKey
+/// nor taken from its [`WitnessHint`].
+pub fn calculate_weight(
+    inputs: &[TxInput],
+    outputs: &[TxOutput],
+    op_return_size: usize,
+) -> Result<usize, FeeError> {
     let base = base_tx_size(inputs, outputs, op_return_size);
-    let witness = witness_size(inputs);
-    let weight = base * 4 + witness;
-    (weight + 3) / 4
+    let witness = witness_size(inputs)?;
+    Ok(base * 4 + witness)
+}
+
+/// Estimate the virtual size of a transaction paying exactly `outputs`, with no ZELD
+/// OP_RETURN added, such as one a wallet already built.
+pub fn calculate_tx_vsize(inputs: &[TxInput], outputs: &[TxOutput]) -> Result<usize, FeeError> {
+    let base = crate::tx::serialize_tx_for_txid(inputs, outputs, Default::default()).len();
+    Ok((base * 4 + witness_size(inputs)? + 3) / 4)
 }
 
 /// Convenience wrapper that uses the caller-provided nonce length for OP_RETURN sizing.
@@ -26,11 +152,157 @@ pub fn calculate_vsize_with_nonce_len(
     inputs: &[TxInput],
     outputs: &[TxOutput],
     nonce_len: usize,
-) -> usize {
+) -> Result<usize, FeeError> {
     calculate_vsize(inputs, outputs, nonce_len)
 }
 
-/// Fee in satoshis for a given vsize and rate.
+/// Witness bytes spending `input` adds, from its hint or else its scriptPubKey.
+///
+/// `index` only labels errors.
+pub fn input_witness_size(index: usize, input: &TxInput) -> Result<usize, FeeError> {
+    // Nested SegWit inputs are spent according to the program they wrap.
+    let program = input
+        .redeem_script
+        .as_deref()
+        .unwrap_or(&input.script_pubkey);
+    if !is_witness_program(program) {
+        return Err(FeeError::NotSegwit { index });
+    }
+    let invalid = FeeError::InvalidWitnessHint { index };
+    match input.witness_hint {
+        Some(WitnessHint::Size(size)) => Ok(size),
+        Some(WitnessHint::Multisig { required, keys }) => {
+            if required == 0 || required > keys || keys > MAX_MULTISIG_KEYS {
+                return Err(invalid);
+            }
+            // OP_m <keys> OP_n OP_CHECKMULTISIG
+            let script_len = 3 + keys as usize * 34;
+            // Stack: the empty dummy CHECKMULTISIG pops, the signatures, the script.
+            let items = required as usize + 2;
+            Ok(encode_varint(items as u64).len()
+                + 1
+                + required as usize * ECDSA_SIG_PUSH
+                + encode_varint(script_len as u64).len()
+                + script_len)
+        }
+        Some(WitnessHint::TapScript {
+            signatures,
+            script_len,
+            depth,
+        }) => {
+            if depth > MAX_TAPROOT_DEPTH {
+                return Err(invalid);
+            }
+            let control_block_len = 33 + 32 * depth as usize;
+            let items = signatures as usize + 2;
+            Ok(encode_varint(items as u64).len()
+                + signatures as usize * SCHNORR_SIG_PUSH
+                + encode_varint(script_len as u64).len()
+                + script_len
+                + encode_varint(control_block_len as u64).len()
+                + control_block_len)
+        }
+        None => match program {
+            // P2WPKH: stack = [sig, pubkey]
+            // Typical size: 1 (items) + 1 (sig len) + 72 (sig) + 1 (pk len) + 33 (pk) = 108 bytes.
+            program @ [0x00, 0x14, ..] if program.len() == 22 => Ok(P2WPKH_WITNESS_TYPICAL),
+            // P2TR key-path: stack = [sig]
+            // Typical sizes: 1 (items) + 1 (sig len) + 64 (sig) = 66 bytes.
+            program @ [0x51, 0x20, ..] if program.len() == 34 => Ok(P2TR_WITNESS_TYPICAL),
+            _ => Err(FeeError::UnknownWitnessSize { index }),
+        },
+    }
+}
+
+/// How much fee a planned transaction pays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fee {
+    /// Rate applied to the estimated vsize, rounded up to the next satoshi.
+    Rate(FeeRate),
+    /// Fixed amount in satoshis, whatever the size.
+    Absolute(u64),
+}
+
+impl Fee {
+    /// Fee in satoshis for a transaction of `vsize` vbytes.
+    pub fn amount_for_vsize(self, vsize: usize) -> u64 {
+        match self {
+            Fee::Rate(rate) => rate.fee_for_vsize(vsize),
+            Fee::Absolute(sats) => sats,
+        }
+    }
+}
+
+impl From<FeeRate> for Fee {
+    fn from(rate: FeeRate) -> Self {
+        Fee::Rate(rate)
+    }
+}
+
+/// Which outputs pay the fee.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FeeMode {
+    /// Inputs cover the outputs plus the fee; the change output takes what is left.
+    #[default]
+    Change,
+    /// Deduct the fee from these outputs (request indices) in equal shares, the first
+    /// one also paying the remainder, like Bitcoin Core's `subtractfeefromoutputs`.
+    SubtractFromOutputs(Vec<usize>),
+    /// Deduct the fee from every non-change, non-anchor output in proportion to its
+    /// amount; the first one also pays the rounding remainder.
+    SubtractProportionally,
+    /// The output at this request index receives every input sat not sent to the other
+    /// outputs or spent on the fee. Its `amount` is ignored and no change output is allowed.
+    SendMax(usize),
+}
+
+impl FeeMode {
+    /// Fee share of each output, by index, for the subtracting modes.
+    ///
+    /// `amounts` holds every output amount and `payers` the indices eligible to pay.
+    pub(crate) fn fee_shares(
+        &self,
+        fee: u64,
+        amounts: &[u64],
+        payers: &[usize],
+    ) -> Vec<(usize, u64)> {
+        match self {
+            FeeMode::SubtractFromOutputs(_) if !payers.is_empty() => {
+                let n = payers.len() as u64;
+                payers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &idx)| {
+                        let remainder = if i == 0 { fee % n } else { 0 };
+                        (idx, fee / n + remainder)
+                    })
+                    .collect()
+            }
+            FeeMode::SubtractProportionally if !payers.is_empty() => {
+                let total: u128 = payers.iter().map(|&idx| amounts[idx] as u128).sum();
+                let mut shares: Vec<(usize, u64)> = payers
+                    .iter()
+                    .map(|&idx| {
+                        let share = if total == 0 {
+                            0
+                        } else {
+                            (fee as u128 * amounts[idx] as u128 / total) as u64
+                        };
+                        (idx, share)
+                    })
+                    .collect();
+                let assigned: u64 = shares.iter().map(|&(_, share)| share).sum();
+                shares[0].1 += fee - assigned;
+                shares
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Fee in satoshis for a given vsize and whole sat/vB rate.
+///
+/// Integer counterpart of [`FeeRate::fee_for_vsize`], kept for existing callers.
 pub fn calculate_fee(vsize: usize, sat_per_vbyte: u64) -> u64 {
     vsize as u64 * sat_per_vbyte
 }
@@ -46,11 +318,10 @@ pub fn calculate_change(
     fee: u64,
     dust_limit: u64,
 ) -> Result<Option<u64>, FeeError> {
-    if total_input < outputs_sum + fee {
-        return Err(FeeError::InsufficientFunds);
-    }
-
-    let change = total_input - outputs_sum - fee;
+    let change = outputs_sum
+        .checked_add(fee)
+        .and_then(|needed| total_input.checked_sub(needed))
+        .ok_or(FeeError::InsufficientFunds)?;
     if change < dust_limit {
         // Change would be dust; omit the change output and let extra sats go to miners
         return Ok(None);
@@ -63,7 +334,11 @@ fn base_tx_size(inputs: &[TxInput], outputs: &[TxOutput], op_return_size: usize)
     let mut size = 4; // version
 
     size += encode_varint(inputs.len() as u64).len();
-    size += inputs.len() * (32 + 4 + 1 + 4); // txid + vout + script len (0) + sequence
+    for input in inputs {
+        // txid + vout + scriptSig (empty unless nested SegWit) + sequence
+        let script_sig_len = input.script_sig().len();
+        size += 32 + 4 + encode_varint(script_sig_len as u64).len() + script_sig_len + 4;
+    }
 
     let total_outputs = outputs.len() + 1; // include OP_RETURN
     size += encode_varint(total_outputs as u64).len();
@@ -88,34 +363,19 @@ fn output_size(output: &TxOutput) -> usize {
     8 + encode_varint(output.script_pubkey.len() as u64).len() + output.script_pubkey.len()
 }
 
-fn witness_size(inputs: &[TxInput]) -> usize {
-    let mut total = 0usize;
-    let mut any_segwit = false;
-
-    for input in inputs {
-        match input.script_pubkey.as_slice() {
-            // P2WPKH: stack = [sig, pubkey]
-            // Typical size: 1 (items) + 1 (sig len) + 72 (sig) + 1 (pk len) + 33 (pk) = 108 bytes.
-            [0x00, 0x14, ..] if input.script_pubkey.len() == 22 => {
-                total += P2WPKH_WITNESS_TYPICAL;
-                any_segwit = true;
-            }
-            // P2TR key-path: stack = [sig]
-            // Typical sizes: 1 (items) + 1 (sig len) + 64 (sig) = 66 bytes.
-            [0x51, 0x20, ..] if input.script_pubkey.len() == 34 => {
-                total += P2TR_WITNESS_TYPICAL;
-                any_segwit = true;
-            }
-            _ => {}
-        }
-    }
-
-    if any_segwit {
-        // SegWit marker + flag (weight 1 each, counted in witness section for weight calc).
-        total += 2;
+fn witness_size(inputs: &[TxInput]) -> Result<usize, FeeError> {
+    let sizes = inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| input_witness_size(index, input))
+        .collect::<Result<Vec<_>, _>>()?;
+    if sizes.iter().all(|&size| size == 0) {
+        return Ok(0);
     }
 
-    total
+    // SegWit marker + flag (weight 1 each, counted in witness section for weight calc),
+    // and an item count for every input, `0x00` for those without a witness.
+    Ok(2 + sizes.iter().map(|&size| size.max(1)).sum::<usize>())
 }
 
 fn pushdata_prefix_len(len: usize) -> usize {
@@ -133,6 +393,7 @@ fn pushdata_prefix_len(len: usize) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::{string::ToString, vec};
 
     fn sample_input_p2wpkh() -> TxInput {
         let mut spk = vec![0x00, 0x14];
@@ -143,6 +404,9 @@ mod tests {
             script_pubkey: spk,
             amount: 100_000,
             sequence: crate::tx::DEFAULT_SEQUENCE,
+            witness_hint: None,
+            redeem_script: None,
+            psbt: Default::default(),
         }
     }
 
@@ -159,10 +423,153 @@ mod tests {
     fn estimates_vsize_with_op_return() {
         let inputs = vec![sample_input_p2wpkh()];
         let outputs = vec![sample_output()];
-        let vsize = calculate_vsize_with_nonce_len(&inputs, &outputs, 8);
+        let vsize = calculate_vsize_with_nonce_len(&inputs, &outputs, 8).unwrap();
         assert_eq!(vsize, 129);
     }
 
+    #[test]
+    fn fee_rate_rounds_fees_up_like_bitcoin_core() {
+        let rate: FeeRate = "1.5".parse().unwrap();
+        assert_eq!(rate.to_sat_per_kvb(), 1_500);
+        assert_eq!(rate.fee_for_vsize(141), 212); // 211.5 rounds up
+        assert_eq!(rate.fee_for_vsize(0), 0);
+        assert_eq!(FeeRate::from_sat_per_kvb(1).fee_for_vsize(1), 1);
+
+        let whole = FeeRate::from_sat_per_vb(2).unwrap();
+        assert_eq!(whole.fee_for_vsize(129), calculate_fee(129, 2));
+        assert_eq!(
+            FeeRate::from_fee_and_vsize(212, 141).to_sat_per_kvb(),
+            1_503
+        );
+        assert_eq!(FeeRate::from_sat_per_vb(u64::MAX), None);
+        assert_eq!(rate.to_string(), "1.500 sat/vB");
+    }
+
+    #[test]
+    fn parses_decimal_sat_per_vbyte() {
+        assert_eq!("2".parse(), Ok(FeeRate::from_sat_per_kvb(2_000)));
+        assert_eq!("0.123".parse(), Ok(FeeRate::from_sat_per_kvb(123)));
+        assert_eq!("1.2500".parse(), Ok(FeeRate::from_sat_per_kvb(1_250)));
+        assert_eq!("1.2345".parse::<FeeRate>(), Err(FeeRateError::TooPrecise));
+        for bad in ["", ".5", "1.", "-1", "1,5", "1.5e3"] {
+            assert_eq!(bad.parse::<FeeRate>(), Err(FeeRateError::Invalid), "{bad}");
+        }
+        assert_eq!(
+            "18446744073709552".parse::<FeeRate>(),
+            Err(FeeRateError::Overflow)
+        );
+    }
+
+    #[test]
+    fn splits_subtracted_fees_like_bitcoin_core() {
+        let amounts = [10_000, 30_000, 5_000];
+        let even = FeeMode::SubtractFromOutputs(vec![0, 1]);
+        assert_eq!(
+            even.fee_shares(301, &amounts, &[0, 1]),
+            vec![(0, 151), (1, 150)]
+        );
+
+        let shares = FeeMode::SubtractProportionally.fee_shares(1_001, &amounts, &[0, 1, 2]);
+        assert_eq!(shares, vec![(0, 223), (1, 667), (2, 111)]);
+        assert_eq!(shares.iter().map(|&(_, s)| s).sum::<u64>(), 1_001);
+
+        assert!(FeeMode::Change.fee_shares(500, &amounts, &[0]).is_empty());
+        assert_eq!(Fee::Absolute(700).amount_for_vsize(10_000), 700);
+    }
+
+    #[test]
+    fn sizes_witnesses_from_hints() {
+        let mut p2wsh = sample_input_p2wpkh();
+        p2wsh.script_pubkey = vec![0x00, 0x20];
+        p2wsh.script_pubkey.extend([0x44; 32]);
+        assert_eq!(
+            input_witness_size(3, &p2wsh),
+            Err(FeeError::UnknownWitnessSize { index: 3 })
+        );
+
+        // 2-of-3: item count, dummy, two signatures, 105-byte witness script.
+        p2wsh.witness_hint = Some(WitnessHint::Multisig {
+            required: 2,
+            keys: 3,
+        });
+        assert_eq!(input_witness_size(0, &p2wsh), Ok(1 + 1 + 2 * 73 + 1 + 105));
+        p2wsh.witness_hint = Some(WitnessHint::Multisig {
+            required: 4,
+            keys: 3,
+        });
+        assert_eq!(
+            input_witness_size(0, &p2wsh),
+            Err(FeeError::InvalidWitnessHint { index: 0 })
+        );
+
+        // One signature, 34-byte leaf, control block with one merkle step.
+        p2wsh.witness_hint = Some(WitnessHint::TapScript {
+            signatures: 1,
+            script_len: 34,
+            depth: 1,
+        });
+        assert_eq!(input_witness_size(0, &p2wsh), Ok(1 + 65 + 1 + 34 + 1 + 65));
+
+        // An explicit size overrides the scriptPubKey default.
+        let mut hinted = sample_input_p2wpkh();
+        hinted.witness_hint = Some(WitnessHint::Size(200));
+        let outputs = vec![sample_output()];
+        let default_weight = calculate_weight(&[sample_input_p2wpkh()], &outputs, 1).unwrap();
+        assert_eq!(
+            calculate_weight(&[hinted], &outputs, 1).unwrap(),
+            default_weight - P2WPKH_WITNESS_TYPICAL + 200
+        );
+    }
+
+    #[test]
+    fn rejects_inputs_that_are_not_segwit() {
+        // P2PKH is signed in the scriptSig, which is part of the txid.
+        let mut p2pkh = sample_input_p2wpkh();
+        p2pkh.script_pubkey = vec![0x76, 0xa9, 0x14];
+        p2pkh.script_pubkey.extend([0x11; 20]);
+        p2pkh.script_pubkey.extend([0x88, 0xac]);
+        assert_eq!(
+            input_witness_size(1, &p2pkh),
+            Err(FeeError::NotSegwit { index: 1 })
+        );
+        // A witness hint does not make it SegWit.
+        p2pkh.witness_hint = Some(WitnessHint::Size(0));
+        let outputs = vec![sample_output()];
+        assert_eq!(
+            calculate_weight(&[sample_input_p2wpkh(), p2pkh], &outputs, 1),
+            Err(FeeError::NotSegwit { index: 1 })
+        );
+
+        // Neither is P2SH without the witness program it wraps.
+        let mut p2sh = sample_input_p2wpkh();
+        p2sh.script_pubkey = vec![0xa9, 0x14];
+        p2sh.script_pubkey.extend([0x55; 20]);
+        p2sh.script_pubkey.push(0x87);
+        p2sh.witness_hint = Some(WitnessHint::Size(108));
+        assert_eq!(
+            input_witness_size(0, &p2sh),
+            Err(FeeError::NotSegwit { index: 0 })
+        );
+    }
+
+    #[test]
+    fn nested_segwit_adds_script_sig_to_base_size() {
+        let native = sample_input_p2wpkh();
+        let mut nested = native.clone();
+        nested.script_pubkey = vec![0xa9, 0x14];
+        nested.script_pubkey.extend([0x55; 20]);
+        nested.script_pubkey.push(0x87);
+        nested.redeem_script = Some(native.script_pubkey.clone());
+
+        // The witness follows the wrapped program; the 23-byte scriptSig is base data.
+        assert_eq!(input_witness_size(0, &nested), Ok(P2WPKH_WITNESS_TYPICAL));
+        let outputs = vec![sample_output()];
+        assert_eq!(
+            calculate_weight(&[nested], &outputs, 1).unwrap(),
+            calculate_weight(&[native], &outputs, 1).unwrap() + 23 * 4
+        );
+    }
+
     #[test]
     fn detects_insufficient_funds() {
         let err = calculate_change(10_000, 9_500, 600, 330).unwrap_err();
@@ -188,8 +595,8 @@ mod tests {
         let inputs = vec![sample_input_p2wpkh()];
         let outputs = vec![sample_output()];
 
-        let v_fixed = calculate_vsize_with_nonce_len(&inputs, &outputs, 8);
-        let v_manual = calculate_vsize(&inputs, &outputs, 8);
+        let v_fixed = calculate_vsize_with_nonce_len(&inputs, &outputs, 8).unwrap();
+        let v_manual = calculate_vsize(&inputs, &outputs, 8).unwrap();
         assert_eq!(v_fixed, v_manual);
     }
 
@@ -199,7 +606,7 @@ mod tests {
         let outputs = vec![sample_output()];
         let op_return_size = 4; // small OP_RETURN payload (e.g., nonce bytes)
 
-        let vsize = calculate_vsize(&inputs, &outputs, op_return_size);
+        let vsize = calculate_vsize(&inputs, &outputs, op_return_size).unwrap();
 
         // Manual weight calculation for regression:
         // base size (non-witness) + witness size / 4 (rounded up).
@@ -236,7 +643,7 @@ mod tests {
         let outputs = vec![sample_output()];
         let op_return_size = 1;
 
-        let vsize = calculate_vsize(&inputs, &outputs, op_return_size);
+        let vsize = calculate_vsize(&inputs, &outputs, op_return_size).unwrap();
 
         let mut base = 4;
         base += encode_varint(inputs.len() as u64).len();
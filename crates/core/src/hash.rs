@@ -1,4 +1,12 @@
-use sha2::{Digest, Sha256};
+use alloc::vec::Vec;
+
+use ripemd::Ripemd160;
+use sha2::{digest::generic_array::GenericArray, Digest, Sha256};
+
+pub(crate) const SHA256_BLOCK_LEN: usize = 64;
+pub(crate) const SHA256_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
 
 /// Compute SHA256(SHA256(data)).
 pub fn double_sha256(data: &[u8]) -> [u8; 32] {
@@ -9,56 +17,168 @@ pub fn double_sha256(data: &[u8]) -> [u8; 32] {
     out
 }
 
-/// Count leading hex zeros of a txid-style hash.
-/// Txids are displayed reversed, so we count from the end of the hash bytes.
-pub fn count_leading_zeros(hash: &[u8; 32]) -> u8 {
-    let mut zeros = 0u8;
-    for &byte in hash.iter().rev() {
-        if byte == 0 {
-            zeros = zeros.saturating_add(2);
-            continue;
-        }
+/// Compute RIPEMD160(SHA256(data)), the hash P2SH and P2WPKH scripts commit to.
+pub(crate) fn hash160(data: &[u8]) -> [u8; 20] {
+    let mut out = [0u8; 20];
+    out.copy_from_slice(&Ripemd160::digest(Sha256::digest(data)));
+    out
+}
 
-        if byte >> 4 == 0 {
-            zeros = zeros.saturating_add(1);
+/// Double-SHA256 for `prefix || nonce || suffix` messages where only the nonce changes.
+///
+/// The SHA-256 state after every full 64-byte block of the prefix is computed once
+/// at construction; each [`MidstateHasher::hash`] call only compresses the blocks
+/// holding the tail of the prefix, the nonce, the suffix and the padding. Message
+/// length is fixed by `nonce_len`, so padding is also precomputed and hashing does
+/// not allocate.
+#[derive(Debug, Clone)]
+pub struct MidstateHasher {
+    midstate: [u32; 8],
+    /// Unabsorbed prefix bytes, nonce slot, suffix and SHA-256 padding.
+    tail: Vec<u8>,
+    nonce_offset: usize,
+    nonce_len: usize,
+}
+
+impl MidstateHasher {
+    /// Precompute the midstate for a template with a nonce of `nonce_len` bytes.
+    pub fn new(prefix: &[u8], suffix: &[u8], nonce_len: usize) -> Self {
+        let (midstate, tail, nonce_offset) = midstate_and_tail(prefix, suffix, nonce_len);
+        Self {
+            midstate,
+            tail,
+            nonce_offset,
+            nonce_len,
         }
-        break;
     }
-    zeros
+
+    /// Nonce width (in bytes) this hasher was built for.
+    pub fn nonce_len(&self) -> usize {
+        self.nonce_len
+    }
+
+    /// Number of 64-byte blocks compressed for every nonce.
+    pub fn blocks_per_nonce(&self) -> usize {
+        self.tail.len() / SHA256_BLOCK_LEN
+    }
+
+    /// Compute SHA256(SHA256(prefix || nonce || suffix)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `nonce.len()` differs from the length given at construction.
+    pub fn hash(&mut self, nonce: &[u8]) -> [u8; 32] {
+        self.tail[self.nonce_offset..self.nonce_offset + self.nonce_len].copy_from_slice(nonce);
+
+        let mut state = self.midstate;
+        compress_blocks(&mut state, &self.tail);
+        finish_double_sha256(state)
+    }
 }
 
-/// Quick check that a hash meets a target number of leading zeros (txid view).
-pub fn hash_meets_target(hash: &[u8; 32], target_zeros: u8) -> bool {
-    if target_zeros == 0 {
-        return true;
+/// Absorb the full blocks of `prefix` and lay out the padded remainder of the
+/// message, returning `(midstate, tail, nonce_offset)`.
+pub(crate) fn midstate_and_tail(
+    prefix: &[u8],
+    suffix: &[u8],
+    nonce_len: usize,
+) -> ([u32; 8], Vec<u8>, usize) {
+    let absorbed = prefix.len() - prefix.len() % SHA256_BLOCK_LEN;
+    let mut midstate = SHA256_IV;
+    compress_blocks(&mut midstate, &prefix[..absorbed]);
+
+    let msg_len = prefix.len() + nonce_len + suffix.len();
+    let tail_len = msg_len - absorbed;
+    let padded_len = (tail_len + 9).div_ceil(SHA256_BLOCK_LEN) * SHA256_BLOCK_LEN;
+
+    let mut tail = Vec::with_capacity(padded_len);
+    tail.extend_from_slice(&prefix[absorbed..]);
+    let nonce_offset = tail.len();
+    tail.resize(nonce_offset + nonce_len, 0);
+    tail.extend_from_slice(suffix);
+    tail.push(0x80);
+    tail.resize(padded_len - 8, 0);
+    tail.extend_from_slice(&((msg_len as u64) * 8).to_be_bytes());
+
+    (midstate, tail, nonce_offset)
+}
+
+/// Hash the 32-byte output of the first SHA-256 pass and serialize the digest.
+pub(crate) fn finish_double_sha256(first: [u32; 8]) -> [u8; 32] {
+    // A 32-byte message always fits a single padded block.
+    let mut block = [0u8; SHA256_BLOCK_LEN];
+    for (chunk, word) in block[..32].chunks_exact_mut(4).zip(first.iter()) {
+        chunk.copy_from_slice(&word.to_be_bytes());
     }
-    if target_zeros > 64 {
-        return false;
+    block[32] = 0x80;
+    block[62] = 0x01; // 256-bit message length
+
+    let mut outer = SHA256_IV;
+    compress_blocks(&mut outer, &block);
+
+    let mut out = [0u8; 32];
+    for (chunk, word) in out.chunks_exact_mut(4).zip(outer.iter()) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+pub(crate) fn compress_blocks(state: &mut [u32; 8], data: &[u8]) {
+    debug_assert_eq!(data.len() % SHA256_BLOCK_LEN, 0);
+    for block in data.chunks_exact(SHA256_BLOCK_LEN) {
+        sha2::compress256(
+            state,
+            core::slice::from_ref(GenericArray::from_slice(block)),
+        );
     }
+}
 
-    let full_zero_bytes = (target_zeros / 2) as usize;
-    let needs_half_nibble = target_zeros % 2 == 1;
+/// Count leading zero bits of a txid-style hash.
+/// Txids are displayed reversed, so we count from the end of the hash bytes.
+pub fn count_leading_zero_bits(hash: &[u8; 32]) -> u16 {
+    let mut bits = 0u16;
+    for &byte in hash.iter().rev() {
+        bits += byte.leading_zeros() as u16;
+        if byte != 0 {
+            break;
+        }
+    }
+    bits
+}
 
-    // Check required full zero bytes from the end.
+/// Count leading hex zeros of a txid-style hash.
+pub fn count_leading_zeros(hash: &[u8; 32]) -> u8 {
+    (count_leading_zero_bits(hash) / 4) as u8
+}
+
+/// Quick check that a hash meets a target number of leading zero bits (txid view).
+pub fn hash_meets_target_bits(hash: &[u8; 32], target_bits: u16) -> bool {
+    if target_bits > 256 {
+        return false;
+    }
+
+    let full_zero_bytes = (target_bits / 8) as usize;
     if hash.iter().rev().take(full_zero_bytes).any(|b| *b != 0) {
         return false;
     }
 
-    if needs_half_nibble {
-        let idx = match hash.len().checked_sub(full_zero_bytes + 1) {
-            Some(idx) => idx,
-            None => return false,
-        };
-        let byte = hash[idx];
-        return byte >> 4 == 0;
+    match target_bits % 8 {
+        0 => true,
+        partial => hash[31 - full_zero_bytes] >> (8 - partial) == 0,
     }
+}
 
-    true
+/// Quick check that a hash meets a target number of leading zeros (txid view).
+pub fn hash_meets_target(hash: &[u8; 32], target_zeros: u8) -> bool {
+    target_zeros <= 64 && hash_meets_target_bits(hash, target_zeros as u16 * 4)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{count_leading_zeros, double_sha256, hash_meets_target};
+    use super::{
+        count_leading_zero_bits, count_leading_zeros, double_sha256, hash_meets_target,
+        hash_meets_target_bits, MidstateHasher,
+    };
 
     #[test]
     fn double_hash_matches_genesis_block_header() {
@@ -116,4 +236,59 @@ mod tests {
         assert!(hash_meets_target(&hash, 6));
         assert!(!hash_meets_target(&hash, 7));
     }
+
+    #[test]
+    fn counts_and_checks_leading_zero_bits() {
+        let mut hash = [0xffu8; 32];
+        assert_eq!(count_leading_zero_bits(&hash), 0);
+        assert!(hash_meets_target_bits(&hash, 0));
+        assert!(!hash_meets_target_bits(&hash, 1));
+
+        hash[31] = 0x00;
+        hash[30] = 0x1f; // 8 + 3 zero bits, i.e. 2 hex zeros
+        assert_eq!(count_leading_zero_bits(&hash), 11);
+        assert_eq!(count_leading_zeros(&hash), 2);
+        assert!(hash_meets_target_bits(&hash, 11));
+        assert!(!hash_meets_target_bits(&hash, 12));
+
+        hash.fill(0);
+        assert_eq!(count_leading_zero_bits(&hash), 256);
+        assert!(hash_meets_target_bits(&hash, 256));
+        assert!(!hash_meets_target_bits(&hash, 257));
+    }
+
+    #[test]
+    fn midstate_hasher_matches_double_sha256() {
+        // Cover prefixes shorter than, equal to and longer than a block, and
+        // tails that straddle the 55/56-byte padding boundary.
+        for prefix_len in [0usize, 1, 55, 63, 64, 65, 127, 128, 200] {
+            for suffix_len in [0usize, 4, 47, 60, 120] {
+                for nonce_len in [1usize, 3, 9] {
+                    let prefix: Vec<u8> = (0..prefix_len).map(|i| i as u8).collect();
+                    let suffix: Vec<u8> = (0..suffix_len).map(|i| (i * 7) as u8).collect();
+                    let mut hasher = MidstateHasher::new(&prefix, &suffix, nonce_len);
+
+                    for fill in [0x00u8, 0x5a, 0xff] {
+                        let nonce = vec![fill; nonce_len];
+                        let mut full = prefix.clone();
+                        full.extend_from_slice(&nonce);
+                        full.extend_from_slice(&suffix);
+                        assert_eq!(
+                            hasher.hash(&nonce),
+                            double_sha256(&full),
+                            "prefix={prefix_len} suffix={suffix_len} nonce={nonce_len}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn midstate_hasher_skips_constant_prefix_blocks() {
+        let prefix = [0x11u8; 300];
+        let hasher = MidstateHasher::new(&prefix, &[0u8; 4], 2);
+        // 300 - 256 = 44 tail bytes + 2 nonce + 4 suffix + 9 padding -> one block.
+        assert_eq!(hasher.blocks_per_nonce(), 1);
+    }
 }
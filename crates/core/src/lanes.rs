@@ -0,0 +1,634 @@
+//! Multi-lane double-SHA256 for mining loops.
+//!
+//! [`LaneHasher`] hashes several `prefix || nonce || suffix` candidates per call.
+//! Like [`MidstateHasher`](crate::hash::MidstateHasher) it absorbs the constant
+//! leading blocks of the prefix once; the remaining blocks are then compressed
+//! for 4 or 8 nonces side by side in SIMD lanes, or one at a time through the
+//! SHA extensions on CPUs that have them. The backend is chosen at runtime with
+//! [`HashBackend::detect`].
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::hash::{compress_blocks, finish_double_sha256, midstate_and_tail, SHA256_IV};
+
+#[cfg(all(target_arch = "x86_64", feature = "std"))]
+macro_rules! x86_feature {
+    ($($feature:tt),+) => {
+        $(std::arch::is_x86_feature_detected!($feature))&&+
+    };
+}
+
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+macro_rules! x86_feature {
+    ($($feature:tt),+) => {
+        cfg!(all($(target_feature = $feature),+))
+    };
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+macro_rules! x86_feature {
+    ($($feature:tt),+) => {
+        false
+    };
+}
+
+/// Largest lane count of any backend; sizes the output buffer of [`LaneHasher::hash_lanes`].
+pub const MAX_LANES: usize = 8;
+
+/// Double-SHA256 implementation used by [`LaneHasher`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashBackend {
+    /// One candidate at a time through `sha2`'s compression function.
+    Scalar,
+    /// Same single-lane path as [`HashBackend::Scalar`], which `sha2` dispatches to
+    /// the x86 SHA extensions (SHA-NI).
+    ShaNi,
+    /// Four lanes of plain `u32` arithmetic, left to the compiler to vectorize.
+    Portable,
+    /// Four lanes in SSE2 registers (x86_64).
+    Sse2,
+    /// Eight lanes in AVX2 registers (x86_64).
+    Avx2,
+    /// Four lanes in NEON registers (aarch64).
+    Neon,
+}
+
+impl HashBackend {
+    /// Every backend, supported on this CPU or not.
+    pub const ALL: [HashBackend; 6] = [
+        HashBackend::Scalar,
+        HashBackend::ShaNi,
+        HashBackend::Portable,
+        HashBackend::Sse2,
+        HashBackend::Avx2,
+        HashBackend::Neon,
+    ];
+
+    /// Fastest backend supported by the running CPU.
+    pub fn detect() -> Self {
+        [
+            HashBackend::ShaNi,
+            HashBackend::Avx2,
+            HashBackend::Neon,
+            HashBackend::Sse2,
+            HashBackend::Portable,
+        ]
+        .into_iter()
+        .find(|backend| backend.is_supported())
+        .unwrap_or(HashBackend::Scalar)
+    }
+
+    /// Whether the running CPU can execute this backend.
+    pub fn is_supported(self) -> bool {
+        match self {
+            HashBackend::Scalar | HashBackend::Portable => true,
+            HashBackend::ShaNi => x86_feature!("sha", "sse2", "ssse3", "sse4.1"),
+            HashBackend::Sse2 => x86_feature!("sse2"),
+            HashBackend::Avx2 => x86_feature!("avx2"),
+            HashBackend::Neon => cfg!(all(target_arch = "aarch64", target_feature = "neon")),
+        }
+    }
+
+    /// Number of candidates hashed per [`LaneHasher::hash_lanes`] call.
+    pub fn lanes(self) -> usize {
+        match self {
+            HashBackend::Scalar | HashBackend::ShaNi => 1,
+            HashBackend::Portable | HashBackend::Sse2 | HashBackend::Neon => 4,
+            HashBackend::Avx2 => 8,
+        }
+    }
+
+    /// Short lowercase name, suitable for logs.
+    pub fn name(self) -> &'static str {
+        match self {
+            HashBackend::Scalar => "scalar",
+            HashBackend::ShaNi => "sha-ni",
+            HashBackend::Portable => "portable",
+            HashBackend::Sse2 => "sse2",
+            HashBackend::Avx2 => "avx2",
+            HashBackend::Neon => "neon",
+        }
+    }
+}
+
+/// Double-SHA256 of up to [`MAX_LANES`] `prefix || nonce || suffix` candidates at once.
+///
+/// Write each lane's nonce with [`LaneHasher::set_nonce`], then call
+/// [`LaneHasher::hash_lanes`]; lanes keep their nonce between calls.
+#[derive(Debug, Clone)]
+pub struct LaneHasher {
+    backend: HashBackend,
+    midstate: [u32; 8],
+    /// One padded tail per lane, `tail_len` bytes each.
+    tails: Vec<u8>,
+    /// Big-endian words of the tail shared by every lane (nonce slot zeroed).
+    template: Vec<u32>,
+    /// Word indices of the tail that overlap the nonce and differ per lane.
+    nonce_words: Range<usize>,
+    tail_len: usize,
+    nonce_offset: usize,
+    nonce_len: usize,
+}
+
+impl LaneHasher {
+    /// Build a hasher on the backend returned by [`HashBackend::detect`].
+    pub fn new(prefix: &[u8], suffix: &[u8], nonce_len: usize) -> Self {
+        Self::with_backend(prefix, suffix, nonce_len, HashBackend::detect())
+    }
+
+    /// Build a hasher on a specific backend, falling back to
+    /// [`HashBackend::detect`] when the CPU does not support it.
+    pub fn with_backend(
+        prefix: &[u8],
+        suffix: &[u8],
+        nonce_len: usize,
+        backend: HashBackend,
+    ) -> Self {
+        let backend = if backend.is_supported() {
+            backend
+        } else {
+            HashBackend::detect()
+        };
+        let (midstate, tail, nonce_offset) = midstate_and_tail(prefix, suffix, nonce_len);
+        let tail_len = tail.len();
+        let template = tail
+            .chunks_exact(4)
+            .map(|word| u32::from_be_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+        let nonce_words = nonce_offset / 4..(nonce_offset + nonce_len).div_ceil(4);
+        let tails = tail.repeat(backend.lanes());
+
+        Self {
+            backend,
+            midstate,
+            tails,
+            template,
+            nonce_words,
+            tail_len,
+            nonce_offset,
+            nonce_len,
+        }
+    }
+
+    /// Backend this hasher runs on.
+    pub fn backend(&self) -> HashBackend {
+        self.backend
+    }
+
+    /// Number of candidates hashed per [`LaneHasher::hash_lanes`] call.
+    pub fn lanes(&self) -> usize {
+        self.backend.lanes()
+    }
+
+    /// Nonce width (in bytes) this hasher was built for.
+    pub fn nonce_len(&self) -> usize {
+        self.nonce_len
+    }
+
+    /// Write the nonce bytes hashed by `lane`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lane >= self.lanes()` or `nonce.len()` differs from the length
+    /// given at construction.
+    pub fn set_nonce(&mut self, lane: usize, nonce: &[u8]) {
+        assert!(lane < self.lanes(), "lane out of range");
+        let start = lane * self.tail_len + self.nonce_offset;
+        self.tails[start..start + self.nonce_len].copy_from_slice(nonce);
+    }
+
+    /// Compute SHA256(SHA256(prefix || nonce || suffix)) for every lane.
+    ///
+    /// Only the first [`LaneHasher::lanes`] entries of `out` are written.
+    pub fn hash_lanes(&self, out: &mut [[u8; 32]; MAX_LANES]) {
+        match self.backend {
+            HashBackend::Scalar | HashBackend::ShaNi => {
+                let mut state = self.midstate;
+                compress_blocks(&mut state, &self.tails);
+                out[0] = finish_double_sha256(state);
+            }
+            // SAFETY: `Portable` only uses plain integer arithmetic.
+            HashBackend::Portable => unsafe { double_sha256_lanes::<portable::U32x4>(self, out) },
+            // SAFETY: SSE2 is part of the x86_64 baseline.
+            #[cfg(target_arch = "x86_64")]
+            HashBackend::Sse2 => unsafe { double_sha256_lanes::<x86::Sse2>(self, out) },
+            // SAFETY: `with_backend` only keeps `Avx2` when the CPU reports AVX2.
+            #[cfg(target_arch = "x86_64")]
+            HashBackend::Avx2 => unsafe { x86::double_sha256_avx2(self, out) },
+            // SAFETY: NEON is enabled at compile time for this target.
+            #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+            HashBackend::Neon => unsafe { double_sha256_lanes::<neon::U32x4>(self, out) },
+            #[allow(unreachable_patterns)]
+            _ => unreachable!("backend not supported on this target"),
+        }
+    }
+}
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// One `u32` per lane, packed in a SIMD register (or an array for `Portable`).
+///
+/// Methods are `unsafe` because SIMD implementations require their target
+/// feature to be available.
+trait Lanes: Copy {
+    const LANES: usize;
+
+    unsafe fn splat(value: u32) -> Self;
+    unsafe fn load(words: &[u32; MAX_LANES]) -> Self;
+    unsafe fn store(self, words: &mut [u32; MAX_LANES]);
+    unsafe fn add(self, other: Self) -> Self;
+    unsafe fn xor(self, other: Self) -> Self;
+    unsafe fn and(self, other: Self) -> Self;
+    unsafe fn or(self, other: Self) -> Self;
+    unsafe fn shr<const N: i32>(self) -> Self;
+    unsafe fn shl<const N: i32>(self) -> Self;
+}
+
+macro_rules! rotr {
+    ($x:expr, $n:literal) => {
+        $x.shr::<$n>().or($x.shl::<{ 32 - $n }>())
+    };
+}
+
+#[inline(always)]
+unsafe fn compress<V: Lanes>(state: &mut [V; 8], w: &mut [V; 16]) {
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+
+    macro_rules! round {
+        ($k:expr, $w:expr) => {{
+            let big_s1 = rotr!(e, 6).xor(rotr!(e, 11)).xor(rotr!(e, 25));
+            let ch = g.xor(e.and(f.xor(g)));
+            let t1 = h.add(big_s1).add(ch).add(V::splat($k)).add($w);
+            let big_s0 = rotr!(a, 2).xor(rotr!(a, 13)).xor(rotr!(a, 22));
+            let maj = a.and(b).or(c.and(a.or(b)));
+            let t2 = big_s0.add(maj);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.add(t2);
+        }};
+    }
+
+    for i in 0..16 {
+        round!(K[i], w[i]);
+    }
+    for i in 16..64 {
+        let w15 = w[(i + 1) & 15];
+        let w2 = w[(i + 14) & 15];
+        let s0 = rotr!(w15, 7).xor(rotr!(w15, 18)).xor(w15.shr::<3>());
+        let s1 = rotr!(w2, 17).xor(rotr!(w2, 19)).xor(w2.shr::<10>());
+        let next = w[i & 15].add(s0).add(w[(i + 9) & 15]).add(s1);
+        w[i & 15] = next;
+        round!(K[i], next);
+    }
+
+    for (slot, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *slot = slot.add(value);
+    }
+}
+
+#[inline(always)]
+unsafe fn double_sha256_lanes<V: Lanes>(hasher: &LaneHasher, out: &mut [[u8; 32]; MAX_LANES]) {
+    let tails = &hasher.tails;
+    let tail_len = hasher.tail_len;
+    let mut words = [0u32; MAX_LANES];
+    let mut state = [V::splat(0); 8];
+    for (slot, word) in state.iter_mut().zip(hasher.midstate) {
+        *slot = V::splat(word);
+    }
+
+    for (block, template) in hasher.template.chunks_exact(16).enumerate() {
+        let mut w = [V::splat(0); 16];
+        for (j, slot) in w.iter_mut().enumerate() {
+            let index = block * 16 + j;
+            if !hasher.nonce_words.contains(&index) {
+                *slot = V::splat(template[j]);
+                continue;
+            }
+            for (lane, word) in words.iter_mut().take(V::LANES).enumerate() {
+                let at = lane * tail_len + 4 * index;
+                *word =
+                    u32::from_be_bytes([tails[at], tails[at + 1], tails[at + 2], tails[at + 3]]);
+            }
+            *slot = V::load(&words);
+        }
+        compress(&mut state, &mut w);
+    }
+
+    // Second pass over the 32-byte digest: one block with fixed padding.
+    let mut w = [V::splat(0); 16];
+    w[..8].copy_from_slice(&state);
+    w[8] = V::splat(0x8000_0000);
+    w[15] = V::splat(256);
+    let mut outer = [V::splat(0); 8];
+    for (slot, word) in outer.iter_mut().zip(SHA256_IV) {
+        *slot = V::splat(word);
+    }
+    compress(&mut outer, &mut w);
+
+    for (idx, value) in outer.iter().enumerate() {
+        value.store(&mut words);
+        for (lane, word) in words.iter().take(V::LANES).enumerate() {
+            out[lane][4 * idx..4 * idx + 4].copy_from_slice(&word.to_be_bytes());
+        }
+    }
+}
+
+mod portable {
+    use super::{Lanes, MAX_LANES};
+
+    #[derive(Clone, Copy)]
+    pub(super) struct U32x4([u32; 4]);
+
+    impl U32x4 {
+        #[inline(always)]
+        fn map2(self, other: Self, op: impl Fn(u32, u32) -> u32) -> Self {
+            let mut out = [0u32; 4];
+            for (slot, (a, b)) in out.iter_mut().zip(self.0.into_iter().zip(other.0)) {
+                *slot = op(a, b);
+            }
+            U32x4(out)
+        }
+    }
+
+    impl Lanes for U32x4 {
+        const LANES: usize = 4;
+
+        #[inline(always)]
+        unsafe fn splat(value: u32) -> Self {
+            U32x4([value; 4])
+        }
+        #[inline(always)]
+        unsafe fn load(words: &[u32; MAX_LANES]) -> Self {
+            U32x4([words[0], words[1], words[2], words[3]])
+        }
+        #[inline(always)]
+        unsafe fn store(self, words: &mut [u32; MAX_LANES]) {
+            words[..4].copy_from_slice(&self.0);
+        }
+        #[inline(always)]
+        unsafe fn add(self, other: Self) -> Self {
+            self.map2(other, u32::wrapping_add)
+        }
+        #[inline(always)]
+        unsafe fn xor(self, other: Self) -> Self {
+            self.map2(other, |a, b| a ^ b)
+        }
+        #[inline(always)]
+        unsafe fn and(self, other: Self) -> Self {
+            self.map2(other, |a, b| a & b)
+        }
+        #[inline(always)]
+        unsafe fn or(self, other: Self) -> Self {
+            self.map2(other, |a, b| a | b)
+        }
+        #[inline(always)]
+        unsafe fn shr<const N: i32>(self) -> Self {
+            U32x4(self.0.map(|a| a >> N))
+        }
+        #[inline(always)]
+        unsafe fn shl<const N: i32>(self) -> Self {
+            U32x4(self.0.map(|a| a << N))
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use core::arch::x86_64::*;
+
+    use super::{double_sha256_lanes, LaneHasher, Lanes, MAX_LANES};
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Sse2(__m128i);
+
+    impl Lanes for Sse2 {
+        const LANES: usize = 4;
+
+        #[inline(always)]
+        unsafe fn splat(value: u32) -> Self {
+            Sse2(_mm_set1_epi32(value as i32))
+        }
+        #[inline(always)]
+        unsafe fn load(words: &[u32; MAX_LANES]) -> Self {
+            Sse2(_mm_loadu_si128(words.as_ptr() as *const __m128i))
+        }
+        #[inline(always)]
+        unsafe fn store(self, words: &mut [u32; MAX_LANES]) {
+            _mm_storeu_si128(words.as_mut_ptr() as *mut __m128i, self.0)
+        }
+        #[inline(always)]
+        unsafe fn add(self, other: Self) -> Self {
+            Sse2(_mm_add_epi32(self.0, other.0))
+        }
+        #[inline(always)]
+        unsafe fn xor(self, other: Self) -> Self {
+            Sse2(_mm_xor_si128(self.0, other.0))
+        }
+        #[inline(always)]
+        unsafe fn and(self, other: Self) -> Self {
+            Sse2(_mm_and_si128(self.0, other.0))
+        }
+        #[inline(always)]
+        unsafe fn or(self, other: Self) -> Self {
+            Sse2(_mm_or_si128(self.0, other.0))
+        }
+        #[inline(always)]
+        unsafe fn shr<const N: i32>(self) -> Self {
+            Sse2(_mm_srli_epi32::<N>(self.0))
+        }
+        #[inline(always)]
+        unsafe fn shl<const N: i32>(self) -> Self {
+            Sse2(_mm_slli_epi32::<N>(self.0))
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Avx2(__m256i);
+
+    impl Lanes for Avx2 {
+        const LANES: usize = 8;
+
+        #[inline(always)]
+        unsafe fn splat(value: u32) -> Self {
+            Avx2(_mm256_set1_epi32(value as i32))
+        }
+        #[inline(always)]
+        unsafe fn load(words: &[u32; MAX_LANES]) -> Self {
+            Avx2(_mm256_loadu_si256(words.as_ptr() as *const __m256i))
+        }
+        #[inline(always)]
+        unsafe fn store(self, words: &mut [u32; MAX_LANES]) {
+            _mm256_storeu_si256(words.as_mut_ptr() as *mut __m256i, self.0)
+        }
+        #[inline(always)]
+        unsafe fn add(self, other: Self) -> Self {
+            Avx2(_mm256_add_epi32(self.0, other.0))
+        }
+        #[inline(always)]
+        unsafe fn xor(self, other: Self) -> Self {
+            Avx2(_mm256_xor_si256(self.0, other.0))
+        }
+        #[inline(always)]
+        unsafe fn and(self, other: Self) -> Self {
+            Avx2(_mm256_and_si256(self.0, other.0))
+        }
+        #[inline(always)]
+        unsafe fn or(self, other: Self) -> Self {
+            Avx2(_mm256_or_si256(self.0, other.0))
+        }
+        #[inline(always)]
+        unsafe fn shr<const N: i32>(self) -> Self {
+            Avx2(_mm256_srli_epi32::<N>(self.0))
+        }
+        #[inline(always)]
+        unsafe fn shl<const N: i32>(self) -> Self {
+            Avx2(_mm256_slli_epi32::<N>(self.0))
+        }
+    }
+
+    /// Compiled with AVX2 enabled so the generic lane code inlines the intrinsics.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn double_sha256_avx2(hasher: &LaneHasher, out: &mut [[u8; 32]; MAX_LANES]) {
+        double_sha256_lanes::<Avx2>(hasher, out)
+    }
+}
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+mod neon {
+    use core::arch::aarch64::*;
+
+    use super::{Lanes, MAX_LANES};
+
+    #[derive(Clone, Copy)]
+    pub(super) struct U32x4(uint32x4_t);
+
+    impl Lanes for U32x4 {
+        const LANES: usize = 4;
+
+        #[inline(always)]
+        unsafe fn splat(value: u32) -> Self {
+            U32x4(vdupq_n_u32(value))
+        }
+        #[inline(always)]
+        unsafe fn load(words: &[u32; MAX_LANES]) -> Self {
+            U32x4(vld1q_u32(words.as_ptr()))
+        }
+        #[inline(always)]
+        unsafe fn store(self, words: &mut [u32; MAX_LANES]) {
+            vst1q_u32(words.as_mut_ptr(), self.0)
+        }
+        #[inline(always)]
+        unsafe fn add(self, other: Self) -> Self {
+            U32x4(vaddq_u32(self.0, other.0))
+        }
+        #[inline(always)]
+        unsafe fn xor(self, other: Self) -> Self {
+            U32x4(veorq_u32(self.0, other.0))
+        }
+        #[inline(always)]
+        unsafe fn and(self, other: Self) -> Self {
+            U32x4(vandq_u32(self.0, other.0))
+        }
+        #[inline(always)]
+        unsafe fn or(self, other: Self) -> Self {
+            U32x4(vorrq_u32(self.0, other.0))
+        }
+        #[inline(always)]
+        unsafe fn shr<const N: i32>(self) -> Self {
+            U32x4(vshrq_n_u32::<N>(self.0))
+        }
+        #[inline(always)]
+        unsafe fn shl<const N: i32>(self) -> Self {
+            U32x4(vshlq_n_u32::<N>(self.0))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HashBackend, LaneHasher, MAX_LANES};
+    use crate::hash::double_sha256;
+    use alloc::vec::Vec;
+
+    fn supported_backends() -> Vec<HashBackend> {
+        HashBackend::ALL
+            .into_iter()
+            .filter(|backend| backend.is_supported())
+            .collect()
+    }
+
+    #[test]
+    fn every_supported_backend_matches_double_sha256() {
+        for backend in supported_backends() {
+            for prefix_len in [0usize, 1, 55, 63, 64, 65, 130] {
+                for suffix_len in [0usize, 4, 47, 60, 120] {
+                    for nonce_len in [1usize, 3, 9] {
+                        let prefix: Vec<u8> = (0..prefix_len).map(|i| i as u8).collect();
+                        let suffix: Vec<u8> = (0..suffix_len).map(|i| (i * 7) as u8).collect();
+                        let mut hasher =
+                            LaneHasher::with_backend(&prefix, &suffix, nonce_len, backend);
+                        assert_eq!(hasher.backend(), backend);
+
+                        let nonces: Vec<Vec<u8>> = (0..hasher.lanes())
+                            .map(|lane| vec![(lane as u8).wrapping_mul(37) ^ 0x5a; nonce_len])
+                            .collect();
+                        for (lane, nonce) in nonces.iter().enumerate() {
+                            hasher.set_nonce(lane, nonce);
+                        }
+
+                        let mut out = [[0u8; 32]; MAX_LANES];
+                        hasher.hash_lanes(&mut out);
+
+                        for (lane, nonce) in nonces.iter().enumerate() {
+                            let mut full = prefix.clone();
+                            full.extend_from_slice(nonce);
+                            full.extend_from_slice(&suffix);
+                            assert_eq!(
+                                out[lane],
+                                double_sha256(&full),
+                                "{} lane={lane} prefix={prefix_len} suffix={suffix_len}",
+                                backend.name()
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn detection_picks_a_supported_backend() {
+        let backend = HashBackend::detect();
+        assert!(backend.is_supported());
+        assert!(backend.lanes() <= MAX_LANES);
+        assert!(HashBackend::Scalar.is_supported());
+        assert!(HashBackend::Portable.is_supported());
+    }
+
+    #[test]
+    fn unsupported_backend_falls_back_to_detection() {
+        let unsupported = HashBackend::ALL
+            .into_iter()
+            .find(|backend| !backend.is_supported());
+        if let Some(backend) = unsupported {
+            let hasher = LaneHasher::with_backend(b"prefix", b"suffix", 2, backend);
+            assert_eq!(hasher.backend(), HashBackend::detect());
+        }
+    }
+}
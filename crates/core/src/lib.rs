@@ -5,37 +5,67 @@ pub mod cbor;
 pub mod error;
 pub mod fees;
 pub mod hash;
+pub mod lanes;
 pub mod miner;
 pub mod nonce;
+pub mod pattern;
+pub mod policy;
 pub mod psbt;
+#[cfg(feature = "signer")]
+pub mod sign;
+pub mod stats;
 pub mod tx;
+pub mod verify;
 
 #[allow(unused_extern_crates)]
 extern crate alloc;
 
 pub use address::{
     address_to_script_pubkey, parse_address, parse_address_for_network, AddressError, AddressType,
-    Network, ParsedAddress,
+    Network, ParsedAddress, ANCHOR_PROGRAM, ANCHOR_SCRIPT_PUBKEY,
 };
 pub use cbor::{
     cbor_array_header_length, cbor_array_length, cbor_nonce_byte_length, cbor_uint_length,
-    encode_cbor_array, encode_cbor_array_header, encode_cbor_uint,
+    encode_cbor_array, encode_cbor_array_header, encode_cbor_uint, encode_cbor_uint_into,
 };
 pub use error::{Result, ZeldError};
 pub use fees::{
-    calculate_change, calculate_fee, calculate_vsize, calculate_vsize_with_nonce_len, FeeError,
+    calculate_change, calculate_fee, calculate_tx_vsize, calculate_vsize,
+    calculate_vsize_with_nonce_len, calculate_weight, input_witness_size, Fee, FeeError, FeeMode,
+    FeeRate, FeeRateError,
 };
-pub use hash::{count_leading_zeros, double_sha256, hash_meets_target};
+pub use hash::{
+    count_leading_zero_bits, count_leading_zeros, double_sha256, hash_meets_target,
+    hash_meets_target_bits, MidstateHasher,
+};
+pub use lanes::{HashBackend, LaneHasher, MAX_LANES};
 pub use miner::{
-    build_mining_template, build_psbt_from_plan, mine_batch, mine_batch_with_cbor, mine_range,
-    nonce_len_for_range, plan_transaction, split_nonce_segments, split_nonce_segments_cbor,
-    txid_to_hex, MineResult, MinerError, MinerResult, MiningTemplate, NonceSegment, OutputRequest,
-    TransactionPlan,
+    build_mining_template, build_psbt_from_plan, mine_batch, mine_batch_with_cbor,
+    mine_batch_with_pattern, mine_range, nonce_len_for_range, plan_transaction,
+    plan_transaction_with_fee, plan_transaction_with_fee_rate, plan_transaction_with_options,
+    split_nonce_segments, split_nonce_segments_cbor, txid_to_hex, LockTime, MineResult, MinerError,
+    MinerResult, MiningTemplate, NonceSegment, OutputKind, OutputRequest, PlanOptions,
+    TransactionPlan, LOCKTIME_THRESHOLD,
 };
 pub use nonce::{encode_nonce, nonce_byte_length};
-pub use psbt::{create_psbt, psbt_to_base64, PsbtError};
+pub use pattern::{ByteMask, CompiledPattern, PatternError, TxidPattern, MAX_PATTERN_ALTERNATIVES};
+pub use policy::{is_op_return, is_standard_output_script, Policy, PolicyError};
+pub use psbt::{
+    create_psbt, extract_tx, parse_derivation_path, parse_psbt, psbt_from_base64, psbt_to_base64,
+    Bip32Derivation, KeySource, ParsedPsbt, PsbtError, PsbtInputFields, PsbtOutputFields, PsbtPair,
+    PsbtVersion, TapKeyDerivation, BIP32_HARDENED, PSBT_PROPRIETARY_IDENTIFIER,
+    PSBT_PROPRIETARY_NETWORK,
+};
+#[cfg(feature = "signer")]
+pub use sign::{parse_private_key, sign_plan, sign_psbt, SignedTx, SignerError};
+pub use stats::{
+    expected_attempts, leading_zero_bits_probability, probability_found, HashStats, MiningOdds,
+    ZERO_HISTOGRAM_LEN,
+};
 pub use tx::{
-    create_op_return_script, create_zeld_distribution_op_return, encode_varint,
+    create_op_return_script, create_zeld_distribution_op_return, decode_tx, encode_varint,
     serialize_tx_for_txid, serialize_unsigned_tx, split_tx_for_distribution_mining,
-    split_tx_for_mining, TxError, TxInput, TxOutput,
+    split_tx_for_mining, DecodedInput, DecodedTx, TxError, TxInput, TxOutput, TxParams,
+    WitnessHint,
 };
+pub use verify::{verify_signed_tx, MinedTx, TxMismatch, Verification, VerifyError};
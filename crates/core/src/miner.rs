@@ -3,47 +3,78 @@ use alloc::{format, string::String, vec::Vec};
 use thiserror::Error;
 
 use crate::{
-    address::{address_to_script_pubkey, parse_address_for_network, AddressType, Network},
-    cbor::{cbor_nonce_byte_length, encode_cbor_array_header, encode_cbor_uint},
+    address::{address_to_script_pubkey, parse_address_for_network, Network, ANCHOR_SCRIPT_PUBKEY},
+    cbor::{
+        cbor_nonce_byte_length, encode_cbor_array_header, encode_cbor_uint, encode_cbor_uint_into,
+    },
     error::ZeldError,
-    fees::{calculate_change, calculate_fee, calculate_vsize, FeeError},
-    hash::{double_sha256, hash_meets_target},
+    fees::{calculate_change, calculate_vsize, calculate_weight, Fee, FeeError, FeeMode, FeeRate},
+    hash::double_sha256,
+    lanes::{LaneHasher, MAX_LANES},
     nonce::nonce_byte_length,
-    psbt::{create_psbt, psbt_to_base64, PsbtError},
+    pattern::CompiledPattern,
+    policy::{
+        check_output_script, is_op_return, Policy, PolicyError, MAX_STANDARD_VERSION,
+        TRUC_MAX_VSIZE, TRUC_VERSION,
+    },
+    psbt::{create_psbt, psbt_to_base64, PsbtError, PsbtOutputFields, PsbtPair, PsbtVersion},
     tx::{
         create_op_return_script, split_tx_for_distribution_mining, split_tx_for_mining,
-        zeld_distribution_payload_length_with_nonce, TxError, TxInput, TxOutput, ZELD_PREFIX,
+        zeld_distribution_payload_length_with_nonce, TxError, TxInput, TxOutput, TxParams,
+        ZELD_PREFIX,
     },
 };
 
-fn dust_limit_for_address(address_type: AddressType) -> u64 {
-    match address_type {
-        // Bech32 P2WPKH (bc1q... / tb1q...)
-        AddressType::P2WPKH => 310,
-        // Taproot P2TR (bc1p... / tb1p...)
-        AddressType::P2TR => 330,
-    }
-}
-
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OutputRequest {
+    /// Destination of [`OutputKind::Address`] outputs; ignored for other kinds.
     pub address: String,
     pub amount: Option<u64>,
     pub change: bool,
+    pub kind: OutputKind,
+    /// Key origins written to the PSBT output map, so signers can recognize change.
+    pub psbt: PsbtOutputFields,
+}
+
+/// What an output pays to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum OutputKind {
+    /// Pay to `address`.
+    #[default]
+    Address,
+    /// Pay-to-anchor output a child can spend to bump the fee (CPFP). Without an
+    /// amount it carries the dust threshold; a lower amount, typically zero, is
+    /// ephemeral dust and requires the transaction itself to pay no fee.
+    Anchor,
+    /// Pay to this raw scriptPubKey, which must be a standard output type. OP_RETURN
+    /// scripts count toward the datacarrier limit with the ZELD OP_RETURN and cannot
+    /// be the change or send-max output.
+    Script(Vec<u8>),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TransactionPlan {
     pub inputs: Vec<TxInput>,
-    /// All non-OP_RETURN outputs in the exact order provided by the caller.
+    /// All caller outputs in the exact order provided; the ZELD OP_RETURN is kept
+    /// apart in `op_return_script`.
     /// If change was dust, the change output is excluded.
     pub outputs: Vec<TxOutput>,
+    /// PSBT metadata of each entry of `outputs`, optionally followed by one for the
+    /// OP_RETURN.
+    pub output_fields: Vec<PsbtOutputFields>,
     /// Index of the change output within `outputs`, if present.
     /// `None` if change was dust and omitted.
     pub change_index: Option<usize>,
     pub op_return_script: Vec<u8>,
     pub op_return_size: usize,
     pub distribution: Option<Vec<u64>>,
+    /// Chain the outputs were validated for, recorded in the PSBT.
+    pub network: Network,
+    pub tx_params: TxParams,
+    pub psbt_version: PsbtVersion,
+    /// Extra pairs of the PSBT global map; empty unless the caller adds some, e.g.
+    /// those of an imported PSBT.
+    pub global_pairs: Vec<PsbtPair>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -84,6 +115,12 @@ impl MinerError {
     }
 }
 
+impl From<FeeError> for MinerError {
+    fn from(err: FeeError) -> Self {
+        MinerError::Core(err.into())
+    }
+}
+
 impl From<TxError> for MinerError {
     fn from(err: TxError) -> Self {
         MinerError::Core(err.into())
@@ -96,25 +133,107 @@ impl From<PsbtError> for MinerError {
     }
 }
 
-fn validate_fee_rate(sats_per_vbyte: u64) -> MinerResult<()> {
-    if sats_per_vbyte == 0 {
-        return Err(MinerError::invalid(
-            "sats_per_vbyte must be greater than zero",
-        ));
+impl From<PolicyError> for MinerError {
+    fn from(err: PolicyError) -> Self {
+        MinerError::Core(err.into())
+    }
+}
+
+/// Fee, relay-policy and transaction-field settings for [`plan_transaction_with_options`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanOptions {
+    pub fee: Fee,
+    pub fee_mode: FeeMode,
+    pub policy: Policy,
+    /// Transaction version: 1, 2, or 3 for TRUC (BIP431).
+    pub version: u32,
+    pub lock_time: LockTime,
+    /// Format `build_psbt_from_plan` serializes the plan in.
+    pub psbt_version: PsbtVersion,
+}
+
+impl PlanOptions {
+    /// Pay `fee` from the inputs, leaving the rest as change, under the default policy,
+    /// in a version 2 transaction without a locktime, serialized as a BIP174 PSBT.
+    pub fn new(fee: impl Into<Fee>) -> Self {
+        Self {
+            fee: fee.into(),
+            fee_mode: FeeMode::Change,
+            policy: Policy::default(),
+            version: TxParams::default().version,
+            lock_time: LockTime::None,
+            psbt_version: PsbtVersion::V0,
+        }
     }
-    Ok(())
 }
 
-/// Information about a potential change output.
-struct ChangeInfo {
-    index: usize,
-    address_type: AddressType,
+/// Lowest nLockTime read as a UNIX timestamp rather than a block height.
+pub const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+
+/// nLockTime of a planned transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LockTime {
+    /// 0: valid in any block.
+    #[default]
+    None,
+    /// Not valid before this block height (below [`LOCKTIME_THRESHOLD`]).
+    Height(u32),
+    /// Not valid before this UNIX timestamp (at or above [`LOCKTIME_THRESHOLD`]).
+    Time(u32),
+    /// Bitcoin Core's anti-fee-sniping: lock to the current tip, so miners gain
+    /// nothing by reorging the transaction into an earlier block. Core also backdates
+    /// one transaction in ten by up to 99 blocks at random; plans stay deterministic
+    /// and skip that.
+    AntiFeeSniping { tip_height: u32 },
 }
 
+impl LockTime {
+    /// Read back an nLockTime field value.
+    pub fn from_consensus(value: u32) -> Self {
+        match value {
+            0 => LockTime::None,
+            height if height < LOCKTIME_THRESHOLD => LockTime::Height(height),
+            time => LockTime::Time(time),
+        }
+    }
+
+    /// The nLockTime field value.
+    pub fn to_consensus(self) -> MinerResult<u32> {
+        match self {
+            LockTime::None => Ok(0),
+            LockTime::Height(height) | LockTime::AntiFeeSniping { tip_height: height }
+                if height < LOCKTIME_THRESHOLD =>
+            {
+                Ok(height)
+            }
+            LockTime::Time(time) if time >= LOCKTIME_THRESHOLD => Ok(time),
+            LockTime::Time(_) => Err(MinerError::invalid(format!(
+                "lock time must be at least {LOCKTIME_THRESHOLD}"
+            ))),
+            _ => Err(MinerError::invalid(format!(
+                "lock height must be below {LOCKTIME_THRESHOLD}"
+            ))),
+        }
+    }
+}
+
+fn validate_fee_rate(fee_rate: FeeRate) -> MinerResult<()> {
+    if fee_rate == FeeRate::ZERO {
+        return Err(MinerError::invalid("fee rate must be greater than zero"));
+    }
+    Ok(())
+}
+
+/// Outputs in request order, the change output index if any, and each output's dust
+/// threshold under `policy`.
+///
+/// The `send_max` output may omit its amount; it is filled in once the fee is known.
 fn collect_outputs(
     outputs: &[OutputRequest],
     network: Network,
-) -> MinerResult<(Vec<TxOutput>, Option<ChangeInfo>)> {
+    send_max: Option<usize>,
+    policy: &Policy,
+) -> MinerResult<(Vec<TxOutput>, Option<usize>, Vec<u64>)> {
     if outputs.is_empty() {
         return Err(MinerError::invalid("at least one output is required"));
     }
@@ -125,22 +244,44 @@ fn collect_outputs(
     }
 
     let mut ordered_outputs = Vec::with_capacity(outputs.len());
-    let mut change_info: Option<ChangeInfo> = None;
+    let mut change_index: Option<usize> = None;
+    let mut dust_limits = Vec::with_capacity(outputs.len());
 
     for (idx, output) in outputs.iter().enumerate() {
-        let parsed = parse_address_for_network(&output.address, Some(network))
-            .map_err(|err| MinerError::invalid(format!("outputs[{idx}] address: {err}")))?;
-        let spk = address_to_script_pubkey(&parsed);
-        let dust_limit = dust_limit_for_address(parsed.address_type);
-
-        if output.change {
-            if change_info.is_some() {
-                return Err(MinerError::MultipleChangeOutputs);
+        let spk = match output.kind {
+            OutputKind::Address => {
+                let parsed = parse_address_for_network(&output.address, Some(network))
+                    .map_err(|err| MinerError::invalid(format!("outputs[{idx}] address: {err}")))?;
+                address_to_script_pubkey(&parsed)
+            }
+            OutputKind::Anchor => {
+                if output.change || send_max == Some(idx) {
+                    return Err(MinerError::invalid(format!(
+                        "outputs[{idx}] anchor cannot be the change or send-max output"
+                    )));
+                }
+                ANCHOR_SCRIPT_PUBKEY.to_vec()
+            }
+            OutputKind::Script(ref script) => {
+                check_output_script(idx, script)?;
+                if is_op_return(script) && (output.change || send_max == Some(idx)) {
+                    return Err(MinerError::invalid(format!(
+                        "outputs[{idx}] OP_RETURN cannot be the change or send-max output"
+                    )));
+                }
+                script.clone()
+            }
+        };
+        let dust_limit = policy.dust_threshold(&spk);
+        dust_limits.push(dust_limit);
+
+        if output.change || send_max == Some(idx) {
+            if output.change {
+                if change_index.is_some() {
+                    return Err(MinerError::MultipleChangeOutputs);
+                }
+                change_index = Some(idx);
             }
-            change_info = Some(ChangeInfo {
-                index: idx,
-                address_type: parsed.address_type,
-            });
             // Amount will be filled after fee calculation; keep placeholder.
             ordered_outputs.push(TxOutput {
                 script_pubkey: spk,
@@ -149,15 +290,18 @@ fn collect_outputs(
             continue;
         }
 
-        let amount = output.amount.ok_or_else(|| {
-            MinerError::invalid(format!(
-                "outputs[{idx}] amount is required for non-change outputs"
-            ))
-        })?;
-        if amount < dust_limit {
-            return Err(MinerError::invalid(format!(
-                "outputs[{idx}] amount must be at least {dust_limit} sats"
-            )));
+        let amount = match (output.amount, &output.kind) {
+            (Some(amount), _) => amount,
+            (None, OutputKind::Anchor) => dust_limit,
+            (None, OutputKind::Address | OutputKind::Script(_)) => {
+                return Err(MinerError::invalid(format!(
+                    "outputs[{idx}] amount is required for non-change outputs"
+                )))
+            }
+        };
+        // Anchors below dust are ephemeral dust, checked once the fee is known.
+        if spk != ANCHOR_SCRIPT_PUBKEY {
+            policy.check_dust(idx, &spk, amount)?;
         }
 
         ordered_outputs.push(TxOutput {
@@ -166,7 +310,191 @@ fn collect_outputs(
         });
     }
 
-    Ok((ordered_outputs, change_info))
+    Ok((ordered_outputs, change_index, dust_limits))
+}
+
+/// Settle the outputs that pay the fee under `fee_mode`; change is handled afterwards.
+fn apply_fee_mode(
+    fee_mode: &FeeMode,
+    fee: u64,
+    total_input: u64,
+    outputs: &mut [TxOutput],
+    change_index: Option<usize>,
+    dust_limits: &[u64],
+) -> MinerResult<()> {
+    let payers: Vec<usize> = match fee_mode {
+        FeeMode::Change => return Ok(()),
+        FeeMode::SendMax(idx) => {
+            let idx = *idx;
+            if idx >= outputs.len() {
+                return Err(MinerError::invalid(format!(
+                    "send-max output index {idx} is out of range"
+                )));
+            }
+            if change_index.is_some() {
+                return Err(MinerError::invalid(
+                    "send-max cannot be combined with a change output",
+                ));
+            }
+            let others = checked_total(
+                outputs
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, o)| (i != idx).then_some(o.amount)),
+                "output",
+            )?;
+            let amount = total_input
+                .checked_sub(others)
+                .and_then(|left| left.checked_sub(fee))
+                .ok_or_else(|| MinerError::invalid("insufficient funds for outputs"))?;
+            check_dust_after_fee(idx, amount, dust_limits)?;
+            outputs[idx].amount = amount;
+            return Ok(());
+        }
+        FeeMode::SubtractFromOutputs(indices) => {
+            if indices.is_empty() {
+                return Err(MinerError::invalid(
+                    "subtract-fee mode needs at least one output",
+                ));
+            }
+            for (pos, &idx) in indices.iter().enumerate() {
+                if idx >= outputs.len() || Some(idx) == change_index {
+                    return Err(MinerError::invalid(format!(
+                        "subtract-fee output index {idx} must name a non-change output"
+                    )));
+                }
+                if indices[..pos].contains(&idx) {
+                    return Err(MinerError::invalid(format!(
+                        "subtract-fee output index {idx} is listed twice"
+                    )));
+                }
+            }
+            indices.clone()
+        }
+        FeeMode::SubtractProportionally => (0..outputs.len())
+            .filter(|&idx| {
+                let script = &outputs[idx].script_pubkey;
+                Some(idx) != change_index
+                    && *script != ANCHOR_SCRIPT_PUBKEY
+                    && !is_op_return(script)
+            })
+            .collect(),
+    };
+    if payers.is_empty() {
+        return Err(MinerError::invalid(
+            "subtract-fee mode needs at least one non-change output",
+        ));
+    }
+
+    let amounts: Vec<u64> = outputs.iter().map(|o| o.amount).collect();
+    for (idx, share) in fee_mode.fee_shares(fee, &amounts, &payers) {
+        let amount = amounts[idx].checked_sub(share).ok_or_else(|| {
+            MinerError::invalid(format!(
+                "outputs[{idx}] amount cannot cover its {share} sat share of the fee"
+            ))
+        })?;
+        check_dust_after_fee(idx, amount, dust_limits)?;
+        outputs[idx].amount = amount;
+    }
+    Ok(())
+}
+
+/// Sum of caller-supplied amounts, rejected when it does not fit in a u64.
+fn checked_total(amounts: impl IntoIterator<Item = u64>, kind: &str) -> MinerResult<u64> {
+    amounts
+        .into_iter()
+        .try_fold(0u64, |total, amount| total.checked_add(amount))
+        .ok_or_else(|| MinerError::invalid(format!("{kind} amounts overflow")))
+}
+
+fn check_dust_after_fee(index: usize, amount: u64, dust_limits: &[u64]) -> MinerResult<()> {
+    let threshold = dust_limits[index];
+    if amount < threshold {
+        return Err(PolicyError::Dust {
+            index,
+            amount,
+            threshold,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Enforce the version, OP_RETURN, dust, weight and minimum relay fee limits of `policy`.
+fn check_standardness(
+    policy: &Policy,
+    inputs: &[TxInput],
+    outputs: &[TxOutput],
+    op_return_script: &[u8],
+    op_return_size: usize,
+    version: u32,
+) -> MinerResult<()> {
+    if !(1..=MAX_STANDARD_VERSION).contains(&version) {
+        return Err(PolicyError::NonStandardVersion { version }.into());
+    }
+
+    let mut datacarrier = op_return_script.len();
+    for output in outputs.iter().filter(|o| is_op_return(&o.script_pubkey)) {
+        if !policy.multiple_op_returns {
+            return Err(PolicyError::MultipleOpReturns.into());
+        }
+        datacarrier += output.script_pubkey.len();
+    }
+    if datacarrier > policy.max_datacarrier_bytes {
+        return Err(PolicyError::DatacarrierTooLarge {
+            size: datacarrier,
+            limit: policy.max_datacarrier_bytes,
+        }
+        .into());
+    }
+
+    let weight = calculate_weight(inputs, outputs, op_return_size)?;
+    if weight > policy.max_standard_weight {
+        return Err(PolicyError::WeightTooHigh {
+            weight,
+            max: policy.max_standard_weight,
+        }
+        .into());
+    }
+    let vsize = (weight + 3) / 4;
+    if version == TRUC_VERSION && vsize > TRUC_MAX_VSIZE {
+        return Err(PolicyError::TrucTooLarge {
+            vsize,
+            max: TRUC_MAX_VSIZE,
+        }
+        .into());
+    }
+
+    // Dropped dust change also goes to the fee, so measure what the plan actually pays.
+    let total_input = checked_total(inputs.iter().map(|i| i.amount), "input")?;
+    let fee = total_input
+        .checked_sub(checked_total(outputs.iter().map(|o| o.amount), "output")?)
+        .ok_or_else(|| MinerError::invalid("insufficient funds for outputs"))?;
+
+    // Only anchors can still be below dust here. Like Bitcoin Core, allow one such
+    // output in a zero-fee transaction, which relays as a package with the child
+    // that spends the anchor.
+    let mut ephemeral_dust = false;
+    for (index, output) in outputs.iter().enumerate() {
+        let threshold = policy.dust_threshold(&output.script_pubkey);
+        if output.amount < threshold {
+            if ephemeral_dust || fee != 0 {
+                return Err(PolicyError::Dust {
+                    index,
+                    amount: output.amount,
+                    threshold,
+                }
+                .into());
+            }
+            ephemeral_dust = true;
+        }
+    }
+
+    let min = policy.min_relay_fee.fee_for_vsize(vsize);
+    if fee < min && !ephemeral_dust {
+        return Err(PolicyError::FeeBelowMinRelay { fee, min }.into());
+    }
+    Ok(())
 }
 
 fn validate_cbor_nonce_len(len: usize) -> MinerResult<()> {
@@ -198,6 +526,9 @@ fn build_distribution_payload(
     Ok((payload, payload_len))
 }
 
+/// Plan a transaction paying a whole number of sats per vbyte.
+///
+/// Integer shim over [`plan_transaction_with_fee_rate`].
 pub fn plan_transaction(
     inputs: Vec<TxInput>,
     outputs: Vec<OutputRequest>,
@@ -206,25 +537,144 @@ pub fn plan_transaction(
     op_return_payload: &[u8],
     distribution: Option<&[u64]>,
 ) -> MinerResult<TransactionPlan> {
-    validate_fee_rate(sats_per_vbyte)?;
+    if sats_per_vbyte == 0 {
+        return Err(MinerError::invalid(
+            "sats_per_vbyte must be greater than zero",
+        ));
+    }
+    let fee_rate = FeeRate::from_sat_per_vb(sats_per_vbyte)
+        .ok_or_else(|| MinerError::invalid("sats_per_vbyte is too large"))?;
+    plan_transaction_with_fee_rate(
+        inputs,
+        outputs,
+        network,
+        fee_rate,
+        op_return_payload,
+        distribution,
+    )
+}
 
-    let (mut ordered_outputs, change_info) = collect_outputs(&outputs, network)?;
+/// Plan a transaction whose fee is `fee_rate` applied to its estimated vsize,
+/// rounded up to the next satoshi.
+pub fn plan_transaction_with_fee_rate(
+    inputs: Vec<TxInput>,
+    outputs: Vec<OutputRequest>,
+    network: Network,
+    fee_rate: FeeRate,
+    op_return_payload: &[u8],
+    distribution: Option<&[u64]>,
+) -> MinerResult<TransactionPlan> {
+    validate_fee_rate(fee_rate)?;
+    plan_transaction_with_fee(
+        inputs,
+        outputs,
+        network,
+        Fee::Rate(fee_rate),
+        &FeeMode::Change,
+        op_return_payload,
+        distribution,
+    )
+}
+
+/// Plan a transaction paying `fee`, taken from the outputs `fee_mode` designates,
+/// under the default [`Policy`].
+pub fn plan_transaction_with_fee(
+    inputs: Vec<TxInput>,
+    outputs: Vec<OutputRequest>,
+    network: Network,
+    fee: Fee,
+    fee_mode: &FeeMode,
+    op_return_payload: &[u8],
+    distribution: Option<&[u64]>,
+) -> MinerResult<TransactionPlan> {
+    let options = PlanOptions {
+        fee_mode: fee_mode.clone(),
+        ..PlanOptions::new(fee)
+    };
+    plan_transaction_with_options(
+        inputs,
+        outputs,
+        network,
+        &options,
+        op_return_payload,
+        distribution,
+    )
+}
+
+/// Plan a transaction under `options`.
+///
+/// Outputs reduced by the fee must stay above their dust threshold; a change output
+/// that would be dust is dropped (with its distribution entry). The finished plan
+/// must then satisfy the standardness rules of `options.policy`.
+pub fn plan_transaction_with_options(
+    inputs: Vec<TxInput>,
+    outputs: Vec<OutputRequest>,
+    network: Network,
+    options: &PlanOptions,
+    op_return_payload: &[u8],
+    distribution: Option<&[u64]>,
+) -> MinerResult<TransactionPlan> {
+    let PlanOptions {
+        fee,
+        ref fee_mode,
+        ref policy,
+        version,
+        lock_time,
+        psbt_version,
+    } = *options;
+    let tx_params = TxParams {
+        version,
+        lock_time: lock_time.to_consensus()?,
+    };
+    if psbt_version == PsbtVersion::V2 && version < 2 {
+        return Err(MinerError::invalid(
+            "PSBTv2 needs a transaction version of at least 2",
+        ));
+    }
+    // nLockTime is only enforced when some input is not final.
+    if tx_params.lock_time != 0 && inputs.iter().all(|i| i.sequence == u32::MAX) {
+        return Err(MinerError::invalid(
+            "lock time needs an input with a sequence below 0xffffffff",
+        ));
+    }
+    match fee {
+        Fee::Rate(rate) => validate_fee_rate(rate)?,
+        // A zero-fee parent is only useful with an anchor a child can bump it through.
+        Fee::Absolute(0) if !outputs.iter().any(|o| o.kind == OutputKind::Anchor) => {
+            return Err(MinerError::invalid(
+                "fee must be greater than zero without an anchor output",
+            ))
+        }
+        Fee::Absolute(_) => {}
+    }
+    let send_max = match *fee_mode {
+        FeeMode::SendMax(idx) => Some(idx),
+        _ => None,
+    };
+    for (idx, input) in inputs.iter().enumerate() {
+        input
+            .check_redeem_script()
+            .map_err(|err| MinerError::invalid(format!("inputs[{idx}]: {err}")))?;
+        input
+            .check_psbt_fields()
+            .map_err(|err| MinerError::invalid(format!("inputs[{idx}]: {err}")))?;
+    }
+    let mut output_fields = Vec::with_capacity(outputs.len());
+    for (idx, output) in outputs.iter().enumerate() {
+        output
+            .psbt
+            .check()
+            .map_err(|err| MinerError::invalid(format!("outputs[{idx}]: {err}")))?;
+        output_fields.push(output.psbt.clone());
+    }
+
+    let (mut ordered_outputs, change_index, dust_limits) =
+        collect_outputs(&outputs, network, send_max, policy)?;
 
     // Determine dust limit for change (if any)
-    let change_dust_limit = change_info
-        .as_ref()
-        .map(|info| dust_limit_for_address(info.address_type))
-        .unwrap_or(0);
-
-    let total_input: u64 = inputs.iter().map(|i| i.amount).sum();
-    let outputs_sum: u64 = ordered_outputs
-        .iter()
-        .enumerate()
-        .filter_map(|(idx, o)| {
-            let is_change = change_info.as_ref().is_some_and(|info| idx == info.index);
-            (!is_change).then_some(o.amount)
-        })
-        .sum();
+    let change_dust_limit = change_index.map_or(0, |idx| dust_limits[idx]);
+
+    let total_input = checked_total(inputs.iter().map(|i| i.amount), "input")?;
 
     let (op_return_size_for_fee, distribution_owned) = if let Some(dist) = distribution {
         let (_, payload_len) = build_distribution_payload(dist, op_return_payload)?;
@@ -234,32 +684,48 @@ pub fn plan_transaction(
     };
 
     // Calculate fee with change output included (if present) for accurate estimation
-    let vsize = calculate_vsize(&inputs, &ordered_outputs, op_return_size_for_fee);
-    let fee = calculate_fee(vsize, sats_per_vbyte);
+    let vsize = calculate_vsize(&inputs, &ordered_outputs, op_return_size_for_fee)?;
+    let fee = fee.amount_for_vsize(vsize);
+
+    apply_fee_mode(
+        fee_mode,
+        fee,
+        total_input,
+        &mut ordered_outputs,
+        change_index,
+        &dust_limits,
+    )?;
+    let outputs_sum = checked_total(
+        ordered_outputs
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, o)| (Some(idx) != change_index).then_some(o.amount)),
+        "output",
+    )?;
 
     // Determine if we have a change output and its amount
-    let (final_outputs, final_change_index, final_distribution) = if let Some(info) = change_info {
+    let (final_outputs, final_change_index, final_distribution) = if let Some(index) = change_index
+    {
         let change_amount = calculate_change(total_input, outputs_sum, fee, change_dust_limit)
-            .map_err(|FeeError::InsufficientFunds| {
-                MinerError::invalid("insufficient funds for outputs")
-            })?;
+            .map_err(|_| MinerError::invalid("insufficient funds for outputs"))?;
 
         match change_amount {
             Some(amount) => {
                 // Change is above dust limit, include it
-                if let Some(change) = ordered_outputs.get_mut(info.index) {
+                if let Some(change) = ordered_outputs.get_mut(index) {
                     change.amount = amount;
                 }
-                (ordered_outputs, Some(info.index), distribution_owned)
+                (ordered_outputs, Some(index), distribution_owned)
             }
             None => {
                 // Change would be dust, remove the change output
-                ordered_outputs.remove(info.index);
+                ordered_outputs.remove(index);
+                output_fields.remove(index);
 
                 // Adjust distribution if present (remove the change entry)
                 let adjusted_distribution = distribution_owned.map(|mut dist| {
-                    if info.index < dist.len() {
-                        dist.remove(info.index);
+                    if index < dist.len() {
+                        dist.remove(index);
                     }
                     dist
                 });
@@ -270,7 +736,10 @@ pub fn plan_transaction(
     } else {
         // No change output specified
         // Verify we have enough funds for outputs + fee
-        if total_input < outputs_sum + fee {
+        if outputs_sum
+            .checked_add(fee)
+            .map_or(true, |needed| total_input < needed)
+        {
             return Err(MinerError::invalid("insufficient funds for outputs"));
         }
         (ordered_outputs, None, distribution_owned)
@@ -299,13 +768,27 @@ pub fn plan_transaction(
         ),
     };
 
+    check_standardness(
+        policy,
+        &inputs,
+        &final_outputs,
+        &op_return_script,
+        op_return_size,
+        version,
+    )?;
+
     Ok(TransactionPlan {
         inputs,
         outputs: final_outputs,
+        output_fields,
         change_index: final_change_index,
         op_return_script,
         op_return_size,
         distribution: final_distribution,
+        network,
+        tx_params,
+        psbt_version,
+        global_pairs: Vec::new(),
     })
 }
 
@@ -324,11 +807,18 @@ pub fn build_mining_template(
             &outputs_after,
             dist,
             nonce_len,
+            plan.tx_params,
         )
         .map_err(MinerError::from)?
     } else {
-        split_tx_for_mining(&plan.inputs, &outputs_before, &outputs_after, nonce_len)
-            .map_err(MinerError::from)?
+        split_tx_for_mining(
+            &plan.inputs,
+            &outputs_before,
+            &outputs_after,
+            nonce_len,
+            plan.tx_params,
+        )
+        .map_err(MinerError::from)?
     };
 
     Ok(MiningTemplate {
@@ -345,10 +835,19 @@ pub fn build_psbt_from_plan(plan: &TransactionPlan) -> MinerResult<(String, [u8;
         amount: 0,
     });
 
-    let psbt_bytes = create_psbt(&plan.inputs, &outputs).map_err(MinerError::from)?;
+    let psbt_bytes = create_psbt(
+        &plan.inputs,
+        &outputs,
+        &plan.output_fields,
+        &plan.global_pairs,
+        plan.tx_params,
+        plan.network,
+        plan.psbt_version,
+    )
+    .map_err(MinerError::from)?;
     let psbt_b64 = psbt_to_base64(&psbt_bytes);
 
-    let unsigned = crate::tx::serialize_tx_for_txid(&plan.inputs, &outputs);
+    let unsigned = crate::tx::serialize_tx_for_txid(&plan.inputs, &outputs, plan.tx_params);
     let txid = double_sha256(&unsigned);
 
     Ok((psbt_b64, txid))
@@ -505,6 +1004,25 @@ pub fn mine_batch_with_cbor(
     batch_size: u32,
     target_zeros: u8,
     use_cbor_nonce: bool,
+) -> Result<Option<MineResult>, &'static str> {
+    mine_batch_with_pattern(
+        tx_prefix,
+        tx_suffix,
+        start_nonce,
+        batch_size,
+        &CompiledPattern::leading_zeros(target_zeros),
+        use_cbor_nonce,
+    )
+}
+
+/// Mine a single-length nonce range for the first txid matching `pattern`.
+pub fn mine_batch_with_pattern(
+    tx_prefix: &[u8],
+    tx_suffix: &[u8],
+    start_nonce: u64,
+    batch_size: u32,
+    pattern: &CompiledPattern,
+    use_cbor_nonce: bool,
 ) -> Result<Option<MineResult>, &'static str> {
     let nonce_len = if use_cbor_nonce {
         cbor_nonce_len_for_range(start_nonce, batch_size)?
@@ -512,35 +1030,48 @@ pub fn mine_batch_with_cbor(
         nonce_len_for_range(start_nonce, batch_size)?
     };
 
-    let mut buffer = Vec::with_capacity(tx_prefix.len() + tx_suffix.len() + nonce_len as usize);
+    let mut hasher = LaneHasher::new(tx_prefix, tx_suffix, nonce_len as usize);
+    let lanes = hasher.lanes() as u32;
     let mut nonce_buf = [0u8; 9];
+    let mut hashes = [[0u8; 32]; MAX_LANES];
+    let mut offset = 0u32;
+
+    while offset < batch_size {
+        let mut filled = 0u32;
+        while filled < lanes.min(batch_size - offset) {
+            let nonce = match start_nonce.checked_add((offset + filled) as u64) {
+                Some(n) => n,
+                None => break,
+            };
+
+            let written = if use_cbor_nonce {
+                let written = encode_cbor_uint_into(nonce, &mut nonce_buf);
+                if written != nonce_len as usize {
+                    return Err("CBOR nonce length mismatch for segment");
+                }
+                written
+            } else {
+                encode_nonce_fixed_into(nonce, nonce_len, &mut nonce_buf)?
+            };
 
-    for offset in 0..batch_size {
-        let nonce = match start_nonce.checked_add(offset as u64) {
-            Some(n) => n,
-            None => break,
-        };
+            hasher.set_nonce(filled as usize, &nonce_buf[..written]);
+            filled += 1;
+        }
+        if filled == 0 {
+            break;
+        }
 
-        let written = if use_cbor_nonce {
-            let encoded = encode_cbor_uint(nonce);
-            if encoded.len() != nonce_len as usize {
-                return Err("CBOR nonce length mismatch for segment");
+        hasher.hash_lanes(&mut hashes);
+        // Lanes are checked in nonce order so the first hit matches a sequential scan.
+        for (lane, hash) in hashes.iter().take(filled as usize).enumerate() {
+            if pattern.matches(hash) {
+                return Ok(Some(MineResult {
+                    nonce: start_nonce + (offset + lane as u32) as u64,
+                    txid: *hash,
+                }));
             }
-            nonce_buf[..encoded.len()].copy_from_slice(&encoded);
-            encoded.len()
-        } else {
-            encode_nonce_fixed_into(nonce, nonce_len, &mut nonce_buf)?
-        };
-
-        buffer.clear();
-        buffer.extend_from_slice(tx_prefix);
-        buffer.extend_from_slice(&nonce_buf[..written]);
-        buffer.extend_from_slice(tx_suffix);
-
-        let hash = double_sha256(&buffer);
-        if hash_meets_target(&hash, target_zeros) {
-            return Ok(Some(MineResult { nonce, txid: hash }));
         }
+        offset += filled;
     }
 
     Ok(None)
@@ -565,7 +1096,7 @@ pub fn mine_range(
     Ok(None)
 }
 
-fn encode_nonce_fixed_into(
+pub(crate) fn encode_nonce_fixed_into(
     nonce: u64,
     nonce_len: u8,
     out: &mut [u8; 9],
@@ -607,7 +1138,8 @@ mod tests {
         cbor::encode_cbor_uint,
         encode_nonce,
         fees::{calculate_fee, calculate_vsize},
-        hash::hash_meets_target,
+        hash::{hash160, hash_meets_target},
+        psbt::{Bip32Derivation, KeySource, BIP32_HARDENED},
         tx::{
             create_zeld_distribution_op_return, serialize_tx_for_txid,
             zeld_distribution_payload_length_with_nonce, DEFAULT_SEQUENCE, ZELD_PREFIX,
@@ -639,6 +1171,9 @@ mod tests {
             script_pubkey: script_pubkey(change_addr, network),
             amount,
             sequence: DEFAULT_SEQUENCE,
+            witness_hint: None,
+            redeem_script: None,
+            psbt: Default::default(),
         }
     }
 
@@ -648,18 +1183,21 @@ mod tests {
                 address: user_addr.to_string(),
                 amount: Some(user_amount),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: change_addr.to_string(),
                 amount: None,
                 change: true,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
         ]
     }
 
     fn dust_limit_for_addr(addr: &str, network: Network) -> u64 {
-        let parsed = parse_address_for_network(addr, Some(network)).expect("address must parse");
-        super::dust_limit_for_address(parsed.address_type)
+        Policy::default().dust_threshold(&script_pubkey(addr, network))
     }
 
     #[test]
@@ -694,7 +1232,7 @@ mod tests {
             },
         ];
 
-        let vsize = calculate_vsize(&inputs, &outputs_for_fee, op_return.len());
+        let vsize = calculate_vsize(&inputs, &outputs_for_fee, op_return.len()).unwrap();
         let fee = calculate_fee(vsize, 2);
         let expected_change = inputs[0].amount - outputs_for_fee[0].amount - fee;
         let change_dust_limit = dust_limit_for_addr(&change_addr, network);
@@ -733,7 +1271,7 @@ mod tests {
                 amount: 0,
             },
         ];
-        let vsize = calculate_vsize(&inputs, &outputs_for_fee, op_return.len());
+        let vsize = calculate_vsize(&inputs, &outputs_for_fee, op_return.len()).unwrap();
         let total_input: u64 = inputs.iter().map(|i| i.amount).sum();
         let outputs_sum = outputs_for_fee[0].amount;
         let change_dust_limit = dust_limit_for_addr(&change_addr, network);
@@ -776,7 +1314,7 @@ mod tests {
             },
         ];
 
-        let vsize = calculate_vsize(&inputs, &outputs_for_fee, payload_len_for_fee);
+        let vsize = calculate_vsize(&inputs, &outputs_for_fee, payload_len_for_fee).unwrap();
         let total_input: u64 = inputs.iter().map(|i| i.amount).sum();
         let outputs_sum = outputs_for_fee[0].amount;
         let change_dust_limit = dust_limit_for_addr(&change_addr, network);
@@ -812,6 +1350,98 @@ mod tests {
         assert_eq!(plan.op_return_script, expected_script);
     }
 
+    #[test]
+    fn redeem_scripts_must_be_witness_programs_their_p2sh_commits_to() {
+        let network = Network::Mainnet;
+        let (user_addr, change_addr) = sample_addresses(network);
+        let program = script_pubkey(&change_addr, network);
+        let p2sh = |script: &[u8]| {
+            let mut spk = vec![0xa9, 0x14];
+            spk.extend(hash160(script));
+            spk.push(0x87);
+            spk
+        };
+        let plan_error = |script_pubkey: Vec<u8>, redeem_script: Vec<u8>| {
+            let mut input = sample_input(120_000, &change_addr, network);
+            input.script_pubkey = script_pubkey;
+            input.redeem_script = Some(redeem_script);
+            plan_transaction(
+                vec![input],
+                outputs(60_000, &user_addr, &change_addr),
+                network,
+                2,
+                &[0xaa],
+                None,
+            )
+            .err()
+            .map(|err| err.to_string())
+        };
+
+        assert_eq!(plan_error(p2sh(&program), program.clone()), None);
+
+        // Native SegWit inputs are spent with an empty scriptSig.
+        let mut p2tr = vec![0x51, 0x20];
+        p2tr.extend([0x44; 32]);
+        for native in [program.clone(), p2tr] {
+            assert_eq!(
+                plan_error(native, program.clone()).as_deref(),
+                Some("invalid input: inputs[0]: redeem script needs a P2SH scriptPubKey")
+            );
+        }
+
+        // 1-of-1 bare multisig behind P2SH is not nested SegWit.
+        let mut multisig = vec![0x51, 0x21, 0x02];
+        multisig.extend([0x55; 32]);
+        multisig.extend([0x51, 0xae]);
+        assert_eq!(
+            plan_error(p2sh(&multisig), multisig).as_deref(),
+            Some("invalid input: inputs[0]: redeem script is not a witness program")
+        );
+
+        let other = script_pubkey(&user_addr, network);
+        assert_eq!(
+            plan_error(p2sh(&other), program).as_deref(),
+            Some("invalid input: inputs[0]: redeem script does not hash to the P2SH scriptPubKey")
+        );
+    }
+
+    #[test]
+    fn overflowing_amounts_are_rejected() {
+        let network = Network::Mainnet;
+        let (user_addr, change_addr) = sample_addresses(network);
+        let plan_error = |inputs: Vec<TxInput>, outputs: Vec<OutputRequest>| {
+            plan_transaction(inputs, outputs, network, 2, &[0xaa], None)
+                .err()
+                .map(|err| err.to_string())
+        };
+
+        let mut second = sample_input(u64::MAX, &change_addr, network);
+        second.vout = 1;
+        let inputs = vec![sample_input(u64::MAX, &change_addr, network), second];
+        assert_eq!(
+            plan_error(inputs, outputs(60_000, &user_addr, &change_addr)).as_deref(),
+            Some("invalid input: input amounts overflow")
+        );
+
+        let mut payments = outputs(u64::MAX, &user_addr, &change_addr);
+        payments[1].amount = Some(u64::MAX);
+        payments[1].change = false;
+        assert_eq!(
+            plan_error(vec![sample_input(120_000, &change_addr, network)], payments).as_deref(),
+            Some("invalid input: output amounts overflow")
+        );
+
+        // The change output cannot absorb a fee on top of a u64::MAX payment.
+        assert_eq!(
+            plan_error(
+                vec![sample_input(u64::MAX, &change_addr, network)],
+                outputs(u64::MAX, &user_addr, &change_addr)
+            )
+            .as_deref(),
+            Some("invalid input: insufficient funds for outputs")
+        );
+    }
+
     #[test]
     fn nonce_len_and_segments_handle_boundaries() {
         assert_eq!(nonce_len_for_range(0, 1).unwrap(), 1);
@@ -885,6 +1515,60 @@ mod tests {
         assert_eq!(result.txid, double_sha256(&full_tx));
     }
 
+    #[test]
+    fn mine_batch_with_cbor_matches_naive_double_sha256() {
+        // Prefix longer than one SHA-256 block so the midstate path is exercised.
+        let prefix = [0x42u8; 150];
+        let suffix = [0x24u8; 40];
+        let target = 2;
+        let result = mine_batch_with_cbor(&prefix, &suffix, 256, 4096, target, true)
+            .expect("batch ok")
+            .expect("two zeros expected within 4096 nonces");
+
+        let naive = (256u64..256 + 4096)
+            .map(|nonce| {
+                let mut full = prefix.to_vec();
+                full.extend_from_slice(&encode_cbor_uint(nonce));
+                full.extend_from_slice(&suffix);
+                (nonce, double_sha256(&full))
+            })
+            .find(|(_, hash)| hash_meets_target(hash, target))
+            .expect("naive search finds the same hit");
+
+        assert_eq!((result.nonce, result.txid), naive);
+    }
+
+    #[test]
+    fn mine_batch_with_pattern_finds_branded_txid() {
+        let prefix = [0x42u8; 90];
+        let suffix = [0x24u8; 12];
+        let pattern = crate::pattern::TxidPattern::AnyOf(vec![
+            crate::pattern::TxidPattern::Prefix("ca".into()),
+            crate::pattern::TxidPattern::Suffix("fe".into()),
+        ])
+        .compile()
+        .expect("pattern compiles");
+
+        let result = mine_batch_with_pattern(&prefix, &suffix, 256, 8192, &pattern, false)
+            .expect("batch ok")
+            .expect("a one-byte pattern hits within 8192 nonces");
+
+        let naive = (256u64..256 + 8192)
+            .map(|nonce| {
+                let mut full = prefix.to_vec();
+                full.extend_from_slice(&encode_nonce(nonce));
+                full.extend_from_slice(&suffix);
+                (nonce, double_sha256(&full))
+            })
+            .find(|(_, hash)| {
+                let txid = txid_to_hex(hash);
+                txid.starts_with("ca") || txid.ends_with("fe")
+            })
+            .expect("naive search finds the same hit");
+
+        assert_eq!((result.nonce, result.txid), naive);
+    }
+
     #[test]
     fn hash_meets_target_guardrails() {
         let mut hash = [0xffu8; 32];
@@ -908,21 +1592,29 @@ mod tests {
                 address: user_addr,
                 amount: Some(40_000),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: addr_b,
                 amount: Some(30_000),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: addr_c,
                 amount: Some(20_000),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: change_addr,
                 amount: None,
                 change: true,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
         ];
         let distribution = [600u64, 300, 100, 0];
@@ -959,21 +1651,29 @@ mod tests {
                 address: user_addr,
                 amount: Some(50_000),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: addr_b,
                 amount: Some(40_000),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: addr_c,
                 amount: Some(30_000),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: change_addr,
                 amount: None,
                 change: true,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
         ];
         let distribution = [600u64, 300, 100, 0];
@@ -1002,6 +1702,203 @@ mod tests {
             .any(|w| w == ZELD_PREFIX));
     }
 
+    #[test]
+    fn fractional_fee_rate_rounds_fee_up() {
+        let network = Network::Mainnet;
+        let (user_addr, change_addr) = sample_addresses(network);
+        let inputs = vec![sample_input(200_000, &change_addr, network)];
+        let outputs = vec![
+            OutputRequest {
+                address: user_addr,
+                amount: Some(50_000),
+                change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
+            },
+            OutputRequest {
+                address: change_addr,
+                amount: None,
+                change: true,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
+            },
+        ];
+        let nonce_bytes = [0xAAu8];
+        let fee_rate: FeeRate = "1.5".parse().unwrap();
+
+        let plan = plan_transaction_with_fee_rate(
+            inputs.clone(),
+            outputs.clone(),
+            network,
+            fee_rate,
+            &nonce_bytes,
+            None,
+        )
+        .expect("plan should succeed");
+        let vsize = calculate_vsize(&inputs, &plan.outputs, nonce_bytes.len()).unwrap();
+        let fee = 200_000 - plan.outputs.iter().map(|o| o.amount).sum::<u64>();
+        assert_eq!(fee, (vsize as u64 * 1_500).div_ceil(1_000));
+
+        let whole = plan_transaction(
+            inputs.clone(),
+            outputs.clone(),
+            network,
+            2,
+            &nonce_bytes,
+            None,
+        )
+        .expect("integer rate still plans");
+        let via_rate = plan_transaction_with_fee_rate(
+            inputs,
+            outputs,
+            network,
+            FeeRate::from_sat_per_vb(2).unwrap(),
+            &nonce_bytes,
+            None,
+        )
+        .expect("plan should succeed");
+        assert_eq!(whole, via_rate);
+    }
+
+    fn fee_mode_outputs(network: Network) -> (Vec<TxInput>, Vec<OutputRequest>) {
+        let (user_addr, change_addr) = sample_addresses(network);
+        let other_addr = segwit_address(0x99, network);
+        let inputs = vec![sample_input(100_000, &change_addr, network)];
+        let outputs = vec![
+            OutputRequest {
+                address: user_addr,
+                amount: Some(60_000),
+                change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
+            },
+            OutputRequest {
+                address: other_addr,
+                amount: Some(20_000),
+                change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
+            },
+        ];
+        (inputs, outputs)
+    }
+
+    #[test]
+    fn absolute_fee_leaves_rest_to_change() {
+        let network = Network::Mainnet;
+        let (inputs, mut outputs) = fee_mode_outputs(network);
+        let (_, change_addr) = sample_addresses(network);
+        outputs.push(OutputRequest {
+            address: change_addr,
+            amount: None,
+            change: true,
+            kind: OutputKind::Address,
+            psbt: Default::default(),
+        });
+
+        let plan = plan_transaction_with_fee(
+            inputs,
+            outputs,
+            network,
+            Fee::Absolute(1_234),
+            &FeeMode::Change,
+            &[0xAA],
+            None,
+        )
+        .expect("absolute fee plans");
+        assert_eq!(plan.outputs[2].amount, 100_000 - 80_000 - 1_234);
+    }
+
+    #[test]
+    fn subtracts_fee_from_designated_outputs() {
+        let network = Network::Mainnet;
+        let (inputs, outputs) = fee_mode_outputs(network);
+
+        let plan = plan_transaction_with_fee(
+            inputs.clone(),
+            outputs.clone(),
+            network,
+            Fee::Absolute(1_001),
+            &FeeMode::SubtractFromOutputs(vec![1, 0]),
+            &[0xAA],
+            None,
+        )
+        .expect("subtract-fee plans");
+        // Equal shares, the first listed output pays the remainder.
+        assert_eq!(plan.outputs[1].amount, 20_000 - 501);
+        assert_eq!(plan.outputs[0].amount, 60_000 - 500);
+
+        let plan = plan_transaction_with_fee(
+            inputs.clone(),
+            outputs.clone(),
+            network,
+            Fee::Absolute(1_000),
+            &FeeMode::SubtractProportionally,
+            &[0xAA],
+            None,
+        )
+        .expect("proportional subtract-fee plans");
+        assert_eq!(plan.outputs[0].amount, 60_000 - 750);
+        assert_eq!(plan.outputs[1].amount, 20_000 - 250);
+
+        let err = plan_transaction_with_fee(
+            inputs,
+            outputs,
+            network,
+            Fee::Absolute(19_800),
+            &FeeMode::SubtractFromOutputs(vec![1]),
+            &[0xAA],
+            None,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("dust"), "{err}");
+    }
+
+    #[test]
+    fn send_max_sweeps_remaining_value_and_keeps_distribution() {
+        let network = Network::Mainnet;
+        let (inputs, mut outputs) = fee_mode_outputs(network);
+        outputs[0].amount = None;
+        let fee_rate = FeeRate::from_sat_per_vb(3).unwrap();
+        let distribution = [7u64, 9];
+
+        let plan = plan_transaction_with_fee(
+            inputs.clone(),
+            outputs.clone(),
+            network,
+            Fee::Rate(fee_rate),
+            &FeeMode::SendMax(0),
+            &encode_cbor_uint(5),
+            Some(&distribution),
+        )
+        .expect("send-max plans");
+        let vsize = calculate_vsize(&inputs, &plan.outputs, plan.op_return_size).unwrap();
+        assert_eq!(
+            plan.outputs[0].amount,
+            100_000 - 20_000 - fee_rate.fee_for_vsize(vsize)
+        );
+        assert_eq!(plan.distribution.as_deref(), Some(&distribution[..]));
+
+        let (_, change_addr) = sample_addresses(network);
+        outputs.push(OutputRequest {
+            address: change_addr,
+            amount: None,
+            change: true,
+            kind: OutputKind::Address,
+            psbt: Default::default(),
+        });
+        assert!(plan_transaction_with_fee(
+            inputs,
+            outputs,
+            network,
+            Fee::Rate(fee_rate),
+            &FeeMode::SendMax(0),
+            &[0xAA],
+            None,
+        )
+        .is_err());
+    }
+
     #[test]
     fn preserves_output_order_and_places_op_return_last() {
         let network = Network::Mainnet;
@@ -1013,16 +1910,22 @@ mod tests {
                 address: user_addr.clone(),
                 amount: Some(50_000),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: change_addr.clone(),
                 amount: None,
                 change: true,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: other_addr.clone(),
                 amount: Some(25_000),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
         ];
         let nonce_bytes = [0xAAu8];
@@ -1055,10 +1958,345 @@ mod tests {
             script_pubkey: plan.op_return_script.clone(),
             amount: 0,
         });
-        let expected_tx = serialize_tx_for_txid(&inputs, &expected_outputs);
+        let expected_tx = serialize_tx_for_txid(&inputs, &expected_outputs, TxParams::default());
         assert_eq!(rebuilt, expected_tx);
     }
 
+    #[test]
+    fn policy_rejects_non_standard_plans() {
+        let network = Network::Mainnet;
+        let plan = |options: &PlanOptions, outputs: Vec<OutputRequest>, op_return: &[u8]| {
+            let (inputs, _) = fee_mode_outputs(network);
+            plan_transaction_with_options(inputs, outputs, network, options, op_return, None)
+        };
+        let (_, outputs) = fee_mode_outputs(network);
+
+        let mut dusty = outputs.clone();
+        dusty[1].amount = Some(293);
+        assert!(matches!(
+            plan(&PlanOptions::new(Fee::Absolute(500)), dusty, &[0xAA]),
+            Err(MinerError::Core(ZeldError::Policy(PolicyError::Dust {
+                index: 1,
+                amount: 293,
+                threshold: 294
+            })))
+        ));
+
+        let legacy = PlanOptions {
+            policy: Policy::CORE_V28,
+            ..PlanOptions::new(Fee::Absolute(500))
+        };
+        assert!(matches!(
+            plan(&legacy, outputs.clone(), &[0xAA; 81]),
+            Err(MinerError::Core(ZeldError::Policy(
+                PolicyError::DatacarrierTooLarge {
+                    size: 84,
+                    limit: 83
+                }
+            )))
+        ));
+        assert!(plan(&legacy, outputs.clone(), &[0xAA; 80]).is_ok());
+
+        // 0.5 sat/vB clears 0.1 sat/vB relay under Core 29.1 but not 1 sat/vB under 28.
+        // The outputs spend the whole input so the rate alone sets the fee.
+        let mut exact = outputs.clone();
+        exact[1].amount = Some(40_000);
+        let half = PlanOptions {
+            fee_mode: FeeMode::SubtractFromOutputs(vec![0]),
+            ..PlanOptions::new(FeeRate::from_sat_per_kvb(500))
+        };
+        assert!(plan(&half, exact.clone(), &[0xAA]).is_ok());
+        assert!(matches!(
+            plan(
+                &PlanOptions {
+                    policy: Policy::CORE_V28,
+                    ..half
+                },
+                exact,
+                &[0xAA]
+            ),
+            Err(MinerError::Core(ZeldError::Policy(
+                PolicyError::FeeBelowMinRelay { .. }
+            )))
+        ));
+
+        let tight = PlanOptions {
+            policy: Policy {
+                max_standard_weight: 500,
+                ..Policy::default()
+            },
+            ..PlanOptions::new(Fee::Absolute(500))
+        };
+        assert!(matches!(
+            plan(&tight, outputs, &[0xAA]),
+            Err(MinerError::Core(ZeldError::Policy(
+                PolicyError::WeightTooHigh { max: 500, .. }
+            )))
+        ));
+    }
+
+    #[test]
+    fn anchors_default_to_dust_or_need_a_zero_fee_parent() {
+        let network = Network::Mainnet;
+        let (inputs, mut outputs) = fee_mode_outputs(network);
+        let anchor = OutputRequest {
+            address: String::new(),
+            amount: None,
+            change: false,
+            kind: OutputKind::Anchor,
+            psbt: Default::default(),
+        };
+        outputs.push(anchor.clone());
+
+        let plan = plan_transaction_with_options(
+            inputs.clone(),
+            outputs.clone(),
+            network,
+            &PlanOptions::new(FeeRate::from_sat_per_vb(2).unwrap()),
+            &[0xAA],
+            None,
+        )
+        .expect("anchor plans");
+        assert_eq!(plan.outputs[2].script_pubkey, ANCHOR_SCRIPT_PUBKEY);
+        assert_eq!(plan.outputs[2].amount, 240);
+
+        // A zero-value anchor is ephemeral dust: only a zero-fee parent may carry it.
+        outputs[1].amount = Some(40_000);
+        outputs[2].amount = Some(0);
+        let zero_fee = PlanOptions::new(Fee::Absolute(0));
+        let plan = plan_transaction_with_options(
+            inputs.clone(),
+            outputs.clone(),
+            network,
+            &zero_fee,
+            &[0xAA],
+            None,
+        )
+        .expect("zero-fee parent with ephemeral anchor");
+        assert_eq!(plan.outputs[2].amount, 0);
+
+        let paying = PlanOptions {
+            fee_mode: FeeMode::SubtractFromOutputs(vec![0]),
+            ..PlanOptions::new(Fee::Absolute(500))
+        };
+        assert!(matches!(
+            plan_transaction_with_options(inputs.clone(), outputs, network, &paying, &[0xAA], None),
+            Err(MinerError::Core(ZeldError::Policy(PolicyError::Dust {
+                index: 2,
+                amount: 0,
+                threshold: 240
+            })))
+        ));
+
+        let (_, mut change_anchor) = fee_mode_outputs(network);
+        change_anchor.push(OutputRequest {
+            change: true,
+            ..anchor
+        });
+        assert!(matches!(
+            plan_transaction_with_options(inputs, change_anchor, network, &zero_fee, &[0xAA], None),
+            Err(MinerError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn raw_script_outputs_must_be_standard() {
+        let network = Network::Mainnet;
+        let (inputs, mut outputs) = fee_mode_outputs(network);
+        let data = vec![0x6a, 0x02, 0xbe, 0xef];
+        outputs.push(OutputRequest {
+            address: String::new(),
+            amount: Some(0),
+            change: false,
+            kind: OutputKind::Script(data.clone()),
+            psbt: Default::default(),
+        });
+        let plan = |options: &PlanOptions, outputs: Vec<OutputRequest>| {
+            plan_transaction_with_options(inputs.clone(), outputs, network, options, &[0xAA], None)
+        };
+
+        // The data carrier sits among the caller's outputs and pays no fee share.
+        let proportional = PlanOptions {
+            fee_mode: FeeMode::SubtractProportionally,
+            ..PlanOptions::new(Fee::Absolute(800))
+        };
+        let planned = plan(&proportional, outputs.clone()).expect("OP_RETURN output plans");
+        assert_eq!(planned.outputs[2].script_pubkey, data);
+        assert_eq!(planned.outputs[2].amount, 0);
+        assert_eq!(planned.outputs[0].amount, 60_000 - 600);
+
+        let legacy = PlanOptions {
+            policy: Policy::CORE_V28,
+            ..PlanOptions::new(Fee::Absolute(800))
+        };
+        assert!(matches!(
+            plan(&legacy, outputs.clone()),
+            Err(MinerError::Core(ZeldError::Policy(
+                PolicyError::MultipleOpReturns
+            )))
+        ));
+
+        let fee = PlanOptions::new(Fee::Absolute(800));
+        outputs[2].kind = OutputKind::Script(vec![0x51]);
+        assert!(matches!(
+            plan(&fee, outputs.clone()),
+            Err(MinerError::Core(ZeldError::Policy(
+                PolicyError::NonStandardScript { index: 2 }
+            )))
+        ));
+
+        let mut p2pk = vec![33];
+        p2pk.extend([0x02; 33]);
+        p2pk.push(0xac);
+        outputs[2].kind = OutputKind::Script(p2pk);
+        outputs[2].amount = Some(500);
+        assert!(matches!(
+            plan(&fee, outputs),
+            Err(MinerError::Core(ZeldError::Policy(PolicyError::Dust {
+                index: 2,
+                threshold: 576,
+                ..
+            })))
+        ));
+    }
+
+    #[test]
+    fn version_and_lock_time_frame_the_plan() {
+        let network = Network::Mainnet;
+        let (inputs, outputs) = fee_mode_outputs(network);
+        let plan = |options: &PlanOptions, inputs: Vec<TxInput>| {
+            plan_transaction_with_options(inputs, outputs.clone(), network, options, &[0xAA], None)
+        };
+
+        let sniping = PlanOptions {
+            version: 3,
+            lock_time: LockTime::AntiFeeSniping {
+                tip_height: 870_000,
+            },
+            ..PlanOptions::new(Fee::Absolute(500))
+        };
+        let planned = plan(&sniping, inputs.clone()).expect("TRUC plan");
+        assert_eq!(
+            planned.tx_params,
+            TxParams {
+                version: 3,
+                lock_time: 870_000
+            }
+        );
+        let template = build_mining_template(&planned, 1).expect("template");
+        assert_eq!(template.prefix[..4], 3u32.to_le_bytes());
+        assert_eq!(
+            template.suffix[template.suffix.len() - 4..],
+            870_000u32.to_le_bytes()
+        );
+
+        let bad_version = PlanOptions {
+            version: 4,
+            ..PlanOptions::new(Fee::Absolute(500))
+        };
+        assert!(matches!(
+            plan(&bad_version, inputs.clone()),
+            Err(MinerError::Core(ZeldError::Policy(
+                PolicyError::NonStandardVersion { version: 4 }
+            )))
+        ));
+
+        let v1_psbt_v2 = PlanOptions {
+            version: 1,
+            psbt_version: PsbtVersion::V2,
+            ..PlanOptions::new(Fee::Absolute(500))
+        };
+        assert!(matches!(
+            plan(&v1_psbt_v2, inputs.clone()),
+            Err(MinerError::InvalidInput(_))
+        ));
+        let v1_psbt_v0 = PlanOptions {
+            version: 1,
+            ..PlanOptions::new(Fee::Absolute(500))
+        };
+        assert!(plan(&v1_psbt_v0, inputs.clone()).is_ok());
+
+        let early_time = PlanOptions {
+            lock_time: LockTime::Time(1_000),
+            ..PlanOptions::new(Fee::Absolute(500))
+        };
+        assert!(matches!(
+            plan(&early_time, inputs.clone()),
+            Err(MinerError::InvalidInput(_))
+        ));
+
+        // A locktime only binds when some input sequence is not final.
+        let mut final_inputs = inputs;
+        final_inputs[0].sequence = u32::MAX;
+        assert!(matches!(
+            plan(&sniping, final_inputs),
+            Err(MinerError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn psbt_fields_follow_their_outputs() {
+        let network = Network::Mainnet;
+        let (inputs, mut outputs) = fee_mode_outputs(network);
+        let (_, change_addr) = sample_addresses(network);
+        let origin = |last: u32| PsbtOutputFields {
+            bip32_derivations: vec![Bip32Derivation {
+                pubkey: [&[0x03][..], &[last as u8; 32]].concat(),
+                source: KeySource {
+                    fingerprint: [0xd3, 0x4d, 0xb3, 0x3f],
+                    path: vec![84 | BIP32_HARDENED, BIP32_HARDENED, BIP32_HARDENED, 1, last],
+                },
+            }],
+            ..Default::default()
+        };
+        outputs[1].psbt = origin(9);
+        outputs.insert(
+            1,
+            OutputRequest {
+                address: change_addr,
+                amount: None,
+                change: true,
+                kind: OutputKind::Address,
+                psbt: origin(3),
+            },
+        );
+
+        // Change of 100 sats is dust and dropped along with its PSBT fields.
+        let plan = plan_transaction_with_fee(
+            inputs.clone(),
+            outputs.clone(),
+            network,
+            Fee::Absolute(19_900),
+            &FeeMode::Change,
+            &[0xAA],
+            None,
+        )
+        .expect("plan succeeds");
+        assert_eq!(plan.change_index, None);
+        assert_eq!(
+            plan.output_fields,
+            vec![PsbtOutputFields::default(), origin(9)]
+        );
+        build_psbt_from_plan(&plan).expect("psbt builds");
+
+        let mut bad_inputs = inputs;
+        bad_inputs[0].psbt.non_witness_utxo = Some(vec![0x02]);
+        let err = plan_transaction_with_fee(
+            bad_inputs,
+            outputs,
+            network,
+            Fee::Absolute(1_000),
+            &FeeMode::Change,
+            &[0xAA],
+            None,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(&err, MinerError::InvalidInput(msg) if msg.starts_with("inputs[0]")),
+            "{err}"
+        );
+    }
+
     #[test]
     fn mining_template_splits_correctly_with_distribution() {
         let network = Network::Mainnet;
@@ -1071,21 +2309,29 @@ mod tests {
                 address: user_addr,
                 amount: Some(50_000),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: addr_b,
                 amount: Some(40_000),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: addr_c,
                 amount: Some(30_000),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: change_addr,
                 amount: None,
                 change: true,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
         ];
         let distribution = [600u64, 300, 100, 0];
@@ -1115,7 +2361,7 @@ mod tests {
             amount: 0,
         });
 
-        let expected_tx = serialize_tx_for_txid(&inputs, &full_outputs);
+        let expected_tx = serialize_tx_for_txid(&inputs, &full_outputs, TxParams::default());
         assert_eq!(rebuilt, expected_tx);
     }
 
@@ -1131,21 +2377,29 @@ mod tests {
                 address: user_addr,
                 amount: Some(50_000),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: addr_b,
                 amount: Some(40_000),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: addr_c,
                 amount: Some(30_000),
                 change: false,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
             OutputRequest {
                 address: change_addr,
                 amount: None,
                 change: true,
+                kind: OutputKind::Address,
+                psbt: Default::default(),
             },
         ];
         let distribution = [600u64, 300, 100, 0];
@@ -1180,7 +2434,7 @@ mod tests {
             script_pubkey: expected_op_return.clone(),
             amount: 0,
         });
-        let expected_tx = serialize_tx_for_txid(&inputs, &outputs_full);
+        let expected_tx = serialize_tx_for_txid(&inputs, &outputs_full, TxParams::default());
 
         assert_eq!(mined_tx, expected_tx);
         assert!(expected_op_return
@@ -0,0 +1,386 @@
+//! Vanity txid patterns.
+//!
+//! A [`TxidPattern`] describes what the displayed txid must look like (leading
+//! zeros, a hex prefix or suffix, masked hex with wildcards, or a set of
+//! alternatives). [`TxidPattern::compile`] turns it into a [`CompiledPattern`]:
+//! one mask/value pair per alternative, laid out in hash byte order so mining
+//! loops compare raw digests without reversing them.
+
+use alloc::{string::String, vec::Vec};
+
+use thiserror::Error;
+
+use crate::stats::leading_zero_bits_probability;
+
+/// Largest number of alternatives a compiled pattern may hold.
+///
+/// Bounded so the GPU uniform block has a fixed size.
+pub const MAX_PATTERN_ALTERNATIVES: usize = 8;
+
+/// Hex digits in a displayed txid.
+const TXID_HEX_LEN: usize = 64;
+/// Bits in a txid.
+const TXID_BITS: u16 = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxidPattern {
+    /// At least this many leading hex zeros (same rule as `hash_meets_target`).
+    LeadingZeros(u8),
+    /// At least this many leading zero bits of the displayed txid.
+    LeadingZeroBits(u16),
+    /// The txid starts with these hex digits (e.g. `cafe`).
+    Prefix(String),
+    /// The txid ends with these hex digits.
+    Suffix(String),
+    /// Hex digits anchored at the start of the txid; `?` matches any digit.
+    Masked(String),
+    /// Any of the nested patterns.
+    AnyOf(Vec<TxidPattern>),
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PatternError {
+    #[error("pattern is longer than 64 hex digits")]
+    TooLong,
+    #[error("invalid pattern character {0:?}")]
+    InvalidCharacter(char),
+    #[error("leading zero target exceeds the txid length")]
+    TargetTooLarge,
+    #[error("pattern has no alternatives")]
+    Empty,
+    #[error("pattern has more than {MAX_PATTERN_ALTERNATIVES} alternatives")]
+    TooManyAlternatives,
+}
+
+/// A single mask/value check in hash (internal, non-reversed) byte order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteMask {
+    mask: [u8; 32],
+    value: [u8; 32],
+    /// Range of hash bytes with a non-zero mask; the rest are not compared.
+    start: usize,
+    end: usize,
+}
+
+impl ByteMask {
+    fn new(mask: [u8; 32], value: [u8; 32]) -> Self {
+        let start = mask.iter().position(|&m| m != 0).unwrap_or(32);
+        let end = mask.iter().rposition(|&m| m != 0).map_or(start, |i| i + 1);
+        Self {
+            mask,
+            value,
+            start,
+            end,
+        }
+    }
+
+    /// Bits compared for each hash byte.
+    pub fn mask(&self) -> &[u8; 32] {
+        &self.mask
+    }
+
+    /// Expected value of the masked bits.
+    pub fn value(&self) -> &[u8; 32] {
+        &self.value
+    }
+
+    /// Number of constrained bits; a random hash matches with probability 2^-bits.
+    pub fn constrained_bits(&self) -> u32 {
+        self.mask.iter().map(|m| m.count_ones()).sum()
+    }
+
+    #[inline]
+    pub fn matches(&self, hash: &[u8; 32]) -> bool {
+        (self.start..self.end).all(|i| hash[i] & self.mask[i] == self.value[i])
+    }
+}
+
+/// A pattern lowered to byte masks, ready for mining loops.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledPattern {
+    alternatives: Vec<ByteMask>,
+}
+
+impl CompiledPattern {
+    /// Leading-zero target with the semantics of `hash_meets_target`: `0` matches
+    /// every hash and targets above 64 never match.
+    pub fn leading_zeros(target_zeros: u8) -> Self {
+        Self::compile_or_never(TxidPattern::LeadingZeros(target_zeros))
+    }
+
+    /// Leading-zero-bit target with the semantics of `hash_meets_target_bits`:
+    /// `0` matches every hash and targets above 256 never match.
+    pub fn leading_zero_bits(target_bits: u16) -> Self {
+        Self::compile_or_never(TxidPattern::LeadingZeroBits(target_bits))
+    }
+
+    fn compile_or_never(pattern: TxidPattern) -> Self {
+        match pattern.compile() {
+            Ok(compiled) => compiled,
+            Err(_) => Self {
+                alternatives: Vec::new(),
+            },
+        }
+    }
+
+    /// Probability that a random hash matches, treating alternatives as independent.
+    pub fn hit_probability(&self) -> f64 {
+        let miss = self.alternatives.iter().fold(1.0, |miss, alt| {
+            miss * (1.0 - leading_zero_bits_probability(alt.constrained_bits()))
+        });
+        1.0 - miss
+    }
+
+    pub fn alternatives(&self) -> &[ByteMask] {
+        &self.alternatives
+    }
+
+    /// Whether a double-SHA256 digest (hash byte order) satisfies any alternative.
+    #[inline]
+    pub fn matches(&self, hash: &[u8; 32]) -> bool {
+        self.alternatives.iter().any(|alt| alt.matches(hash))
+    }
+}
+
+impl TxidPattern {
+    /// Validate the pattern and lower it to byte masks.
+    pub fn compile(&self) -> Result<CompiledPattern, PatternError> {
+        let mut alternatives = Vec::new();
+        self.collect_alternatives(&mut alternatives)?;
+        if alternatives.is_empty() {
+            return Err(PatternError::Empty);
+        }
+        if alternatives.len() > MAX_PATTERN_ALTERNATIVES {
+            return Err(PatternError::TooManyAlternatives);
+        }
+        Ok(CompiledPattern { alternatives })
+    }
+
+    fn collect_alternatives(&self, out: &mut Vec<ByteMask>) -> Result<(), PatternError> {
+        match self {
+            TxidPattern::LeadingZeros(zeros) => {
+                if *zeros as usize > TXID_HEX_LEN {
+                    return Err(PatternError::TargetTooLarge);
+                }
+                out.push(leading_zero_bits_mask(*zeros as u16 * 4));
+            }
+            TxidPattern::LeadingZeroBits(bits) => {
+                if *bits > TXID_BITS {
+                    return Err(PatternError::TargetTooLarge);
+                }
+                out.push(leading_zero_bits_mask(*bits));
+            }
+            TxidPattern::Prefix(hex) => {
+                let digits = parse_digits(hex, false)?;
+                out.push(digits_mask(&digits, 0));
+            }
+            TxidPattern::Suffix(hex) => {
+                let digits = parse_digits(hex, false)?;
+                out.push(digits_mask(&digits, TXID_HEX_LEN - digits.len()));
+            }
+            TxidPattern::Masked(hex) => {
+                let digits = parse_digits(hex, true)?;
+                out.push(digits_mask(&digits, 0));
+            }
+            TxidPattern::AnyOf(patterns) => {
+                for pattern in patterns {
+                    pattern.collect_alternatives(out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hash byte holding the displayed hex digit at `position` (0 = leftmost), and
+/// whether it is the high nibble.
+fn digit_location(position: usize) -> (usize, bool) {
+    // Txids are displayed reversed, so the leftmost digits live in the last byte.
+    (31 - position / 2, position % 2 == 0)
+}
+
+fn leading_zero_bits_mask(bits: u16) -> ByteMask {
+    let mut mask = [0u8; 32];
+    let full_bytes = (bits / 8) as usize;
+    for byte in mask.iter_mut().rev().take(full_bytes) {
+        *byte = 0xff;
+    }
+    let partial = bits % 8;
+    if partial != 0 {
+        mask[31 - full_bytes] = 0xffu8 << (8 - partial);
+    }
+    ByteMask::new(mask, [0u8; 32])
+}
+
+/// Parse hex digits; `None` entries are wildcards (only when allowed).
+fn parse_digits(hex: &str, allow_wildcards: bool) -> Result<Vec<Option<u8>>, PatternError> {
+    let digits = hex
+        .chars()
+        .map(|c| match c {
+            '?' if allow_wildcards => Ok(None),
+            _ => c
+                .to_digit(16)
+                .map(|d| Some(d as u8))
+                .ok_or(PatternError::InvalidCharacter(c)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if digits.len() > TXID_HEX_LEN {
+        return Err(PatternError::TooLong);
+    }
+    Ok(digits)
+}
+
+fn digits_mask(digits: &[Option<u8>], first_position: usize) -> ByteMask {
+    let mut mask = [0u8; 32];
+    let mut value = [0u8; 32];
+    for (offset, digit) in digits.iter().enumerate() {
+        let Some(digit) = digit else { continue };
+        let (idx, high) = digit_location(first_position + offset);
+        let shift = if high { 4 } else { 0 };
+        mask[idx] |= 0x0f << shift;
+        value[idx] |= digit << shift;
+    }
+    ByteMask::new(mask, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::{hash_meets_target, hash_meets_target_bits};
+    use alloc::{string::ToString, vec};
+
+    /// Hash bytes whose displayed txid is `display_hex`.
+    fn hash_from_display(display_hex: &str) -> [u8; 32] {
+        let mut bytes = hex::decode(display_hex).unwrap();
+        bytes.reverse();
+        bytes.try_into().unwrap()
+    }
+
+    fn txid(prefix: &str) -> [u8; 32] {
+        let mut display = prefix.to_string();
+        while display.len() < 64 {
+            display.push('7');
+        }
+        hash_from_display(&display)
+    }
+
+    #[test]
+    fn prefix_and_suffix_match_displayed_txid() {
+        let prefix = TxidPattern::Prefix("CaFe".into()).compile().unwrap();
+        assert!(prefix.matches(&txid("cafe")));
+        assert!(!prefix.matches(&txid("cafd")));
+        assert!(!prefix.matches(&txid("0cafe")));
+
+        let suffix = TxidPattern::Suffix("beef".into()).compile().unwrap();
+        let mut display = "1".repeat(60);
+        display.push_str("beef");
+        assert!(suffix.matches(&hash_from_display(&display)));
+        assert!(!suffix.matches(&txid("beef")));
+
+        // Odd-length patterns stop mid-byte.
+        let odd = TxidPattern::Suffix("abc".into()).compile().unwrap();
+        let mut display = "f".repeat(61);
+        display.push_str("abc");
+        assert!(odd.matches(&hash_from_display(&display)));
+    }
+
+    #[test]
+    fn masked_pattern_skips_wildcards() {
+        let pattern = TxidPattern::Masked("c?f?".into()).compile().unwrap();
+        assert!(pattern.matches(&txid("c0f9")));
+        assert!(pattern.matches(&txid("caff")));
+        assert!(!pattern.matches(&txid("d0f0")));
+        assert_eq!(pattern.alternatives()[0].constrained_bits(), 8);
+
+        assert_eq!(
+            TxidPattern::Prefix("c?fe".into()).compile().unwrap_err(),
+            PatternError::InvalidCharacter('?')
+        );
+    }
+
+    #[test]
+    fn leading_zero_targets_match_hash_meets_target() {
+        let hashes = [
+            txid("0"),
+            txid("00"),
+            txid("000f"),
+            txid("0001"),
+            txid("00000"),
+            [0u8; 32],
+        ];
+        for target in 0..=8u8 {
+            let pattern = CompiledPattern::leading_zeros(target);
+            for hash in &hashes {
+                assert_eq!(
+                    pattern.matches(hash),
+                    hash_meets_target(hash, target),
+                    "target={target}"
+                );
+            }
+        }
+        assert!(!CompiledPattern::leading_zeros(65).matches(&[0u8; 32]));
+
+        for target in [0u16, 1, 7, 9, 13, 16] {
+            let pattern = CompiledPattern::leading_zero_bits(target);
+            for hash in &hashes {
+                assert_eq!(
+                    pattern.matches(hash),
+                    hash_meets_target_bits(hash, target),
+                    "bits={target}"
+                );
+            }
+        }
+        assert!(!CompiledPattern::leading_zero_bits(257).matches(&[0u8; 32]));
+    }
+
+    #[test]
+    fn leading_zero_bits_constrain_partial_nibbles() {
+        let pattern = TxidPattern::LeadingZeroBits(10).compile().unwrap();
+        assert!(pattern.matches(&txid("003f")));
+        assert!(!pattern.matches(&txid("004f")));
+        assert_eq!(pattern.alternatives()[0].constrained_bits(), 10);
+        assert_eq!(pattern.hit_probability(), 1.0 / 1024.0);
+        assert_eq!(CompiledPattern::leading_zeros(0).hit_probability(), 1.0);
+        assert_eq!(CompiledPattern::leading_zeros(65).hit_probability(), 0.0);
+
+        assert!(TxidPattern::LeadingZeroBits(256)
+            .compile()
+            .unwrap()
+            .matches(&[0u8; 32]));
+        assert_eq!(
+            TxidPattern::LeadingZeroBits(257).compile().unwrap_err(),
+            PatternError::TargetTooLarge
+        );
+    }
+
+    #[test]
+    fn alternatives_flatten_and_are_bounded() {
+        let pattern = TxidPattern::AnyOf(vec![
+            TxidPattern::Prefix("cafe".into()),
+            TxidPattern::AnyOf(vec![TxidPattern::Prefix("beef".into())]),
+        ])
+        .compile()
+        .unwrap();
+        assert_eq!(pattern.alternatives().len(), 2);
+        assert!(pattern.matches(&txid("cafe")));
+        assert!(pattern.matches(&txid("beef")));
+        assert!(!pattern.matches(&txid("dead")));
+
+        assert_eq!(
+            TxidPattern::AnyOf(Vec::new()).compile().unwrap_err(),
+            PatternError::Empty
+        );
+        let too_many = TxidPattern::AnyOf(vec![
+            TxidPattern::LeadingZeros(1);
+            MAX_PATTERN_ALTERNATIVES + 1
+        ]);
+        assert_eq!(
+            too_many.compile().unwrap_err(),
+            PatternError::TooManyAlternatives
+        );
+        assert_eq!(
+            TxidPattern::Prefix("0".repeat(65)).compile().unwrap_err(),
+            PatternError::TooLong
+        );
+    }
+}
@@ -0,0 +1,317 @@
+//! Relay policy a planned transaction must satisfy to be standard.
+//!
+//! Mirrors the Bitcoin Core defaults that decide whether a node relays a transaction:
+//! output script types, dust thresholds, OP_RETURN size, transaction weight and the
+//! minimum relay fee.
+
+use thiserror::Error;
+
+use crate::{fees::FeeRate, tx::encode_varint};
+
+/// Standardness rules enforced while planning a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Policy {
+    /// Rate dust thresholds are derived from (`-dustrelayfee`).
+    pub dust_relay_fee: FeeRate,
+    /// Largest total size of the OP_RETURN scriptPubKeys, opcodes and pushes
+    /// included (`-datacarriersize`).
+    pub max_datacarrier_bytes: usize,
+    /// Whether a transaction may carry more than one OP_RETURN output.
+    pub multiple_op_returns: bool,
+    /// Largest standard transaction weight (`MAX_STANDARD_TX_WEIGHT`).
+    pub max_standard_weight: usize,
+    /// Lowest fee rate the transaction may pay (`-minrelaytxfee`).
+    pub min_relay_fee: FeeRate,
+}
+
+/// Highest standard transaction version (`TX_MAX_STANDARD_VERSION`).
+pub const MAX_STANDARD_VERSION: u32 = 3;
+/// Version that opts into the TRUC (BIP431) topology and size rules.
+pub const TRUC_VERSION: u32 = 3;
+/// Largest standard TRUC transaction, in vbytes.
+pub const TRUC_MAX_VSIZE: usize = 10_000;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PolicyError {
+    #[error("transaction version {version} is not standard")]
+    NonStandardVersion { version: u32 },
+    #[error("TRUC transaction vsize {vsize} exceeds the {max} vbyte limit")]
+    TrucTooLarge { vsize: usize, max: usize },
+    #[error("outputs[{index}] amount {amount} is below the {threshold} sat dust threshold")]
+    Dust {
+        index: usize,
+        amount: u64,
+        threshold: u64,
+    },
+    #[error("outputs[{index}] scriptPubKey is not a standard output type")]
+    NonStandardScript { index: usize },
+    #[error("OP_RETURN scripts total {size} bytes, above the {limit}-byte datacarrier limit")]
+    DatacarrierTooLarge { size: usize, limit: usize },
+    #[error("only one OP_RETURN output is standard under this policy")]
+    MultipleOpReturns,
+    #[error("transaction weight {weight} exceeds the standard maximum of {max}")]
+    WeightTooHigh { weight: usize, max: usize },
+    #[error("fee of {fee} sats is below the {min} sat minimum relay fee")]
+    FeeBelowMinRelay { fee: u64, min: u64 },
+}
+
+impl Policy {
+    /// Bitcoin Core up to 29.0: one 83-byte OP_RETURN, 1 sat/vB minimum relay fee.
+    pub const CORE_V28: Policy = Policy {
+        dust_relay_fee: FeeRate::from_sat_per_kvb(3_000),
+        max_datacarrier_bytes: 83,
+        multiple_op_returns: false,
+        max_standard_weight: 400_000,
+        min_relay_fee: FeeRate::from_sat_per_kvb(1_000),
+    };
+
+    /// Bitcoin Core 29.1: minimum relay fee lowered to 0.1 sat/vB.
+    pub const CORE_V29_1: Policy = Policy {
+        min_relay_fee: FeeRate::from_sat_per_kvb(100),
+        ..Policy::CORE_V28
+    };
+
+    /// Bitcoin Core 30: any number of OP_RETURN outputs, limited only by the 100 kB
+    /// datacarrier default.
+    pub const CORE_V30: Policy = Policy {
+        max_datacarrier_bytes: 100_000,
+        multiple_op_returns: true,
+        ..Policy::CORE_V29_1
+    };
+
+    /// Smallest amount an output paying to `script_pubkey` may carry, as computed by
+    /// Bitcoin Core's `GetDustThreshold`: the fee, at the dust relay rate, of creating
+    /// the output plus spending it later.
+    pub fn dust_threshold(&self, script_pubkey: &[u8]) -> u64 {
+        if is_op_return(script_pubkey) {
+            // OP_RETURN outputs are unspendable and never dust.
+            return 0;
+        }
+        let output_size = 8 + encode_varint(script_pubkey.len() as u64).len() + script_pubkey.len();
+        // Outpoint, script length, sequence, plus a 107-byte signature script or its
+        // witness-discounted equivalent.
+        let spend_size = if is_witness_program(script_pubkey) {
+            32 + 4 + 1 + 107 / 4 + 4
+        } else {
+            32 + 4 + 1 + 107 + 4
+        };
+        self.dust_relay_fee.fee_for_vsize(output_size + spend_size)
+    }
+
+    /// Reject `amount` on the output at `index` when it is below its dust threshold.
+    pub fn check_dust(
+        &self,
+        index: usize,
+        script_pubkey: &[u8],
+        amount: u64,
+    ) -> Result<(), PolicyError> {
+        let threshold = self.dust_threshold(script_pubkey);
+        if amount < threshold {
+            return Err(PolicyError::Dust {
+                index,
+                amount,
+                threshold,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Reject the script of the output at `index` unless Bitcoin Core relays it.
+pub fn check_output_script(index: usize, script_pubkey: &[u8]) -> Result<(), PolicyError> {
+    if is_standard_output_script(script_pubkey) {
+        Ok(())
+    } else {
+        Err(PolicyError::NonStandardScript { index })
+    }
+}
+
+/// Whether `script_pubkey` is a standard output type, as in Bitcoin Core's `Solver`:
+/// P2PK, P2PKH, P2SH, bare multisig of up to 3 keys, SegWit v0 P2WPKH/P2WSH, any
+/// witness program of a later version, or an OP_RETURN followed only by pushes.
+pub fn is_standard_output_script(script_pubkey: &[u8]) -> bool {
+    let len = script_pubkey.len();
+    match script_pubkey {
+        [0x76, 0xa9, 0x14, .., 0x88, 0xac] if len == 25 => true,
+        [0xa9, 0x14, .., 0x87] if len == 23 => true,
+        [0x6a, data @ ..] => is_push_only(data),
+        [size, key @ .., 0xac] if *size as usize == key.len() => is_valid_pubkey(key),
+        [0x00, ..] if is_witness_program(script_pubkey) => len == 22 || len == 34,
+        _ if is_witness_program(script_pubkey) => true,
+        _ => is_bare_multisig(script_pubkey),
+    }
+}
+
+/// `OP_RETURN ...`: an unspendable data carrier.
+pub fn is_op_return(script_pubkey: &[u8]) -> bool {
+    script_pubkey.first() == Some(&0x6a)
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy::CORE_V30
+    }
+}
+
+/// `OP_n <2..40-byte program>`, as in Bitcoin Core's `CScript::IsWitnessProgram`.
+fn is_witness_program(script_pubkey: &[u8]) -> bool {
+    match script_pubkey {
+        [version, len, program @ ..] if (4..=42).contains(&script_pubkey.len()) => {
+            (*version == 0x00 || (0x51..=0x60).contains(version)) && *len as usize == program.len()
+        }
+        _ => false,
+    }
+}
+
+/// Only push opcodes (up to `OP_16`), with every push complete.
+fn is_push_only(mut script: &[u8]) -> bool {
+    while let [opcode, rest @ ..] = script {
+        let (len, rest) = match *opcode {
+            op @ 0x00..=0x4b => (op as usize, rest),
+            0x4c => match rest {
+                [len, rest @ ..] => (*len as usize, rest),
+                _ => return false,
+            },
+            0x4d => match rest {
+                [a, b, rest @ ..] => (u16::from_le_bytes([*a, *b]) as usize, rest),
+                _ => return false,
+            },
+            0x4e => match rest {
+                [a, b, c, d, rest @ ..] => (u32::from_le_bytes([*a, *b, *c, *d]) as usize, rest),
+                _ => return false,
+            },
+            0x4f..=0x60 => (0, rest),
+            _ => return false,
+        };
+        if rest.len() < len {
+            return false;
+        }
+        script = &rest[len..];
+    }
+    true
+}
+
+/// `OP_m <pubkey>... OP_n OP_CHECKMULTISIG` with `1 <= m <= n <= 3`.
+fn is_bare_multisig(script_pubkey: &[u8]) -> bool {
+    let &[m @ 0x51..=0x60, ref keys @ .., n @ 0x51..=0x60, 0xae] = script_pubkey else {
+        return false;
+    };
+    let (required, total) = (m - 0x50, n - 0x50);
+    let mut keys = keys;
+    let mut count = 0;
+    while let [size, rest @ ..] = keys {
+        let size = *size as usize;
+        if rest.len() < size || !is_valid_pubkey(&rest[..size]) {
+            return false;
+        }
+        keys = &rest[size..];
+        count += 1;
+    }
+    count == total && required <= total && total <= 3
+}
+
+/// Compressed or uncompressed public key by size and prefix, like `CPubKey::ValidSize`.
+pub(crate) fn is_valid_pubkey(key: &[u8]) -> bool {
+    matches!(
+        (key.len(), key.first()),
+        (33, Some(0x02 | 0x03)) | (65, Some(0x04 | 0x06 | 0x07))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn dust_thresholds_match_bitcoin_core() {
+        let policy = Policy::default();
+        let mut p2wpkh = vec![0x00, 0x14];
+        p2wpkh.extend([0x11; 20]);
+        let mut p2tr = vec![0x51, 0x20];
+        p2tr.extend([0x22; 32]);
+        let mut p2pkh = vec![0x76, 0xa9, 0x14];
+        p2pkh.extend([0x33; 20]);
+        p2pkh.extend([0x88, 0xac]);
+
+        assert_eq!(policy.dust_threshold(&p2wpkh), 294);
+        assert_eq!(policy.dust_threshold(&p2tr), 330);
+        assert_eq!(policy.dust_threshold(&p2pkh), 546);
+        assert_eq!(policy.dust_threshold(&[0x6a, 0x01, 0x00]), 0);
+
+        assert_eq!(
+            policy.check_dust(2, &p2tr, 329),
+            Err(PolicyError::Dust {
+                index: 2,
+                amount: 329,
+                threshold: 330
+            })
+        );
+        assert_eq!(policy.check_dust(2, &p2tr, 330), Ok(()));
+    }
+
+    #[test]
+    fn recognizes_standard_output_scripts() {
+        let key = [0x02; 33];
+        let mut p2pk = vec![33];
+        p2pk.extend(key);
+        p2pk.push(0xac);
+        let mut multisig = vec![0x52];
+        for _ in 0..3 {
+            multisig.push(33);
+            multisig.extend(key);
+        }
+        multisig.extend([0x53, 0xae]);
+        let mut p2wsh = vec![0x00, 0x20];
+        p2wsh.extend([0x44; 32]);
+
+        for script in [
+            &p2pk[..],
+            &multisig,
+            &p2wsh,
+            &[0x52, 0x02, 0xaa, 0xbb],
+            &[0x6a],
+            &[0x6a, 0x02, 0xaa, 0xbb, 0x4c, 0x01, 0xcc, 0x51],
+        ] {
+            assert!(is_standard_output_script(script), "{script:?}");
+        }
+
+        let mut four_keys = vec![0x51];
+        for _ in 0..4 {
+            four_keys.push(33);
+            four_keys.extend(key);
+        }
+        four_keys.extend([0x54, 0xae]);
+        let mut v0_odd = vec![0x00, 0x18];
+        v0_odd.extend([0x44; 24]);
+        for script in [
+            &four_keys[..],
+            &v0_odd,
+            &[0x6a, 0x02, 0xaa],
+            &[0x6a, 0x61],
+            &[0x51],
+            &[],
+        ] {
+            assert!(!is_standard_output_script(script), "{script:?}");
+        }
+        assert_eq!(
+            check_output_script(4, &[0x51]),
+            Err(PolicyError::NonStandardScript { index: 4 })
+        );
+    }
+
+    #[test]
+    fn presets_follow_core_defaults() {
+        assert_eq!(Policy::CORE_V28.max_datacarrier_bytes, 83);
+        assert_eq!(Policy::CORE_V29_1.min_relay_fee.to_sat_per_kvb(), 100);
+        assert_eq!(Policy::CORE_V30.max_datacarrier_bytes, 100_000);
+        assert_eq!(
+            (
+                Policy::CORE_V29_1.multiple_op_returns,
+                Policy::CORE_V30.multiple_op_returns
+            ),
+            (false, true)
+        );
+        assert_eq!(Policy::default(), Policy::CORE_V30);
+    }
+}
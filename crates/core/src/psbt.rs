@@ -1,6 +1,13 @@
-use alloc::{string::String, vec::Vec};
+use alloc::{string::String, vec, vec::Vec};
 
-use crate::tx::{encode_varint, TxInput, TxOutput};
+use crate::{
+    address::Network,
+    policy::is_valid_pubkey,
+    tx::{
+        decode_tx, encode_varint, write_input, write_output, ByteReader, TxInput, TxOutput,
+        TxParams,
+    },
+};
 use base64::Engine;
 use thiserror::Error;
 
@@ -8,45 +15,793 @@ use thiserror::Error;
 pub enum PsbtError {
     #[error("invalid psbt construction")]
     InvalidPsbt,
+    #[error("non-witness UTXO is not a valid transaction")]
+    MalformedNonWitnessUtxo,
+    #[error("non-witness UTXO does not match the spent txid, amount and scriptPubKey")]
+    NonWitnessUtxoMismatch,
+    #[error("BIP32 derivation key is not a valid public key")]
+    InvalidPublicKey,
+    #[error("public key has more than one derivation")]
+    DuplicateKey,
+    #[error("invalid BIP32 derivation path {0:?}")]
+    InvalidDerivationPath(String),
+    #[error("malformed PSBT: {0}")]
+    Malformed(&'static str),
+    #[error("unsupported PSBT version {0}")]
+    UnsupportedVersion(u32),
+    #[error("inputs[{index}] has neither a witness nor a non-witness UTXO")]
+    MissingUtxo { index: usize },
+    #[error("inputs[{index}] has no final scriptSig or witness")]
+    NotFinalized { index: usize },
+    #[error("PSBTv2 needs a transaction version of at least 2, got {0}")]
+    TxVersionTooLow(u32),
 }
 
-pub fn create_psbt(inputs: &[TxInput], outputs: &[TxOutput]) -> Result<Vec<u8>, PsbtError> {
+/// Bit set on hardened BIP32 child indexes.
+pub const BIP32_HARDENED: u32 = 0x8000_0000;
+
+/// Master key fingerprint and derivation path a key was derived along.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeySource {
+    pub fingerprint: [u8; 4],
+    /// Child indexes from the master key, hardened ones with [`BIP32_HARDENED`] set.
+    pub path: Vec<u32>,
+}
+
+/// Origin of an ECDSA key that signs for an input or owns an output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bip32Derivation {
+    /// Compressed (33-byte) or uncompressed (65-byte) public key.
+    pub pubkey: Vec<u8>,
+    pub source: KeySource,
+}
+
+/// Origin of a taproot x-only key, with the hashes of the leaves it appears in
+/// (none for the internal key).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TapKeyDerivation {
+    pub x_only_pubkey: [u8; 32],
+    pub leaf_hashes: Vec<[u8; 32]>,
+    pub source: KeySource,
+}
+
+/// Optional signer metadata of a PSBT input (BIP174/BIP371).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PsbtInputFields {
+    /// Full previous transaction (`PSBT_IN_NON_WITNESS_UTXO`), which some signers
+    /// require to rule out fee attacks on SegWit v0 inputs.
+    pub non_witness_utxo: Option<Vec<u8>>,
+    /// `PSBT_IN_SIGHASH_TYPE`; signers default to `SIGHASH_ALL`.
+    pub sighash_type: Option<u32>,
+    pub bip32_derivations: Vec<Bip32Derivation>,
+    /// `PSBT_IN_TAP_INTERNAL_KEY`.
+    pub tap_internal_key: Option<[u8; 32]>,
+    pub tap_key_derivations: Vec<TapKeyDerivation>,
+    /// `PSBT_IN_TAP_MERKLE_ROOT`, which key-path signers tweak the internal key with.
+    pub tap_merkle_root: Option<[u8; 32]>,
+    /// Pairs not modeled above, such as partial signatures, written back verbatim.
+    pub unknown: Vec<PsbtPair>,
+}
+
+/// Optional metadata of a PSBT output, letting signers recognize change (BIP174/BIP371).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PsbtOutputFields {
+    pub bip32_derivations: Vec<Bip32Derivation>,
+    /// `PSBT_OUT_TAP_INTERNAL_KEY`.
+    pub tap_internal_key: Option<[u8; 32]>,
+    pub tap_key_derivations: Vec<TapKeyDerivation>,
+    /// Pairs not modeled above, such as a taproot tree, written back verbatim.
+    pub unknown: Vec<PsbtPair>,
+}
+
+/// Key-value pair of a PSBT map; the key starts with its type byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PsbtPair {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+impl PsbtInputFields {
+    /// Whether the input carries a signature or a finalized script, which any change
+    /// to the transaction invalidates.
+    pub fn is_signed(&self) -> bool {
+        // PSBT_IN_PARTIAL_SIG, PSBT_IN_FINAL_SCRIPTSIG, PSBT_IN_FINAL_SCRIPTWITNESS,
+        // PSBT_IN_TAP_KEY_SIG and PSBT_IN_TAP_SCRIPT_SIG.
+        self.unknown
+            .iter()
+            .any(|pair| matches!(pair.key.first(), Some(0x02 | 0x07 | 0x08 | 0x13 | 0x14)))
+    }
+}
+
+impl PsbtOutputFields {
+    /// Reject invalid or repeated keys.
+    pub fn check(&self) -> Result<(), PsbtError> {
+        check_derivations(&self.bip32_derivations, &self.tap_key_derivations)
+    }
+}
+
+impl TxInput {
+    /// Reject invalid or repeated keys, and a non-witness UTXO that is not the
+    /// transaction this input spends.
+    pub fn check_psbt_fields(&self) -> Result<(), PsbtError> {
+        if let Some(prev_tx) = &self.psbt.non_witness_utxo {
+            let decoded = decode_tx(prev_tx).map_err(|_| PsbtError::MalformedNonWitnessUtxo)?;
+            let spent = usize::try_from(self.vout)
+                .ok()
+                .and_then(|vout| decoded.outputs.get(vout));
+            let matches = spent.is_some_and(|output| {
+                output.amount == self.amount && output.script_pubkey == self.script_pubkey
+            });
+            if decoded.txid != self.txid || !matches {
+                return Err(PsbtError::NonWitnessUtxoMismatch);
+            }
+        }
+        check_derivations(&self.psbt.bip32_derivations, &self.psbt.tap_key_derivations)
+    }
+}
+
+fn check_derivations(bip32: &[Bip32Derivation], tap: &[TapKeyDerivation]) -> Result<(), PsbtError> {
+    for (idx, derivation) in bip32.iter().enumerate() {
+        if !is_valid_pubkey(&derivation.pubkey) {
+            return Err(PsbtError::InvalidPublicKey);
+        }
+        if bip32[..idx].iter().any(|d| d.pubkey == derivation.pubkey) {
+            return Err(PsbtError::DuplicateKey);
+        }
+    }
+    for (idx, derivation) in tap.iter().enumerate() {
+        if tap[..idx]
+            .iter()
+            .any(|d| d.x_only_pubkey == derivation.x_only_pubkey)
+        {
+            return Err(PsbtError::DuplicateKey);
+        }
+    }
+    Ok(())
+}
+
+/// Parse a path like `m/84'/0'/0'/1/5`; `'` or `h` marks hardened indexes and the
+/// leading `m` is optional.
+pub fn parse_derivation_path(path: &str) -> Result<Vec<u32>, PsbtError> {
+    let invalid = || PsbtError::InvalidDerivationPath(path.into());
+    if path.is_empty() || path == "m" {
+        return Ok(Vec::new());
+    }
+    path.strip_prefix("m/")
+        .unwrap_or(path)
+        .split('/')
+        .map(|step| {
+            let (index, hardened) = match step.strip_suffix(['\'', 'h', 'H']) {
+                Some(index) => (index, true),
+                None => (step, false),
+            };
+            if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            match index.parse::<u32>() {
+                Ok(index) if index < BIP32_HARDENED => Ok(if hardened {
+                    index | BIP32_HARDENED
+                } else {
+                    index
+                }),
+                _ => Err(invalid()),
+            }
+        })
+        .collect()
+}
+
+/// `psbt` followed by the 0xFF separator.
+pub(crate) const PSBT_MAGIC: &[u8] = b"psbt\xff";
+
+/// Identifier of this crate's `PSBT_GLOBAL_PROPRIETARY` (0xFC) fields.
+pub const PSBT_PROPRIETARY_IDENTIFIER: &[u8] = b"zeldhash";
+/// Proprietary subtype whose value is the [`Network::name`] the PSBT was built for.
+pub const PSBT_PROPRIETARY_NETWORK: u8 = 0x00;
+
+/// PSBT serialization format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PsbtVersion {
+    /// BIP174: the unsigned transaction is embedded in the global map.
+    #[default]
+    V0,
+    /// BIP370: transaction fields live in the per-input and per-output maps, and
+    /// `PSBT_GLOBAL_TX_MODIFIABLE` is cleared so signers treat the transaction as final.
+    V2,
+}
+
+/// Serialize a PSBT for the unsigned transaction.
+///
+/// `output_fields` line up with `outputs`; outputs past its end get empty maps.
+/// `global_pairs` are appended to the global map as they are. A pair with an empty
+/// key would read back as a map separator and is rejected.
+pub fn create_psbt(
+    inputs: &[TxInput],
+    outputs: &[TxOutput],
+    output_fields: &[PsbtOutputFields],
+    global_pairs: &[PsbtPair],
+    params: TxParams,
+    network: Network,
+    version: PsbtVersion,
+) -> Result<Vec<u8>, PsbtError> {
     let mut psbt = Vec::new();
-    psbt.extend_from_slice(&[0x70, 0x73, 0x62, 0x74, 0xff]); // magic
-
-    // Global map: PSBT_GLOBAL_UNSIGNED_TX (0x00)
-    // Embed the unsigned transaction using legacy (non-witness) serialization per BIP174.
-    let unsigned_tx = crate::tx::serialize_unsigned_tx(inputs, outputs);
-    psbt.extend(encode_varint(1)); // key length = 1 (just the type)
-    psbt.push(0x00); // key type
-    psbt.extend(encode_varint(unsigned_tx.len() as u64));
-    psbt.extend_from_slice(&unsigned_tx);
+    psbt.extend_from_slice(PSBT_MAGIC);
+
+    match version {
+        PsbtVersion::V0 => {
+            // PSBT_GLOBAL_UNSIGNED_TX (0x00): the unsigned transaction in legacy
+            // (non-witness) serialization per BIP174.
+            let unsigned_tx = crate::tx::serialize_unsigned_tx(inputs, outputs, params);
+            write_pair(&mut psbt, &[0x00], &unsigned_tx);
+        }
+        PsbtVersion::V2 => {
+            if params.version < 2 {
+                return Err(PsbtError::TxVersionTooLow(params.version));
+            }
+            // PSBT_GLOBAL_TX_VERSION (0x02), PSBT_GLOBAL_FALLBACK_LOCKTIME (0x03),
+            // PSBT_GLOBAL_INPUT_COUNT (0x04) and PSBT_GLOBAL_OUTPUT_COUNT (0x05).
+            // No input carries a required locktime, so the fallback is the locktime.
+            write_pair(&mut psbt, &[0x02], &params.version.to_le_bytes());
+            write_pair(&mut psbt, &[0x03], &params.lock_time.to_le_bytes());
+            write_pair(&mut psbt, &[0x04], &encode_varint(inputs.len() as u64));
+            write_pair(&mut psbt, &[0x05], &encode_varint(outputs.len() as u64));
+            // PSBT_GLOBAL_TX_MODIFIABLE (0x06) with no flag set: adding inputs or
+            // outputs, or signing with SIGHASH_SINGLE, would change the mined txid.
+            write_pair(&mut psbt, &[0x06], &[0x00]);
+            write_pair(&mut psbt, &[0xfb], &2u32.to_le_bytes()); // PSBT_GLOBAL_VERSION
+        }
+    }
+
+    // Keeps the network at hand, since the testnet HRP and prefixes are shared.
+    write_pair(&mut psbt, &network_key(), network.name().as_bytes());
+    write_pairs(&mut psbt, global_pairs)?;
     psbt.push(0x00); // separator
 
-    // Input maps: include PSBT_IN_WITNESS_UTXO (0x01)
+    // Input maps: PSBT_IN_WITNESS_UTXO (0x01) always, PSBT_IN_REDEEM_SCRIPT (0x04) for
+    // nested SegWit inputs, and whatever signer metadata the caller provided.
     for input in inputs {
+        let fields = &input.psbt;
+        if let Some(prev_tx) = &fields.non_witness_utxo {
+            write_pair(&mut psbt, &[0x00], prev_tx); // PSBT_IN_NON_WITNESS_UTXO
+        }
+
         let mut witness_utxo = Vec::new();
         witness_utxo.extend_from_slice(&input.amount.to_le_bytes());
         witness_utxo.extend(encode_varint(input.script_pubkey.len() as u64));
         witness_utxo.extend_from_slice(&input.script_pubkey);
+        write_pair(&mut psbt, &[0x01], &witness_utxo); // PSBT_IN_WITNESS_UTXO
 
-        psbt.extend(encode_varint(1)); // key length
-        psbt.push(0x01); // PSBT_IN_WITNESS_UTXO
-        psbt.extend(encode_varint(witness_utxo.len() as u64));
-        psbt.extend_from_slice(&witness_utxo);
+        if let Some(sighash_type) = fields.sighash_type {
+            write_pair(&mut psbt, &[0x03], &sighash_type.to_le_bytes()); // PSBT_IN_SIGHASH_TYPE
+        }
+        if let Some(redeem_script) = &input.redeem_script {
+            write_pair(&mut psbt, &[0x04], redeem_script); // PSBT_IN_REDEEM_SCRIPT
+        }
+        if version == PsbtVersion::V2 {
+            // PSBT_IN_PREVIOUS_TXID (0x0e), PSBT_IN_OUTPUT_INDEX (0x0f) and
+            // PSBT_IN_SEQUENCE (0x10).
+            write_pair(&mut psbt, &[0x0e], &input.txid);
+            write_pair(&mut psbt, &[0x0f], &input.vout.to_le_bytes());
+            write_pair(&mut psbt, &[0x10], &input.sequence.to_le_bytes());
+        }
+        // PSBT_IN_BIP32_DERIVATION (0x06), PSBT_IN_TAP_BIP32_DERIVATION (0x16),
+        // PSBT_IN_TAP_INTERNAL_KEY (0x17) and PSBT_IN_TAP_MERKLE_ROOT (0x18).
+        write_bip32_derivations(&mut psbt, 0x06, &fields.bip32_derivations);
+        write_tap_derivations(&mut psbt, 0x16, &fields.tap_key_derivations);
+        if let Some(internal_key) = fields.tap_internal_key {
+            write_pair(&mut psbt, &[0x17], &internal_key);
+        }
+        if let Some(merkle_root) = fields.tap_merkle_root {
+            write_pair(&mut psbt, &[0x18], &merkle_root);
+        }
+        write_pairs(&mut psbt, &fields.unknown)?;
         psbt.push(0x00); // separator
     }
 
-    // Output maps: empty (separator only)
-    psbt.resize(psbt.len() + outputs.len(), 0x00);
+    // Output maps: PSBT_OUT_AMOUNT (0x03) and PSBT_OUT_SCRIPT (0x04) in PSBTv2, then
+    // PSBT_OUT_BIP32_DERIVATION (0x02), PSBT_OUT_TAP_INTERNAL_KEY (0x05) and
+    // PSBT_OUT_TAP_BIP32_DERIVATION (0x07) when provided.
+    for (idx, output) in outputs.iter().enumerate() {
+        if version == PsbtVersion::V2 {
+            write_pair(&mut psbt, &[0x03], &output.amount.to_le_bytes());
+            write_pair(&mut psbt, &[0x04], &output.script_pubkey);
+        }
+        if let Some(fields) = output_fields.get(idx) {
+            write_bip32_derivations(&mut psbt, 0x02, &fields.bip32_derivations);
+            if let Some(internal_key) = fields.tap_internal_key {
+                write_pair(&mut psbt, &[0x05], &internal_key);
+            }
+            write_tap_derivations(&mut psbt, 0x07, &fields.tap_key_derivations);
+            write_pairs(&mut psbt, &fields.unknown)?;
+        }
+        psbt.push(0x00); // separator
+    }
 
     Ok(psbt)
 }
 
+/// `<keylen><key><valuelen><value>`.
+fn write_pair(psbt: &mut Vec<u8>, key: &[u8], value: &[u8]) {
+    psbt.extend(encode_varint(key.len() as u64));
+    psbt.extend_from_slice(key);
+    psbt.extend(encode_varint(value.len() as u64));
+    psbt.extend_from_slice(value);
+}
+
+fn write_pairs(psbt: &mut Vec<u8>, pairs: &[PsbtPair]) -> Result<(), PsbtError> {
+    for pair in pairs {
+        if pair.key.is_empty() {
+            return Err(PsbtError::InvalidPsbt);
+        }
+        write_pair(psbt, &pair.key, &pair.value);
+    }
+    Ok(())
+}
+
+/// `PSBT_GLOBAL_PROPRIETARY` (0xFC) key of the network field:
+/// `<identifier length><identifier><subtype>`.
+fn network_key() -> Vec<u8> {
+    let mut key = vec![0xfc];
+    key.extend(encode_varint(PSBT_PROPRIETARY_IDENTIFIER.len() as u64));
+    key.extend_from_slice(PSBT_PROPRIETARY_IDENTIFIER);
+    key.push(PSBT_PROPRIETARY_NETWORK);
+    key
+}
+
+/// `<key_type><pubkey>` -> `<fingerprint><path>`.
+fn write_bip32_derivations(psbt: &mut Vec<u8>, key_type: u8, derivations: &[Bip32Derivation]) {
+    for derivation in derivations {
+        let mut key = vec![key_type];
+        key.extend_from_slice(&derivation.pubkey);
+        write_pair(psbt, &key, &encode_key_source(&derivation.source));
+    }
+}
+
+/// `<key_type><x-only pubkey>` -> `<leaf hash count><leaf hashes><fingerprint><path>`.
+fn write_tap_derivations(psbt: &mut Vec<u8>, key_type: u8, derivations: &[TapKeyDerivation]) {
+    for derivation in derivations {
+        let mut key = vec![key_type];
+        key.extend_from_slice(&derivation.x_only_pubkey);
+        let mut value = encode_varint(derivation.leaf_hashes.len() as u64);
+        for leaf_hash in &derivation.leaf_hashes {
+            value.extend_from_slice(leaf_hash);
+        }
+        value.extend(encode_key_source(&derivation.source));
+        write_pair(psbt, &key, &value);
+    }
+}
+
+/// Fingerprint followed by each child index as little-endian u32.
+fn encode_key_source(source: &KeySource) -> Vec<u8> {
+    let mut value = Vec::with_capacity(4 + 4 * source.path.len());
+    value.extend_from_slice(&source.fingerprint);
+    for index in &source.path {
+        value.extend_from_slice(&index.to_le_bytes());
+    }
+    value
+}
+
 pub fn psbt_to_base64(psbt: &[u8]) -> String {
     base64::engine::general_purpose::STANDARD.encode(psbt)
 }
 
+pub fn psbt_from_base64(psbt: &str) -> Result<Vec<u8>, PsbtError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(psbt.trim())
+        .map_err(|_| PsbtError::Malformed("invalid base64"))
+}
+
+/// A PSBT read back by [`parse_psbt`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedPsbt {
+    pub version: PsbtVersion,
+    pub tx_params: TxParams,
+    /// Spent outputs from `PSBT_IN_WITNESS_UTXO`, or else the non-witness UTXO, with
+    /// redeem scripts and signer metadata. Witness hints are left unset.
+    pub inputs: Vec<TxInput>,
+    pub outputs: Vec<TxOutput>,
+    /// Metadata of each entry of `outputs`.
+    pub output_fields: Vec<PsbtOutputFields>,
+    /// Network recorded by [`create_psbt`], if any.
+    pub network: Option<Network>,
+    /// Global pairs not modeled here, such as extended public keys.
+    pub global_pairs: Vec<PsbtPair>,
+}
+
+/// Decode a BIP174 (v0) or BIP370 (v2) PSBT.
+///
+/// Every pair this crate does not model is kept in the `unknown` or `global_pairs`
+/// lists, so [`create_psbt`] can write it back.
+pub fn parse_psbt(bytes: &[u8]) -> Result<ParsedPsbt, PsbtError> {
+    let body = bytes
+        .strip_prefix(PSBT_MAGIC)
+        .ok_or(PsbtError::Malformed("missing magic bytes"))?;
+    let mut reader = ByteReader::new(body);
+
+    let mut psbt_version = 0;
+    let mut unsigned_tx = None;
+    let mut v2_globals = V2Globals::default();
+    let mut network = None;
+    let mut global_pairs = Vec::new();
+    for pair in read_map(&mut reader)? {
+        match pair.key.as_slice() {
+            [0x00] => unsigned_tx = Some(pair.value),
+            [0x02] => v2_globals.tx_version = Some(read_u32(&pair.value)?),
+            [0x03] => v2_globals.fallback_lock_time = Some(read_u32(&pair.value)?),
+            [0x04] => v2_globals.input_count = Some(read_count(&pair.value)?),
+            [0x05] => v2_globals.output_count = Some(read_count(&pair.value)?),
+            // PSBT_GLOBAL_TX_MODIFIABLE: `create_psbt` writes it back cleared.
+            [0x06] => v2_globals.tx_modifiable = Some(read_array::<1>(&pair.value)?[0]),
+            [0xfb] => psbt_version = read_u32(&pair.value)?,
+            key if *key == network_key()[..] => {
+                let name = core::str::from_utf8(&pair.value)
+                    .map_err(|_| PsbtError::Malformed("invalid network name"))?;
+                network = Some(
+                    name.parse()
+                        .map_err(|_| PsbtError::Malformed("unknown network name"))?,
+                );
+            }
+            _ => global_pairs.push(pair),
+        }
+    }
+    let has_v2_globals = v2_globals != V2Globals::default();
+
+    let version = match psbt_version {
+        0 => PsbtVersion::V0,
+        2 => PsbtVersion::V2,
+        other => return Err(PsbtError::UnsupportedVersion(other)),
+    };
+    let v2 = version == PsbtVersion::V2;
+    // Outpoints and sequences of a v0 PSBT come from its unsigned transaction.
+    let (mut tx_params, unsigned_inputs, unsigned_outputs, input_count, output_count) =
+        match (version, unsigned_tx) {
+            (PsbtVersion::V0, Some(tx)) if !has_v2_globals => {
+                let tx = decode_tx(&tx)
+                    .map_err(|_| PsbtError::Malformed("invalid unsigned transaction"))?;
+                if tx.inputs.iter().any(|input| !input.script_sig.is_empty()) {
+                    return Err(PsbtError::Malformed("unsigned transaction has scriptSigs"));
+                }
+                let counts = (tx.inputs.len() as u64, tx.outputs.len() as u64);
+                (tx.params, tx.inputs, tx.outputs, counts.0, counts.1)
+            }
+            (PsbtVersion::V2, None) => {
+                let missing = || PsbtError::Malformed("missing PSBTv2 global field");
+                let params = TxParams {
+                    version: v2_globals.tx_version.ok_or_else(missing)?,
+                    lock_time: v2_globals.fallback_lock_time.unwrap_or(0),
+                };
+                let input_count = v2_globals.input_count.ok_or_else(missing)?;
+                let output_count = v2_globals.output_count.ok_or_else(missing)?;
+                (params, Vec::new(), Vec::new(), input_count, output_count)
+            }
+            _ => {
+                return Err(PsbtError::Malformed(
+                    "global fields do not match the PSBT version",
+                ))
+            }
+        };
+
+    let mut inputs = Vec::new();
+    let mut lock_times = Vec::new();
+    for index in 0..input_count as usize {
+        let map = read_input_map(&mut reader, v2)?;
+        let (txid, vout, sequence) = match unsigned_inputs.get(index) {
+            Some(input) => (input.txid, input.vout, input.sequence),
+            None => (
+                map.txid
+                    .ok_or(PsbtError::Malformed("missing PSBT_IN_PREVIOUS_TXID"))?,
+                map.vout
+                    .ok_or(PsbtError::Malformed("missing PSBT_IN_OUTPUT_INDEX"))?,
+                map.sequence.unwrap_or(u32::MAX),
+            ),
+        };
+        let spent = match (map.witness_utxo, &map.fields.non_witness_utxo) {
+            (Some(utxo), _) => utxo,
+            (None, Some(prev_tx)) => {
+                let prev_tx = decode_tx(prev_tx).map_err(|_| PsbtError::MalformedNonWitnessUtxo)?;
+                let spent = usize::try_from(vout)
+                    .ok()
+                    .and_then(|vout| prev_tx.outputs.get(vout));
+                match spent {
+                    Some(output) if prev_tx.txid == txid => output.clone(),
+                    _ => return Err(PsbtError::NonWitnessUtxoMismatch),
+                }
+            }
+            (None, None) => return Err(PsbtError::MissingUtxo { index }),
+        };
+        lock_times.push(map.required_lock_times);
+        inputs.push(TxInput {
+            txid,
+            vout,
+            script_pubkey: spent.script_pubkey,
+            amount: spent.amount,
+            sequence,
+            witness_hint: None,
+            redeem_script: map.redeem_script,
+            psbt: map.fields,
+        });
+    }
+    if v2 {
+        tx_params.lock_time = required_lock_time(&lock_times)?.unwrap_or(tx_params.lock_time);
+    }
+
+    let mut outputs = Vec::new();
+    let mut output_fields = Vec::new();
+    for index in 0..output_count as usize {
+        let (output, fields) = read_output_map(&mut reader, v2)?;
+        let output = match unsigned_outputs.get(index) {
+            Some(output) => output.clone(),
+            None => output.ok_or(PsbtError::Malformed(
+                "missing PSBT_OUT_AMOUNT or PSBT_OUT_SCRIPT",
+            ))?,
+        };
+        outputs.push(output);
+        output_fields.push(fields);
+    }
+    if !reader.rest().is_empty() {
+        return Err(PsbtError::Malformed("trailing data"));
+    }
+
+    Ok(ParsedPsbt {
+        version,
+        tx_params,
+        inputs,
+        outputs,
+        output_fields,
+        network,
+        global_pairs,
+    })
+}
+
+/// Network serialization of a finalized PSBT: the transaction with each input's
+/// `PSBT_IN_FINAL_SCRIPTSIG` and `PSBT_IN_FINAL_SCRIPTWITNESS` attached.
+pub fn extract_tx(psbt: &ParsedPsbt) -> Result<Vec<u8>, PsbtError> {
+    let mut finals = Vec::with_capacity(psbt.inputs.len());
+    for (index, input) in psbt.inputs.iter().enumerate() {
+        let field = |type_byte: u8| {
+            input
+                .psbt
+                .unknown
+                .iter()
+                .find(|pair| pair.key == [type_byte])
+                .map(|pair| pair.value.as_slice())
+        };
+        let (script_sig, witness) = (field(0x07), field(0x08));
+        if script_sig.is_none() && witness.is_none() {
+            return Err(PsbtError::NotFinalized { index });
+        }
+        finals.push((script_sig.unwrap_or_default(), witness));
+    }
+    let segwit = finals.iter().any(|(_, witness)| witness.is_some());
+
+    let mut tx = Vec::new();
+    tx.extend_from_slice(&psbt.tx_params.version.to_le_bytes());
+    if segwit {
+        tx.extend_from_slice(&[0x00, 0x01]);
+    }
+    tx.extend(encode_varint(psbt.inputs.len() as u64));
+    for (input, (script_sig, _)) in psbt.inputs.iter().zip(&finals) {
+        write_input(&mut tx, input, script_sig);
+    }
+    tx.extend(encode_varint(psbt.outputs.len() as u64));
+    for output in &psbt.outputs {
+        write_output(&mut tx, output);
+    }
+    if segwit {
+        for (_, witness) in &finals {
+            // The field holds the serialized stack; an empty stack is a single zero.
+            tx.extend_from_slice(witness.unwrap_or(&[0x00]));
+        }
+    }
+    tx.extend_from_slice(&psbt.tx_params.lock_time.to_le_bytes());
+    Ok(tx)
+}
+
+/// Transaction fields of a PSBTv2 global map.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct V2Globals {
+    tx_version: Option<u32>,
+    fallback_lock_time: Option<u32>,
+    input_count: Option<u64>,
+    output_count: Option<u64>,
+    tx_modifiable: Option<u8>,
+}
+
+/// Fields of one input map, before they are combined with the global map.
+#[derive(Default)]
+struct InputMap {
+    fields: PsbtInputFields,
+    witness_utxo: Option<TxOutput>,
+    redeem_script: Option<Vec<u8>>,
+    txid: Option<[u8; 32]>,
+    vout: Option<u32>,
+    sequence: Option<u32>,
+    /// `PSBT_IN_REQUIRED_TIME_LOCKTIME` and `PSBT_IN_REQUIRED_HEIGHT_LOCKTIME`.
+    required_lock_times: (Option<u32>, Option<u32>),
+}
+
+fn read_input_map(reader: &mut ByteReader<'_>, v2: bool) -> Result<InputMap, PsbtError> {
+    let mut map = InputMap::default();
+    for pair in read_map(reader)? {
+        match pair.key.as_slice() {
+            [0x00] => map.fields.non_witness_utxo = Some(pair.value),
+            [0x01] => {
+                let mut value = ByteReader::new(&pair.value);
+                let utxo = (|| {
+                    let amount = value.u64().ok()?;
+                    let script_pubkey = value.var_bytes().ok()?.to_vec();
+                    value.rest().is_empty().then_some(TxOutput {
+                        script_pubkey,
+                        amount,
+                    })
+                })();
+                map.witness_utxo =
+                    Some(utxo.ok_or(PsbtError::Malformed("invalid PSBT_IN_WITNESS_UTXO"))?);
+            }
+            [0x03] => map.fields.sighash_type = Some(read_u32(&pair.value)?),
+            [0x04] => map.redeem_script = Some(pair.value),
+            [0x06, pubkey @ ..] => map.fields.bip32_derivations.push(Bip32Derivation {
+                pubkey: pubkey.to_vec(),
+                source: read_key_source(&pair.value)?,
+            }),
+            [0x0e] if v2 => map.txid = Some(read_array(&pair.value)?),
+            [0x0f] if v2 => map.vout = Some(read_u32(&pair.value)?),
+            [0x10] if v2 => map.sequence = Some(read_u32(&pair.value)?),
+            // Kept verbatim so signers compute the same locktime.
+            [0x11] if v2 => {
+                map.required_lock_times.0 = Some(read_u32(&pair.value)?);
+                map.fields.unknown.push(pair);
+            }
+            [0x12] if v2 => {
+                map.required_lock_times.1 = Some(read_u32(&pair.value)?);
+                map.fields.unknown.push(pair);
+            }
+            [0x0e..=0x12] => return Err(PsbtError::Malformed("PSBTv2 input field in a v0 PSBT")),
+            [0x16, x_only_pubkey @ ..] => map
+                .fields
+                .tap_key_derivations
+                .push(read_tap_derivation(x_only_pubkey, &pair.value)?),
+            [0x17] => map.fields.tap_internal_key = Some(read_array(&pair.value)?),
+            [0x18] => map.fields.tap_merkle_root = Some(read_array(&pair.value)?),
+            _ => map.fields.unknown.push(pair),
+        }
+    }
+    check_derivations(
+        &map.fields.bip32_derivations,
+        &map.fields.tap_key_derivations,
+    )?;
+    Ok(map)
+}
+
+/// An output map, with the output itself when the PSBT is v2.
+fn read_output_map(
+    reader: &mut ByteReader<'_>,
+    v2: bool,
+) -> Result<(Option<TxOutput>, PsbtOutputFields), PsbtError> {
+    let mut fields = PsbtOutputFields::default();
+    let (mut amount, mut script_pubkey) = (None, None);
+    for pair in read_map(reader)? {
+        match pair.key.as_slice() {
+            [0x02, pubkey @ ..] => fields.bip32_derivations.push(Bip32Derivation {
+                pubkey: pubkey.to_vec(),
+                source: read_key_source(&pair.value)?,
+            }),
+            [0x03] if v2 => {
+                let amount_value = i64::from_le_bytes(read_array(&pair.value)?);
+                amount = Some(
+                    u64::try_from(amount_value)
+                        .map_err(|_| PsbtError::Malformed("negative PSBT_OUT_AMOUNT"))?,
+                );
+            }
+            [0x04] if v2 => script_pubkey = Some(pair.value),
+            [0x03 | 0x04] => return Err(PsbtError::Malformed("PSBTv2 output field in a v0 PSBT")),
+            [0x05] => fields.tap_internal_key = Some(read_array(&pair.value)?),
+            [0x07, x_only_pubkey @ ..] => fields
+                .tap_key_derivations
+                .push(read_tap_derivation(x_only_pubkey, &pair.value)?),
+            _ => fields.unknown.push(pair),
+        }
+    }
+    fields.check()?;
+    let output = match (amount, script_pubkey) {
+        (Some(amount), Some(script_pubkey)) => Some(TxOutput {
+            script_pubkey,
+            amount,
+        }),
+        _ => None,
+    };
+    Ok((output, fields))
+}
+
+/// Key-value pairs up to the next separator, rejecting repeated keys.
+fn read_map(reader: &mut ByteReader<'_>) -> Result<Vec<PsbtPair>, PsbtError> {
+    let truncated = |_| PsbtError::Malformed("truncated map");
+    let mut pairs: Vec<PsbtPair> = Vec::new();
+    loop {
+        let key = reader.var_bytes().map_err(truncated)?;
+        if key.is_empty() {
+            return Ok(pairs);
+        }
+        let value = reader.var_bytes().map_err(truncated)?;
+        if pairs.iter().any(|pair| pair.key == key) {
+            return Err(PsbtError::Malformed("duplicate key"));
+        }
+        pairs.push(PsbtPair {
+            key: key.to_vec(),
+            value: value.to_vec(),
+        });
+    }
+}
+
+/// Locktime BIP370 derives from the inputs' required locktimes: the highest required
+/// height when every such input accepts one, or else the highest required time.
+fn required_lock_time(required: &[(Option<u32>, Option<u32>)]) -> Result<Option<u32>, PsbtError> {
+    let constrained: Vec<_> = required
+        .iter()
+        .filter(|(time, height)| time.is_some() || height.is_some())
+        .collect();
+    if constrained.is_empty() {
+        return Ok(None);
+    }
+    if constrained.iter().all(|(_, height)| height.is_some()) {
+        return Ok(constrained.iter().filter_map(|(_, height)| *height).max());
+    }
+    if constrained.iter().all(|(time, _)| time.is_some()) {
+        return Ok(constrained.iter().filter_map(|(time, _)| *time).max());
+    }
+    Err(PsbtError::Malformed(
+        "inputs require incompatible locktime types",
+    ))
+}
+
+/// Inverse of `write_tap_derivations` for one pair.
+fn read_tap_derivation(x_only_pubkey: &[u8], value: &[u8]) -> Result<TapKeyDerivation, PsbtError> {
+    let mut reader = ByteReader::new(value);
+    let malformed = |_| PsbtError::Malformed("invalid taproot key origin");
+    let mut leaf_hashes = Vec::new();
+    for _ in 0..reader.varint().map_err(malformed)? {
+        leaf_hashes.push(reader.array().map_err(malformed)?);
+    }
+    Ok(TapKeyDerivation {
+        x_only_pubkey: read_array(x_only_pubkey)?,
+        leaf_hashes,
+        source: read_key_source(reader.rest())?,
+    })
+}
+
+/// Inverse of `encode_key_source`.
+fn read_key_source(value: &[u8]) -> Result<KeySource, PsbtError> {
+    if value.len() < 4 || value.len() % 4 != 0 {
+        return Err(PsbtError::Malformed("invalid key origin"));
+    }
+    let (fingerprint, path) = value.split_at(4);
+    Ok(KeySource {
+        fingerprint: read_array(fingerprint)?,
+        path: path
+            .chunks_exact(4)
+            .map(|index| u32::from_le_bytes([index[0], index[1], index[2], index[3]]))
+            .collect(),
+    })
+}
+
+fn read_array<const N: usize>(value: &[u8]) -> Result<[u8; N], PsbtError> {
+    value
+        .try_into()
+        .map_err(|_| PsbtError::Malformed("field has the wrong length"))
+}
+
+fn read_u32(value: &[u8]) -> Result<u32, PsbtError> {
+    read_array(value).map(u32::from_le_bytes)
+}
+
+/// A compact-size count filling the whole value.
+fn read_count(value: &[u8]) -> Result<u64, PsbtError> {
+    let mut reader = ByteReader::new(value);
+    match reader.varint() {
+        Ok(count) if reader.rest().is_empty() => Ok(count),
+        _ => Err(PsbtError::Malformed("invalid count")),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,6 +816,9 @@ mod tests {
             script_pubkey: spk.clone(),
             amount: 75_000,
             sequence: crate::tx::DEFAULT_SEQUENCE,
+            witness_hint: None,
+            redeem_script: None,
+            psbt: Default::default(),
         };
         let output = TxOutput {
             script_pubkey: spk,
@@ -72,7 +830,16 @@ mod tests {
     #[test]
     fn builds_psbt_with_witness_utxo() {
         let (input, output) = sample_input_output();
-        let psbt = create_psbt(&[input], &[output]).unwrap();
+        let psbt = create_psbt(
+            &[input],
+            &[output],
+            &[],
+            &[],
+            TxParams::default(),
+            Network::Mainnet,
+            PsbtVersion::V0,
+        )
+        .unwrap();
         assert_eq!(&psbt[..5], b"psbt\xff");
         let b64 = psbt_to_base64(&psbt);
         assert!(!b64.is_empty());
@@ -81,9 +848,23 @@ mod tests {
     #[test]
     fn parses_psbt_with_bitcoin_crate() {
         let (input, output) = sample_input_output();
-        let psbt_bytes = create_psbt(&[input.clone()], &[output.clone()]).unwrap();
+        let psbt_bytes = create_psbt(
+            &[input.clone()],
+            &[output.clone()],
+            &[],
+            &[],
+            TxParams::default(),
+            Network::Signet,
+            PsbtVersion::V0,
+        )
+        .unwrap();
         let parsed = Psbt::deserialize(&psbt_bytes).expect("psbt must round-trip");
 
+        let (key, network) = parsed.proprietary.iter().next().expect("network recorded");
+        assert_eq!(key.prefix, PSBT_PROPRIETARY_IDENTIFIER);
+        assert_eq!(key.subtype, PSBT_PROPRIETARY_NETWORK);
+        assert_eq!(network, b"signet");
+
         assert_eq!(parsed.unsigned_tx.input.len(), 1);
         assert_eq!(parsed.unsigned_tx.output.len(), 1);
 
@@ -94,4 +875,524 @@ mod tests {
         assert_eq!(witness_utxo.value, bitcoin::Amount::from_sat(input.amount));
         assert_eq!(witness_utxo.script_pubkey.as_bytes(), output.script_pubkey);
     }
+
+    /// Secp256k1 generator point, a public key every parser accepts.
+    const GENERATOR: [u8; 33] = [
+        0x02, 0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87,
+        0x0b, 0x07, 0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b, 0x16,
+        0xf8, 0x17, 0x98,
+    ];
+
+    fn key_source(path: &str) -> KeySource {
+        KeySource {
+            fingerprint: [0xd3, 0x4d, 0xb3, 0x3f],
+            path: parse_derivation_path(path).unwrap(),
+        }
+    }
+
+    #[test]
+    fn writes_key_origins_and_non_witness_utxo() {
+        let (mut input, output) = sample_input_output();
+        let prev_outputs = [
+            TxOutput {
+                script_pubkey: vec![0x51, 0x20, 0x77],
+                amount: 1,
+            },
+            TxOutput {
+                script_pubkey: input.script_pubkey.clone(),
+                amount: input.amount,
+            },
+        ];
+        let prev_tx =
+            crate::tx::serialize_tx_for_txid(&[input.clone()], &prev_outputs, TxParams::default());
+        input.txid = crate::hash::double_sha256(&prev_tx);
+        input.vout = 1;
+        let x_only: [u8; 32] = GENERATOR[1..].try_into().unwrap();
+        input.psbt = PsbtInputFields {
+            non_witness_utxo: Some(prev_tx.clone()),
+            sighash_type: Some(0x01),
+            bip32_derivations: vec![Bip32Derivation {
+                pubkey: GENERATOR.to_vec(),
+                source: key_source("m/84'/0'/0'/0/7"),
+            }],
+            tap_internal_key: Some(x_only),
+            tap_key_derivations: vec![TapKeyDerivation {
+                x_only_pubkey: x_only,
+                leaf_hashes: vec![[0x42; 32]],
+                source: key_source("m/86h/0h/0h/0/7"),
+            }],
+            tap_merkle_root: Some([0x24; 32]),
+            unknown: Vec::new(),
+        };
+        input.check_psbt_fields().expect("fields are consistent");
+        let change = PsbtOutputFields {
+            bip32_derivations: vec![Bip32Derivation {
+                pubkey: GENERATOR.to_vec(),
+                source: key_source("m/84'/0'/0'/1/3"),
+            }],
+            tap_internal_key: Some(x_only),
+            tap_key_derivations: Vec::new(),
+            unknown: Vec::new(),
+        };
+
+        let psbt_bytes = create_psbt(
+            &[input],
+            &[output.clone(), output],
+            &[change],
+            &[],
+            TxParams::default(),
+            Network::Mainnet,
+            PsbtVersion::V0,
+        )
+        .unwrap();
+        let parsed = Psbt::deserialize(&psbt_bytes).expect("psbt must round-trip");
+        let parsed_input = &parsed.inputs[0];
+        assert_eq!(
+            parsed_input
+                .non_witness_utxo
+                .as_ref()
+                .map(|tx| tx.compute_txid()),
+            Some(parsed.unsigned_tx.input[0].previous_output.txid)
+        );
+        assert_eq!(parsed_input.sighash_type.map(|s| s.to_u32()), Some(0x01));
+        let (key, (fingerprint, path)) = parsed_input.bip32_derivation.iter().next().unwrap();
+        assert_eq!(key.serialize(), GENERATOR);
+        assert_eq!(fingerprint.to_bytes(), [0xd3, 0x4d, 0xb3, 0x3f]);
+        assert_eq!(path.to_string(), "84'/0'/0'/0/7");
+        assert_eq!(
+            parsed_input.tap_internal_key.map(|k| k.serialize()),
+            Some(x_only)
+        );
+        let (leaves, (_, tap_path)) = parsed_input.tap_key_origins.values().next().unwrap();
+        assert_eq!(leaves.len(), 1);
+        assert_eq!(tap_path.to_string(), "86'/0'/0'/0/7");
+
+        let (_, (_, change_path)) = parsed.outputs[0].bip32_derivation.iter().next().unwrap();
+        assert_eq!(change_path.to_string(), "84'/0'/0'/1/3");
+        assert!(parsed.outputs[0].tap_internal_key.is_some());
+        assert!(parsed.outputs[1].bip32_derivation.is_empty());
+    }
+
+    /// `(key, value)` pairs of each map after the magic: global, inputs, then outputs.
+    fn read_maps(psbt: &[u8]) -> Vec<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut reader = crate::tx::ByteReader::new(&psbt[5..]);
+        let mut maps = vec![Vec::new()];
+        while !reader.rest().is_empty() {
+            let key = reader.var_bytes().unwrap();
+            if key.is_empty() {
+                maps.push(Vec::new());
+                continue;
+            }
+            let value = reader.var_bytes().unwrap();
+            maps.last_mut()
+                .unwrap()
+                .push((key.to_vec(), value.to_vec()));
+        }
+        maps.pop(); // opened by the last separator
+        maps
+    }
+
+    #[test]
+    fn writes_psbt_v2_fields() {
+        let (mut input, output) = sample_input_output();
+        input.vout = 3;
+        input.sequence = 0xffff_fffd;
+        let params = TxParams {
+            version: 3,
+            lock_time: 850_000,
+        };
+        let build = |version| {
+            create_psbt(
+                &[input.clone()],
+                &[output.clone()],
+                &[],
+                &[],
+                params,
+                Network::Mainnet,
+                version,
+            )
+            .unwrap()
+        };
+        let key_types =
+            |map: &[(Vec<u8>, Vec<u8>)]| map.iter().map(|(k, _)| k[0]).collect::<Vec<_>>();
+
+        let v0 = read_maps(&build(PsbtVersion::V0));
+        assert_eq!(key_types(&v0[0]), [0x00, 0xfc]);
+
+        let v2 = read_maps(&build(PsbtVersion::V2));
+        assert_eq!(v2.len(), 3);
+        let (global, input_map, output_map) = (&v2[0], &v2[1], &v2[2]);
+        assert_eq!(
+            key_types(global),
+            [0x02, 0x03, 0x04, 0x05, 0x06, 0xfb, 0xfc]
+        );
+        let values =
+            |map: &[(Vec<u8>, Vec<u8>)]| map.iter().map(|(_, v)| v.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            values(global)[..6],
+            [
+                3u32.to_le_bytes().to_vec(),
+                850_000u32.to_le_bytes().to_vec(),
+                vec![1],
+                vec![1],
+                vec![0x00],
+                2u32.to_le_bytes().to_vec(),
+            ]
+        );
+
+        assert_eq!(key_types(input_map), [0x01, 0x0e, 0x0f, 0x10]);
+        assert_eq!(
+            values(input_map)[1..],
+            [
+                input.txid.to_vec(),
+                3u32.to_le_bytes().to_vec(),
+                0xffff_fffdu32.to_le_bytes().to_vec(),
+            ]
+        );
+        assert_eq!(key_types(output_map), [0x03, 0x04]);
+        assert_eq!(
+            values(output_map),
+            [
+                output.amount.to_le_bytes().to_vec(),
+                output.script_pubkey.clone()
+            ]
+        );
+
+        // BIP370 requires version 2 transactions or later.
+        let v1 = TxParams {
+            version: 1,
+            ..params
+        };
+        assert_eq!(
+            create_psbt(
+                &[input],
+                &[output],
+                &[],
+                &[],
+                v1,
+                Network::Mainnet,
+                PsbtVersion::V2
+            ),
+            Err(PsbtError::TxVersionTooLow(1))
+        );
+    }
+
+    #[test]
+    fn rejects_inconsistent_signer_fields() {
+        let (mut input, _) = sample_input_output();
+        input.psbt.non_witness_utxo = Some(vec![0x02, 0x00]);
+        assert_eq!(
+            input.check_psbt_fields(),
+            Err(PsbtError::MalformedNonWitnessUtxo)
+        );
+        input.psbt.non_witness_utxo = Some(crate::tx::serialize_tx_for_txid(
+            &[input.clone()],
+            &[],
+            TxParams::default(),
+        ));
+        assert_eq!(
+            input.check_psbt_fields(),
+            Err(PsbtError::NonWitnessUtxoMismatch)
+        );
+
+        let derivation = Bip32Derivation {
+            pubkey: GENERATOR.to_vec(),
+            source: KeySource::default(),
+        };
+        let mut fields = PsbtOutputFields {
+            bip32_derivations: vec![derivation.clone(), derivation],
+            ..Default::default()
+        };
+        assert_eq!(fields.check(), Err(PsbtError::DuplicateKey));
+        fields.bip32_derivations.truncate(1);
+        fields.bip32_derivations[0].pubkey.truncate(32);
+        assert_eq!(fields.check(), Err(PsbtError::InvalidPublicKey));
+    }
+
+    #[test]
+    fn parsed_psbts_write_back_unchanged() {
+        let (mut input, output) = sample_input_output();
+        input.sequence = 0xffff_fffd;
+        input.psbt = PsbtInputFields {
+            sighash_type: Some(0x01),
+            bip32_derivations: vec![Bip32Derivation {
+                pubkey: GENERATOR.to_vec(),
+                source: key_source("m/84'/0'/0'/0/7"),
+            }],
+            // PSBT_IN_TAP_LEAF_SCRIPT, which this crate does not model.
+            unknown: vec![PsbtPair {
+                key: vec![0x15, 0xc0],
+                value: vec![0x51, 0xc0],
+            }],
+            ..Default::default()
+        };
+        let change = PsbtOutputFields {
+            tap_internal_key: Some(GENERATOR[1..].try_into().unwrap()),
+            tap_key_derivations: vec![TapKeyDerivation {
+                x_only_pubkey: GENERATOR[1..].try_into().unwrap(),
+                leaf_hashes: vec![[0x42; 32]],
+                source: key_source("m/86'/0'/0'/1/2"),
+            }],
+            unknown: vec![PsbtPair {
+                key: vec![0x06],
+                value: vec![0x00, 0xc0, 0x01, 0x51],
+            }],
+            ..Default::default()
+        };
+        let global = [PsbtPair {
+            key: [&[0xfc, 0x03][..], b"abc", &[0x01]].concat(),
+            value: vec![0x2a],
+        }];
+        let params = TxParams {
+            version: 3,
+            lock_time: 850_000,
+        };
+
+        for version in [PsbtVersion::V0, PsbtVersion::V2] {
+            let outputs = [output.clone(), output.clone()];
+            let fields = [PsbtOutputFields::default(), change.clone()];
+            let bytes = create_psbt(
+                &[input.clone()],
+                &outputs,
+                &fields,
+                &global,
+                params,
+                Network::Testnet4,
+                version,
+            )
+            .unwrap();
+            let parsed = parse_psbt(&bytes).expect("psbt parses");
+            assert_eq!(parsed.version, version);
+            assert_eq!(parsed.tx_params, params);
+            assert_eq!(parsed.inputs, [input.clone()]);
+            assert_eq!(parsed.outputs, outputs);
+            assert_eq!(parsed.output_fields, fields);
+            assert_eq!(parsed.network, Some(Network::Testnet4));
+            assert_eq!(parsed.global_pairs, global);
+            assert!(!parsed.inputs[0].psbt.is_signed());
+
+            let rewritten = create_psbt(
+                &parsed.inputs,
+                &parsed.outputs,
+                &parsed.output_fields,
+                &parsed.global_pairs,
+                parsed.tx_params,
+                Network::Testnet4,
+                parsed.version,
+            )
+            .unwrap();
+            assert_eq!(rewritten, bytes);
+
+            // An empty key would be written as the map separator.
+            let mut empty_key = parsed.inputs.clone();
+            empty_key[0].psbt.unknown.push(PsbtPair {
+                key: Vec::new(),
+                value: vec![0x01],
+            });
+            assert_eq!(
+                create_psbt(
+                    &empty_key,
+                    &parsed.outputs,
+                    &parsed.output_fields,
+                    &parsed.global_pairs,
+                    parsed.tx_params,
+                    Network::Testnet4,
+                    parsed.version,
+                ),
+                Err(PsbtError::InvalidPsbt)
+            );
+        }
+
+        // BIP370: inputs' required locktimes take precedence over the fallback.
+        input.psbt.unknown = vec![PsbtPair {
+            key: vec![0x12],
+            value: 900_000u32.to_le_bytes().to_vec(),
+        }];
+        let bytes = create_psbt(
+            &[input],
+            &[output],
+            &[],
+            &[],
+            params,
+            Network::Mainnet,
+            PsbtVersion::V2,
+        )
+        .unwrap();
+        assert_eq!(parse_psbt(&bytes).unwrap().tx_params.lock_time, 900_000);
+    }
+
+    #[test]
+    fn parses_psbts_from_other_writers() {
+        use bitcoin::{
+            absolute, transaction, Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut,
+            Witness,
+        };
+
+        let (input, _) = sample_input_output();
+        let prev_tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(input.amount),
+                script_pubkey: ScriptBuf::from_bytes(input.script_pubkey.clone()),
+            }],
+        };
+        let unsigned = Transaction {
+            input: vec![TxIn {
+                previous_output: OutPoint::new(prev_tx.compute_txid(), 0),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                ..prev_tx.input[0].clone()
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(60_000),
+                script_pubkey: ScriptBuf::from_bytes(vec![0x6a, 0x01, 0x2a]),
+            }],
+            ..prev_tx.clone()
+        };
+        let mut psbt = Psbt::from_unsigned_tx(unsigned).unwrap();
+        let bare = psbt.serialize();
+        psbt.inputs[0].non_witness_utxo = Some(prev_tx);
+
+        let parsed = parse_psbt(&psbt.serialize()).expect("psbt parses");
+        let parsed_input = &parsed.inputs[0];
+        assert_eq!(parsed.version, PsbtVersion::V0);
+        assert_eq!(parsed.network, None);
+        assert_eq!(
+            (parsed_input.amount, &parsed_input.script_pubkey),
+            (input.amount, &input.script_pubkey)
+        );
+        assert_eq!(parsed_input.sequence, 0xffff_fffd);
+        assert_eq!(parsed.outputs[0].amount, 60_000);
+        parsed_input
+            .check_psbt_fields()
+            .expect("non-witness UTXO matches");
+
+        assert_eq!(parse_psbt(&bare), Err(PsbtError::MissingUtxo { index: 0 }));
+        assert_eq!(
+            parse_psbt(&bare[1..]),
+            Err(PsbtError::Malformed("missing magic bytes"))
+        );
+        assert_eq!(
+            parse_psbt(&bare[..20]),
+            Err(PsbtError::Malformed("truncated map"))
+        );
+        let mut v2_field = bare[..bare.len() - 2].to_vec();
+        v2_field.extend([0x01, 0x0f, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(
+            parse_psbt(&v2_field),
+            Err(PsbtError::Malformed("PSBTv2 input field in a v0 PSBT"))
+        );
+        let mut short_merkle_root = bare[..bare.len() - 2].to_vec();
+        short_merkle_root.extend([0x01, 0x18, 0x04, 0x77, 0x77, 0x77, 0x77, 0x00, 0x00]);
+        assert_eq!(
+            parse_psbt(&short_merkle_root),
+            Err(PsbtError::Malformed("field has the wrong length"))
+        );
+    }
+
+    #[test]
+    fn parses_derivation_paths() {
+        assert_eq!(
+            parse_derivation_path("m/84'/1h/0H/1/5"),
+            Ok(vec![
+                84 | BIP32_HARDENED,
+                1 | BIP32_HARDENED,
+                BIP32_HARDENED,
+                1,
+                5
+            ])
+        );
+        assert_eq!(parse_derivation_path("0/2"), Ok(vec![0, 2]));
+        assert_eq!(parse_derivation_path("m"), Ok(Vec::new()));
+        for bad in [
+            "m/",
+            "m//1",
+            "m/1/",
+            "m/-1",
+            "m/2147483648",
+            "m/1''",
+            "n/1",
+            "m1",
+        ] {
+            assert_eq!(
+                parse_derivation_path(bad),
+                Err(PsbtError::InvalidDerivationPath(bad.into())),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn includes_redeem_script_for_nested_segwit() {
+        let (mut input, output) = sample_input_output();
+        let program = input.script_pubkey.clone();
+        input.script_pubkey = vec![0xa9, 0x14];
+        input.script_pubkey.extend([0x66; 20]);
+        input.script_pubkey.push(0x87);
+        input.redeem_script = Some(program.clone());
+
+        let psbt_bytes = create_psbt(
+            &[input],
+            &[output],
+            &[],
+            &[],
+            TxParams::default(),
+            Network::Mainnet,
+            PsbtVersion::V0,
+        )
+        .unwrap();
+        let parsed = Psbt::deserialize(&psbt_bytes).expect("psbt must round-trip");
+        assert!(parsed.unsigned_tx.input[0].script_sig.is_empty());
+        assert_eq!(
+            parsed.inputs[0]
+                .redeem_script
+                .as_ref()
+                .map(|s| s.as_bytes()),
+            Some(program.as_slice())
+        );
+    }
+
+    #[test]
+    fn extracts_finalized_transactions() {
+        let (mut input, output) = sample_input_output();
+        let program = input.script_pubkey.clone();
+        input.script_pubkey = vec![0xa9, 0x14];
+        input.script_pubkey.extend([0x66; 20]);
+        input.script_pubkey.push(0x87);
+        input.redeem_script = Some(program);
+        let psbt_bytes = create_psbt(
+            &[input.clone()],
+            &[output],
+            &[],
+            &[],
+            TxParams::default(),
+            Network::Mainnet,
+            PsbtVersion::V0,
+        )
+        .unwrap();
+        assert_eq!(
+            extract_tx(&parse_psbt(&psbt_bytes).unwrap()),
+            Err(PsbtError::NotFinalized { index: 0 })
+        );
+
+        // Finalize the way a wallet would: the redeem script moves to the scriptSig.
+        let mut finalized = Psbt::deserialize(&psbt_bytes).unwrap();
+        let btc_input = &mut finalized.inputs[0];
+        btc_input.final_script_sig = Some(bitcoin::ScriptBuf::from_bytes(input.script_sig()));
+        btc_input.final_script_witness = Some(bitcoin::Witness::from_slice(&[
+            vec![0x30; 71],
+            vec![0x02; 33],
+        ]));
+        btc_input.redeem_script = None;
+        let expected = finalized.clone().extract_tx_unchecked_fee_rate();
+
+        let raw = extract_tx(&parse_psbt(&finalized.serialize()).unwrap()).unwrap();
+        assert_eq!(raw, bitcoin::consensus::encode::serialize(&expected));
+    }
 }
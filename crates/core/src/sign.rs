@@ -0,0 +1,588 @@
+//! Software signer for P2WPKH (native or nested) and P2TR key-path inputs.
+//!
+//! Signs every input of a mined transaction with `SIGHASH_ALL` (BIP143) or
+//! `SIGHASH_DEFAULT` (BIP341), finalizes the PSBT and extracts the transaction.
+//! Signing only adds witnesses, so the txid stays the mined one; this is checked
+//! before the result is returned.
+
+use alloc::vec::Vec;
+
+use secp256k1::{ecdsa::Signature, Keypair, Message, PublicKey, Scalar, Secp256k1, SecretKey};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::{
+    address::Network,
+    hash::{double_sha256, hash160},
+    miner::TransactionPlan,
+    psbt::{create_psbt, extract_tx, ParsedPsbt, PsbtError, PsbtInputFields, PsbtPair},
+    tx::{decode_tx, encode_varint, serialize_tx_for_txid, write_output, TxInput, TxOutput},
+};
+
+const SIGHASH_DEFAULT: u32 = 0x00;
+const SIGHASH_ALL: u32 = 0x01;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SignerError {
+    #[error("invalid private key")]
+    InvalidKey,
+    #[error("WIF key is for another network")]
+    WifNetwork,
+    #[error("uncompressed WIF keys cannot sign SegWit inputs")]
+    UncompressedKey,
+    #[error("{actual} keys given for {expected} inputs")]
+    KeyCount { expected: usize, actual: usize },
+    #[error("inputs[{index}] is neither P2WPKH nor P2TR")]
+    UnsupportedInput { index: usize },
+    #[error("inputs[{index}] requests sighash type {sighash_type}; only ALL is supported")]
+    UnsupportedSighash { index: usize, sighash_type: u32 },
+    #[error("inputs[{index}] key does not match its scriptPubKey")]
+    KeyMismatch { index: usize },
+    #[error("signed transaction does not keep the mined txid")]
+    TxidChanged,
+    #[error("psbt error: {0}")]
+    Psbt(#[from] PsbtError),
+}
+
+/// A finalized transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTx {
+    /// Finalized PSBT, in the version of the one signed.
+    pub psbt: Vec<u8>,
+    /// Network serialization, ready to broadcast.
+    pub tx: Vec<u8>,
+    /// Txid, in internal byte order; always that of the unsigned transaction.
+    pub txid: [u8; 32],
+}
+
+/// Private key given as 64 hex characters or as WIF for `network`.
+pub fn parse_private_key(key: &str, network: Network) -> Result<[u8; 32], SignerError> {
+    let key = key.trim();
+    if key.len() == 64 {
+        if let Ok(raw) = hex::decode(key) {
+            return secret_key(&raw).map(|secret| secret.secret_bytes());
+        }
+    }
+
+    let payload = crate::address::decode_base58check(key).ok_or(SignerError::InvalidKey)?;
+    let (version, rest) = payload.split_first().ok_or(SignerError::InvalidKey)?;
+    let expected = match network {
+        Network::Mainnet => 0x80,
+        _ => 0xef,
+    };
+    if *version != expected {
+        return Err(SignerError::WifNetwork);
+    }
+    let raw = match rest {
+        [raw @ .., 0x01] if raw.len() == 32 => raw,
+        raw if raw.len() == 32 => return Err(SignerError::UncompressedKey),
+        _ => return Err(SignerError::InvalidKey),
+    };
+    secret_key(raw).map(|secret| secret.secret_bytes())
+}
+
+/// Sign the mined transaction of `plan`, one key per input.
+pub fn sign_plan(plan: &TransactionPlan, keys: &[[u8; 32]]) -> Result<SignedTx, SignerError> {
+    let mut outputs = plan.outputs.clone();
+    outputs.push(TxOutput {
+        script_pubkey: plan.op_return_script.clone(),
+        amount: 0,
+    });
+    let psbt = ParsedPsbt {
+        version: plan.psbt_version,
+        tx_params: plan.tx_params,
+        inputs: plan.inputs.clone(),
+        outputs,
+        output_fields: plan.output_fields.clone(),
+        network: Some(plan.network),
+        global_pairs: plan.global_pairs.clone(),
+    };
+    sign_psbt(&psbt, plan.network, keys)
+}
+
+/// Sign every input of `psbt`, one key per input, and finalize it for `network`.
+pub fn sign_psbt(
+    psbt: &ParsedPsbt,
+    network: Network,
+    keys: &[[u8; 32]],
+) -> Result<SignedTx, SignerError> {
+    if keys.len() != psbt.inputs.len() {
+        return Err(SignerError::KeyCount {
+            expected: psbt.inputs.len(),
+            actual: keys.len(),
+        });
+    }
+    let secp = Secp256k1::new();
+    let sighashes = SighashCache::new(psbt);
+
+    let mut finalized = psbt.clone();
+    for (index, (input, key)) in psbt.inputs.iter().zip(keys).enumerate() {
+        let secret = secret_key(key)?;
+        let program = input.redeem_script.as_ref().unwrap_or(&input.script_pubkey);
+        let witness = match program.as_slice() {
+            [0x00, 0x14, key_hash @ ..] if key_hash.len() == 20 => {
+                let sighash_type = input.psbt.sighash_type.unwrap_or(SIGHASH_ALL);
+                if sighash_type != SIGHASH_ALL {
+                    return Err(SignerError::UnsupportedSighash {
+                        index,
+                        sighash_type,
+                    });
+                }
+                let pubkey = PublicKey::from_secret_key(&secp, &secret).serialize();
+                if hash160(&pubkey) != key_hash {
+                    return Err(SignerError::KeyMismatch { index });
+                }
+                let digest = sighashes.segwit_v0(index, key_hash);
+                let signature: Signature =
+                    secp.sign_ecdsa_low_r(&Message::from_digest(digest), &secret);
+                let mut signature = signature.serialize_der().to_vec();
+                signature.push(SIGHASH_ALL as u8);
+                Vec::from([signature, pubkey.to_vec()])
+            }
+            [0x51, 0x20, output_key @ ..]
+                if output_key.len() == 32 && input.redeem_script.is_none() =>
+            {
+                let sighash_type = input.psbt.sighash_type.unwrap_or(SIGHASH_DEFAULT);
+                if !matches!(sighash_type, SIGHASH_DEFAULT | SIGHASH_ALL) {
+                    return Err(SignerError::UnsupportedSighash {
+                        index,
+                        sighash_type,
+                    });
+                }
+                let keypair = tweak_keypair(&secp, &secret, input)?;
+                if keypair.x_only_public_key().0.serialize() != output_key {
+                    return Err(SignerError::KeyMismatch { index });
+                }
+                let digest = sighashes.taproot_key_path(index, sighash_type as u8);
+                let signature =
+                    secp.sign_schnorr_no_aux_rand(&Message::from_digest(digest), &keypair);
+                let mut signature = signature.as_ref().to_vec();
+                if sighash_type != SIGHASH_DEFAULT {
+                    signature.push(sighash_type as u8);
+                }
+                Vec::from([signature])
+            }
+            _ => return Err(SignerError::UnsupportedInput { index }),
+        };
+        finalized.inputs[index] = finalize_input(input, &witness);
+    }
+
+    let tx = extract_tx(&finalized)?;
+    let txid = decode_tx(&tx).map_err(|_| SignerError::TxidChanged)?.txid;
+    let unsigned = serialize_tx_for_txid(&psbt.inputs, &psbt.outputs, psbt.tx_params);
+    if txid != double_sha256(&unsigned) {
+        return Err(SignerError::TxidChanged);
+    }
+    let psbt = create_psbt(
+        &finalized.inputs,
+        &finalized.outputs,
+        &finalized.output_fields,
+        &finalized.global_pairs,
+        finalized.tx_params,
+        network,
+        finalized.version,
+    )?;
+    Ok(SignedTx { psbt, tx, txid })
+}
+
+fn secret_key(raw: &[u8]) -> Result<SecretKey, SignerError> {
+    SecretKey::from_slice(raw).map_err(|_| SignerError::InvalidKey)
+}
+
+/// BIP341 output key pair: the internal key tweaked with its taproot merkle root
+/// (`PSBT_IN_TAP_MERKLE_ROOT`), or with none for a key-path-only output (BIP86).
+fn tweak_keypair(
+    secp: &Secp256k1<secp256k1::All>,
+    secret: &SecretKey,
+    input: &TxInput,
+) -> Result<Keypair, SignerError> {
+    let keypair = Keypair::from_secret_key(secp, secret);
+    let mut tweak_data = keypair.x_only_public_key().0.serialize().to_vec();
+    if let Some(merkle_root) = input.psbt.tap_merkle_root {
+        tweak_data.extend_from_slice(&merkle_root);
+    }
+    let tweak = Scalar::from_be_bytes(tagged_hash(b"TapTweak", &tweak_data))
+        .map_err(|_| SignerError::InvalidKey)?;
+    keypair
+        .add_xonly_tweak(secp, &tweak)
+        .map_err(|_| SignerError::InvalidKey)
+}
+
+/// BIP174 finalizer: the final scriptSig and witness replace the signing metadata.
+fn finalize_input(input: &TxInput, witness: &[Vec<u8>]) -> TxInput {
+    let mut serialized = encode_varint(witness.len() as u64);
+    for item in witness {
+        serialized.extend(encode_varint(item.len() as u64));
+        serialized.extend_from_slice(item);
+    }
+
+    // Partial and taproot signatures, leaf scripts and earlier finalizations.
+    let mut unknown: Vec<PsbtPair> = input
+        .psbt
+        .unknown
+        .iter()
+        .filter(|pair| {
+            !matches!(
+                pair.key.first(),
+                Some(0x02 | 0x07 | 0x08 | 0x13 | 0x14 | 0x15)
+            )
+        })
+        .cloned()
+        .collect();
+    let script_sig = input.script_sig();
+    if !script_sig.is_empty() {
+        unknown.push(PsbtPair {
+            key: Vec::from([0x07]),
+            value: script_sig,
+        });
+    }
+    unknown.push(PsbtPair {
+        key: Vec::from([0x08]),
+        value: serialized,
+    });
+
+    TxInput {
+        redeem_script: None,
+        psbt: PsbtInputFields {
+            non_witness_utxo: input.psbt.non_witness_utxo.clone(),
+            unknown,
+            ..PsbtInputFields::default()
+        },
+        ..input.clone()
+    }
+}
+
+/// Transaction-wide digests shared by every input's sighash.
+struct SighashCache<'a> {
+    psbt: &'a ParsedPsbt,
+    prevouts: [u8; 32],
+    amounts: [u8; 32],
+    script_pubkeys: [u8; 32],
+    sequences: [u8; 32],
+    outputs: [u8; 32],
+}
+
+impl<'a> SighashCache<'a> {
+    /// Single SHA-256 of each field list; BIP143 hashes them once more.
+    fn new(psbt: &'a ParsedPsbt) -> Self {
+        let (mut prevouts, mut amounts, mut script_pubkeys, mut sequences, mut outputs) =
+            (Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        for input in &psbt.inputs {
+            prevouts.extend_from_slice(&input.txid);
+            prevouts.extend_from_slice(&input.vout.to_le_bytes());
+            amounts.extend_from_slice(&input.amount.to_le_bytes());
+            script_pubkeys.extend(encode_varint(input.script_pubkey.len() as u64));
+            script_pubkeys.extend_from_slice(&input.script_pubkey);
+            sequences.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        for output in &psbt.outputs {
+            write_output(&mut outputs, output);
+        }
+        Self {
+            psbt,
+            prevouts: sha256(&prevouts),
+            amounts: sha256(&amounts),
+            script_pubkeys: sha256(&script_pubkeys),
+            sequences: sha256(&sequences),
+            outputs: sha256(&outputs),
+        }
+    }
+
+    /// BIP143 `SIGHASH_ALL` digest of a P2WPKH input.
+    fn segwit_v0(&self, index: usize, key_hash: &[u8]) -> [u8; 32] {
+        let input = &self.psbt.inputs[index];
+        let mut preimage = Vec::with_capacity(182);
+        preimage.extend_from_slice(&self.psbt.tx_params.version.to_le_bytes());
+        preimage.extend_from_slice(&sha256(&self.prevouts));
+        preimage.extend_from_slice(&sha256(&self.sequences));
+        preimage.extend_from_slice(&input.txid);
+        preimage.extend_from_slice(&input.vout.to_le_bytes());
+        // scriptCode: OP_DUP OP_HASH160 <key hash> OP_EQUALVERIFY OP_CHECKSIG.
+        preimage.extend_from_slice(&[0x19, 0x76, 0xa9, 0x14]);
+        preimage.extend_from_slice(key_hash);
+        preimage.extend_from_slice(&[0x88, 0xac]);
+        preimage.extend_from_slice(&input.amount.to_le_bytes());
+        preimage.extend_from_slice(&input.sequence.to_le_bytes());
+        preimage.extend_from_slice(&sha256(&self.outputs));
+        preimage.extend_from_slice(&self.psbt.tx_params.lock_time.to_le_bytes());
+        preimage.extend_from_slice(&SIGHASH_ALL.to_le_bytes());
+        double_sha256(&preimage)
+    }
+
+    /// BIP341 key-path digest for `SIGHASH_DEFAULT` or `SIGHASH_ALL`, without annex.
+    fn taproot_key_path(&self, index: usize, sighash_type: u8) -> [u8; 32] {
+        let mut message = Vec::with_capacity(175);
+        message.push(0x00); // epoch
+        message.push(sighash_type);
+        message.extend_from_slice(&self.psbt.tx_params.version.to_le_bytes());
+        message.extend_from_slice(&self.psbt.tx_params.lock_time.to_le_bytes());
+        message.extend_from_slice(&self.prevouts);
+        message.extend_from_slice(&self.amounts);
+        message.extend_from_slice(&self.script_pubkeys);
+        message.extend_from_slice(&self.sequences);
+        message.extend_from_slice(&self.outputs);
+        message.push(0x00); // spend type: key path, no annex
+        message.extend_from_slice(&(index as u32).to_le_bytes());
+        tagged_hash(b"TapSighash", &message)
+    }
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// BIP340 tagged hash: `SHA256(SHA256(tag) || SHA256(tag) || data)`.
+fn tagged_hash(tag: &[u8], data: &[u8]) -> [u8; 32] {
+    let tag = sha256(tag);
+    let mut hasher = Sha256::new();
+    hasher.update(tag);
+    hasher.update(tag);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        psbt::{parse_psbt, PsbtVersion},
+        tx::{create_op_return_script, TxParams, DEFAULT_SEQUENCE},
+    };
+    use alloc::vec;
+    use bitcoin::{
+        consensus,
+        hashes::Hash,
+        key::{CompressedPublicKey, TapTweak},
+        secp256k1::{self as btc_secp, schnorr},
+        sighash::{EcdsaSighashType, Prevouts, SighashCache as BtcSighashCache},
+        Amount, ScriptBuf, TapNodeHash, Transaction, TxOut,
+    };
+
+    const KEYS: [[u8; 32]; 3] = [[0x11; 32], [0x22; 32], [0x33; 32]];
+    const MERKLE_ROOT: [u8; 32] = [0x77; 32];
+
+    fn input(index: u8, script_pubkey: ScriptBuf, redeem_script: Option<ScriptBuf>) -> TxInput {
+        TxInput {
+            txid: [0x40 + index; 32],
+            vout: index as u32,
+            script_pubkey: script_pubkey.into_bytes(),
+            amount: 30_000 + index as u64,
+            sequence: DEFAULT_SEQUENCE,
+            witness_hint: None,
+            redeem_script: redeem_script.map(ScriptBuf::into_bytes),
+            psbt: Default::default(),
+        }
+    }
+
+    /// P2WPKH, P2TR (BIP86) and P2SH-P2WPKH inputs spent into a payment and the
+    /// ZELD OP_RETURN.
+    fn mined_psbt(version: PsbtVersion) -> ParsedPsbt {
+        let secp = btc_secp::Secp256k1::new();
+        let pubkey = |key: &[u8; 32]| {
+            let secret = btc_secp::SecretKey::from_slice(key).unwrap();
+            CompressedPublicKey(btc_secp::PublicKey::from_secret_key(&secp, &secret))
+        };
+        let p2wpkh = ScriptBuf::new_p2wpkh(&pubkey(&KEYS[0]).wpubkey_hash());
+        let internal_key = pubkey(&KEYS[1]).0.x_only_public_key().0;
+        let p2tr = ScriptBuf::new_p2tr(
+            &secp,
+            internal_key,
+            Some(TapNodeHash::from_byte_array(MERKLE_ROOT)),
+        );
+        let nested = ScriptBuf::new_p2wpkh(&pubkey(&KEYS[2]).wpubkey_hash());
+        let p2sh = ScriptBuf::new_p2sh(&nested.script_hash());
+
+        let mut inputs = vec![
+            input(0, p2wpkh.clone(), None),
+            input(1, p2tr, None),
+            input(2, p2sh, Some(nested)),
+        ];
+        // PSBT_IN_TAP_MERKLE_ROOT, which the finalizer drops with the other signing data.
+        inputs[1].psbt.tap_merkle_root = Some(MERKLE_ROOT);
+        let outputs = vec![
+            TxOutput {
+                script_pubkey: p2wpkh.into_bytes(),
+                amount: 89_000,
+            },
+            TxOutput {
+                script_pubkey: create_op_return_script(&[0x01, 0x02]),
+                amount: 0,
+            },
+        ];
+        let psbt = create_psbt(
+            &inputs,
+            &outputs,
+            &[],
+            &[],
+            TxParams::default(),
+            Network::Regtest,
+            version,
+        )
+        .unwrap();
+        parse_psbt(&psbt).unwrap()
+    }
+
+    #[test]
+    fn signatures_verify_against_bitcoin_sighashes() {
+        for version in [PsbtVersion::V0, PsbtVersion::V2] {
+            let psbt = mined_psbt(version);
+            let signed = sign_psbt(&psbt, Network::Regtest, &KEYS).expect("inputs sign");
+            let unsigned = serialize_tx_for_txid(&psbt.inputs, &psbt.outputs, psbt.tx_params);
+            assert_eq!(signed.txid, double_sha256(&unsigned));
+
+            let tx: Transaction = consensus::deserialize(&signed.tx).expect("tx decodes");
+            assert_eq!(tx.compute_txid().to_byte_array(), signed.txid);
+            let finalized = parse_psbt(&signed.psbt).unwrap();
+            assert_eq!(extract_tx(&finalized), Ok(signed.tx.clone()));
+            // Only the final scriptSig and witness are left.
+            for input in &finalized.inputs {
+                assert!(input
+                    .psbt
+                    .unknown
+                    .iter()
+                    .all(|pair| matches!(pair.key.as_slice(), [0x07] | [0x08])));
+            }
+
+            let prevouts: Vec<TxOut> = psbt
+                .inputs
+                .iter()
+                .map(|input| TxOut {
+                    value: Amount::from_sat(input.amount),
+                    script_pubkey: ScriptBuf::from_bytes(input.script_pubkey.clone()),
+                })
+                .collect();
+            let secp = btc_secp::Secp256k1::new();
+            let mut cache = BtcSighashCache::new(&tx);
+            for index in [0, 2] {
+                let witness = &tx.input[index].witness;
+                let program = psbt.inputs[index]
+                    .redeem_script
+                    .clone()
+                    .unwrap_or_else(|| psbt.inputs[index].script_pubkey.clone());
+                let sighash = cache
+                    .p2wpkh_signature_hash(
+                        index,
+                        &ScriptBuf::from_bytes(program),
+                        prevouts[index].value,
+                        EcdsaSighashType::All,
+                    )
+                    .unwrap();
+                let signature = bitcoin::ecdsa::Signature::from_slice(&witness[0]).unwrap();
+                let pubkey = btc_secp::PublicKey::from_slice(&witness[1]).unwrap();
+                secp.verify_ecdsa(
+                    &btc_secp::Message::from_digest(sighash.to_byte_array()),
+                    &signature.signature,
+                    &pubkey,
+                )
+                .expect("ECDSA signature verifies");
+            }
+            assert_eq!(
+                tx.input[2].script_sig.as_bytes(),
+                psbt.inputs[2].script_sig().as_slice()
+            );
+
+            let sighash = cache
+                .taproot_key_spend_signature_hash(
+                    1,
+                    &Prevouts::All(&prevouts),
+                    bitcoin::TapSighashType::Default,
+                )
+                .unwrap();
+            let keypair = btc_secp::Keypair::from_seckey_slice(&secp, &KEYS[1]).unwrap();
+            let output_key = keypair
+                .x_only_public_key()
+                .0
+                .tap_tweak(&secp, Some(TapNodeHash::from_byte_array(MERKLE_ROOT)))
+                .0;
+            assert_eq!(tx.input[1].witness.len(), 1);
+            secp.verify_schnorr(
+                &schnorr::Signature::from_slice(&tx.input[1].witness[0]).unwrap(),
+                &btc_secp::Message::from_digest(sighash.to_byte_array()),
+                &output_key.to_x_only_public_key(),
+            )
+            .expect("Schnorr signature verifies");
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_or_missing_keys() {
+        let psbt = mined_psbt(PsbtVersion::V0);
+        assert_eq!(
+            sign_psbt(&psbt, Network::Regtest, &KEYS[..2]),
+            Err(SignerError::KeyCount {
+                expected: 3,
+                actual: 2
+            })
+        );
+        let swapped = [KEYS[1], KEYS[0], KEYS[2]];
+        assert_eq!(
+            sign_psbt(&psbt, Network::Regtest, &swapped),
+            Err(SignerError::KeyMismatch { index: 0 })
+        );
+        assert_eq!(
+            sign_psbt(&psbt, Network::Regtest, &[KEYS[0], KEYS[0], KEYS[2]]),
+            Err(SignerError::KeyMismatch { index: 1 })
+        );
+
+        let mut all_but_one = psbt.clone();
+        all_but_one.inputs[0].psbt.sighash_type = Some(0x83);
+        assert_eq!(
+            sign_psbt(&all_but_one, Network::Regtest, &KEYS),
+            Err(SignerError::UnsupportedSighash {
+                index: 0,
+                sighash_type: 0x83
+            })
+        );
+
+        let mut p2pkh = psbt;
+        p2pkh.inputs[0].script_pubkey = vec![0x76, 0xa9, 0x14];
+        p2pkh.inputs[0].script_pubkey.extend([0x11; 20]);
+        p2pkh.inputs[0].script_pubkey.extend([0x88, 0xac]);
+        assert_eq!(
+            sign_psbt(&p2pkh, Network::Regtest, &KEYS),
+            Err(SignerError::UnsupportedInput { index: 0 })
+        );
+    }
+
+    #[test]
+    fn parses_raw_and_wif_keys() {
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        assert_eq!(
+            parse_private_key(&hex::encode(one), Network::Mainnet),
+            Ok(one)
+        );
+        assert_eq!(
+            parse_private_key(
+                "KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgd9M7rFU73sVHnoWn",
+                Network::Mainnet
+            ),
+            Ok(one)
+        );
+        assert_eq!(
+            parse_private_key(
+                "cMahea7zqjxrtgAbB7LSGbcQUr1uX1ojuat9jZodMN87JcbXMTcA",
+                Network::Signet
+            ),
+            Ok(one)
+        );
+        assert_eq!(
+            parse_private_key(
+                "cMahea7zqjxrtgAbB7LSGbcQUr1uX1ojuat9jZodMN87JcbXMTcA",
+                Network::Mainnet
+            ),
+            Err(SignerError::WifNetwork)
+        );
+        assert_eq!(
+            parse_private_key(
+                "5HpHagT65TZzG1PH3CSu63k8DbpvD8s5ip4nEB3kEsreAnchuDf",
+                Network::Mainnet
+            ),
+            Err(SignerError::UncompressedKey)
+        );
+        assert_eq!(
+            parse_private_key(&"00".repeat(32), Network::Mainnet),
+            Err(SignerError::InvalidKey)
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Mining statistics shared by every front end.
+//!
+//! [`HashStats`] accumulates what a run has seen (leading-zero histogram and best
+//! txid); [`MiningOdds`] turns a per-hash hit probability into the expected work
+//! left and the chance a hit should already have turned up.
+
+use crate::hash::count_leading_zero_bits;
+
+/// Buckets in a leading-zero histogram: 0 through 64 hex zeros.
+pub const ZERO_HISTOGRAM_LEN: usize = 65;
+
+/// Leading-zero histogram and best txid over the hashes of a run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashStats {
+    histogram: [u64; ZERO_HISTOGRAM_LEN],
+    /// `(leading zero bits, nonce, hash)` of the best hash; ties keep the lowest nonce.
+    best: Option<(u16, u64, [u8; 32])>,
+}
+
+impl Default for HashStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HashStats {
+    pub fn new() -> Self {
+        Self {
+            histogram: [0; ZERO_HISTOGRAM_LEN],
+            best: None,
+        }
+    }
+
+    /// Record one double-SHA256 digest (hash byte order) mined at `nonce`.
+    #[inline]
+    pub fn record(&mut self, nonce: u64, hash: &[u8; 32]) {
+        let bits = count_leading_zero_bits(hash);
+        self.histogram[(bits / 4) as usize] += 1;
+        self.offer_best(bits, nonce, hash);
+    }
+
+    /// Fold the statistics of another worker into these.
+    pub fn merge(&mut self, other: &HashStats) {
+        for (bucket, count) in self.histogram.iter_mut().zip(other.histogram.iter()) {
+            *bucket += count;
+        }
+        if let Some((bits, nonce, hash)) = other.best {
+            self.offer_best(bits, nonce, &hash);
+        }
+    }
+
+    /// Hashes seen per count of leading hex zeros (index = zeros).
+    pub fn histogram(&self) -> &[u64; ZERO_HISTOGRAM_LEN] {
+        &self.histogram
+    }
+
+    /// Number of recorded hashes.
+    pub fn total(&self) -> u64 {
+        self.histogram.iter().sum()
+    }
+
+    /// Nonce and digest of the hash with the most leading zero bits.
+    pub fn best(&self) -> Option<(u64, [u8; 32])> {
+        self.best.map(|(_, nonce, hash)| (nonce, hash))
+    }
+
+    fn offer_best(&mut self, bits: u16, nonce: u64, hash: &[u8; 32]) {
+        let better = match self.best {
+            None => true,
+            Some((best_bits, best_nonce, _)) => {
+                bits > best_bits || (bits == best_bits && nonce < best_nonce)
+            }
+        };
+        if better {
+            self.best = Some((bits, nonce, *hash));
+        }
+    }
+}
+
+/// Luck figures of a run for a given per-hash hit probability.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MiningOdds {
+    /// Probability that a single hash matches the target.
+    pub hit_probability: f64,
+    /// Expected hashes until a hit. Hashing is memoryless, so work already done
+    /// does not shorten it.
+    pub expected_remaining_attempts: f64,
+    /// Milliseconds to the expected hit at the current hash rate, if known.
+    pub eta_ms: Option<f64>,
+    /// Probability that the attempts so far would have produced a hit.
+    pub probability_found: f64,
+}
+
+impl MiningOdds {
+    pub fn new(hit_probability: f64, attempts: u128, hash_rate: f64) -> Self {
+        let expected_remaining_attempts = expected_attempts(hit_probability);
+        let eta_ms = (hash_rate > 0.0 && expected_remaining_attempts.is_finite())
+            .then(|| expected_remaining_attempts / hash_rate * 1000.0);
+        Self {
+            hit_probability,
+            expected_remaining_attempts,
+            eta_ms,
+            probability_found: probability_found(hit_probability, attempts),
+        }
+    }
+}
+
+/// Probability that a random hash has at least `bits` leading zero bits: 2^-bits.
+pub fn leading_zero_bits_probability(bits: u32) -> f64 {
+    if bits > 1022 {
+        return 0.0;
+    }
+    // Build 2^-bits directly from the IEEE-754 exponent; `powi` needs std.
+    f64::from_bits(((1023 - bits) as u64) << 52)
+}
+
+/// Expected hashes per hit: `1 / hit_probability`.
+pub fn expected_attempts(hit_probability: f64) -> f64 {
+    if hit_probability > 0.0 {
+        1.0 / hit_probability
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// Probability of at least one hit in `attempts` hashes: `1 - (1 - p)^attempts`.
+pub fn probability_found(hit_probability: f64, attempts: u128) -> f64 {
+    if attempts == 0 || hit_probability <= 0.0 {
+        return 0.0;
+    }
+    if hit_probability >= 1.0 {
+        return 1.0;
+    }
+    if hit_probability < 1e-6 {
+        // `1 - p` rounds away small probabilities; (1 - p)^n ~= e^(-n * p) here.
+        return one_minus_exp_neg(attempts as f64 * hit_probability);
+    }
+
+    // Exponentiation by squaring keeps this exact enough without `powf`.
+    let mut base = 1.0 - hit_probability;
+    let mut exp = attempts;
+    let mut miss = 1.0;
+    while exp > 0 && miss > 0.0 {
+        if exp & 1 == 1 {
+            miss *= base;
+        }
+        base *= base;
+        exp >>= 1;
+    }
+    1.0 - miss
+}
+
+/// `1 - e^-x` for `x >= 0`, accurate for tiny `x`.
+fn one_minus_exp_neg(x: f64) -> f64 {
+    if x < 1e-5 {
+        return x - x * x / 2.0;
+    }
+    if x > 745.0 {
+        return 1.0;
+    }
+    // Halve x until the Taylor series converges quickly, then square back up.
+    let mut halvings = 0;
+    let mut y = x;
+    while y > 0.5 {
+        y /= 2.0;
+        halvings += 1;
+    }
+    let mut term = 1.0;
+    let mut exp_neg = 1.0;
+    for i in 1..=16 {
+        term *= -y / i as f64;
+        exp_neg += term;
+    }
+    for _ in 0..halvings {
+        exp_neg *= exp_neg;
+    }
+    1.0 - exp_neg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-9 * b.abs().max(1e-300)
+    }
+
+    #[test]
+    fn histogram_and_best_track_leading_zeros() {
+        let mut stats = HashStats::new();
+        let mut hash = [0xffu8; 32];
+        stats.record(5, &hash);
+        hash[31] = 0x00;
+        hash[30] = 0x0f; // 3 hex zeros, 12 bits
+        stats.record(9, &hash);
+        stats.record(7, &hash);
+
+        let mut other = HashStats::new();
+        let mut worse = [0xffu8; 32];
+        worse[31] = 0x01;
+        other.record(1, &worse);
+        stats.merge(&other);
+
+        assert_eq!(stats.total(), 4);
+        assert_eq!(stats.histogram()[0], 1);
+        assert_eq!(stats.histogram()[1], 1);
+        assert_eq!(stats.histogram()[3], 2);
+        assert_eq!(stats.best(), Some((7, hash)));
+    }
+
+    #[test]
+    fn odds_match_closed_forms() {
+        let p = leading_zero_bits_probability(16);
+        assert_eq!(p, 1.0 / 65_536.0);
+        assert_eq!(leading_zero_bits_probability(0), 1.0);
+        assert_eq!(leading_zero_bits_probability(2000), 0.0);
+
+        // 1 - (1 - 1/16)^16
+        let expected = 1.0 - (15.0f64 / 16.0).powi(16);
+        assert!(close(probability_found(1.0 / 16.0, 16), expected));
+
+        // Tiny probabilities go through the exponential approximation.
+        let tiny = leading_zero_bits_probability(40);
+        let n = 1u128 << 40;
+        assert!(close(probability_found(tiny, n), 1.0 - (-1.0f64).exp()));
+        assert!(close(probability_found(tiny, 3), 3.0 * tiny));
+        assert_eq!(probability_found(tiny, 0), 0.0);
+
+        let odds = MiningOdds::new(p, 0, 1_000.0);
+        assert_eq!(odds.expected_remaining_attempts, 65_536.0);
+        assert_eq!(odds.eta_ms, Some(65_536.0));
+        assert_eq!(MiningOdds::new(p, 10, 0.0).eta_ms, None);
+    }
+}
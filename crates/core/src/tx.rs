@@ -1,7 +1,11 @@
 use alloc::{vec, vec::Vec};
 
-use crate::cbor::{
-    cbor_array_header_length, cbor_uint_length, encode_cbor_array_header, encode_cbor_uint,
+use crate::{
+    cbor::{
+        cbor_array_header_length, cbor_uint_length, encode_cbor_array_header, encode_cbor_uint,
+    },
+    hash::{double_sha256, hash160},
+    psbt::PsbtInputFields,
 };
 use thiserror::Error;
 
@@ -15,6 +19,76 @@ pub struct TxInput {
     pub script_pubkey: Vec<u8>,
     pub amount: u64,
     pub sequence: u32,
+    /// How the input will be spent, for witness sizing. Required unless the
+    /// scriptPubKey (or redeem script) is P2WPKH or P2TR (spent through the key path).
+    pub witness_hint: Option<WitnessHint>,
+    /// Witness program a P2SH scriptPubKey wraps (nested SegWit). Its push forms
+    /// the scriptSig, which is part of the txid.
+    pub redeem_script: Option<Vec<u8>>,
+    /// Signer metadata written to the PSBT input map; does not affect the txid.
+    pub psbt: PsbtInputFields,
+}
+
+impl TxInput {
+    /// scriptSig of the unsigned input: a push of the redeem script, if any.
+    pub fn script_sig(&self) -> Vec<u8> {
+        match &self.redeem_script {
+            Some(script) => {
+                let mut script_sig =
+                    pushdata_prefix(script.len()).expect("redeem script length is bounded");
+                script_sig.extend_from_slice(script);
+                script_sig
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Check that the redeem script, if any, is a witness program the P2SH
+    /// scriptPubKey commits to; any other input is only spendable with an empty
+    /// scriptSig.
+    pub fn check_redeem_script(&self) -> Result<(), TxError> {
+        let Some(redeem_script) = &self.redeem_script else {
+            return Ok(());
+        };
+        let script_hash = match self.script_pubkey.as_slice() {
+            [0xa9, 0x14, hash @ .., 0x87] if hash.len() == 20 => hash,
+            _ => return Err(TxError::RedeemScriptWithoutP2sh),
+        };
+        if !is_witness_program(redeem_script) {
+            return Err(TxError::RedeemScriptNotWitnessProgram);
+        }
+        if hash160(redeem_script) != script_hash {
+            return Err(TxError::RedeemScriptMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Whether `script` is a SegWit program: OP_0..OP_16 followed by a single 2..=40
+/// byte push.
+pub(crate) fn is_witness_program(script: &[u8]) -> bool {
+    match script {
+        [0x00 | 0x51..=0x60, len, program @ ..] => {
+            program.len() == *len as usize && (2..=40).contains(&program.len())
+        }
+        _ => false,
+    }
+}
+
+/// Witness size of an input, given directly or derived from how it is spent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WitnessHint {
+    /// Serialized witness size in bytes, item count and length prefixes included.
+    Size(usize),
+    /// P2WSH `OP_CHECKMULTISIG` of `required` signatures over `keys` compressed keys.
+    Multisig { required: u8, keys: u8 },
+    /// Taproot script path: `signatures` Schnorr signatures satisfying a leaf script
+    /// of `script_len` bytes at `depth` in the script tree.
+    TapScript {
+        signatures: u8,
+        script_len: usize,
+        depth: u8,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -25,12 +99,177 @@ pub struct TxOutput {
 
 pub const DEFAULT_SEQUENCE: u32 = 0xffff_fffd;
 
+/// Version and nLockTime, the transaction fields outside the inputs and outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxParams {
+    pub version: u32,
+    pub lock_time: u32,
+}
+
+impl Default for TxParams {
+    /// Version 2 (BIP68 relative locktimes), no absolute locktime.
+    fn default() -> Self {
+        Self {
+            version: 2,
+            lock_time: 0,
+        }
+    }
+}
+
 #[derive(Debug, Error, PartialEq, Eq)]
 pub enum TxError {
     #[error("data too large to encode")]
     DataTooLarge,
     #[error("invalid CBOR nonce length; must be 1, 2, 3, 5, or 9 bytes")]
     InvalidCborNonceLength,
+    #[error("malformed transaction")]
+    Malformed,
+    #[error("redeem script needs a P2SH scriptPubKey")]
+    RedeemScriptWithoutP2sh,
+    #[error("redeem script is not a witness program")]
+    RedeemScriptNotWitnessProgram,
+    #[error("redeem script does not hash to the P2SH scriptPubKey")]
+    RedeemScriptMismatch,
+}
+
+/// Input of a transaction read back by [`decode_tx`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedInput {
+    pub txid: [u8; 32],
+    pub vout: u32,
+    pub script_sig: Vec<u8>,
+    pub sequence: u32,
+}
+
+/// Transaction read back from its network serialization. Witnesses are skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedTx {
+    pub params: TxParams,
+    pub inputs: Vec<DecodedInput>,
+    pub outputs: Vec<TxOutput>,
+    /// Double-SHA256 of the non-witness serialization, in internal byte order.
+    pub txid: [u8; 32],
+    /// BIP141 weight of the bytes decoded, witnesses included.
+    pub weight: usize,
+}
+
+/// Decode a transaction with or without the SegWit marker, rejecting trailing bytes.
+pub fn decode_tx(bytes: &[u8]) -> Result<DecodedTx, TxError> {
+    let mut reader = ByteReader::new(bytes);
+    let version = reader.u32()?;
+    let segwit = reader.rest().starts_with(&[0x00, 0x01]);
+    if segwit {
+        reader.take(2)?;
+    }
+
+    let body_start = reader.position();
+    let input_count = reader.varint()?;
+    let mut inputs = Vec::new();
+    for _ in 0..input_count {
+        inputs.push(DecodedInput {
+            txid: reader.array()?,
+            vout: reader.u32()?,
+            script_sig: reader.var_bytes()?.to_vec(),
+            sequence: reader.u32()?,
+        });
+    }
+    let output_count = reader.varint()?;
+    let mut outputs = Vec::new();
+    for _ in 0..output_count {
+        outputs.push(TxOutput {
+            amount: reader.u64()?,
+            script_pubkey: reader.var_bytes()?.to_vec(),
+        });
+    }
+    let body = &bytes[body_start..reader.position()];
+
+    if segwit {
+        for _ in 0..input_count {
+            for _ in 0..reader.varint()? {
+                reader.var_bytes()?;
+            }
+        }
+    }
+    let lock_time = reader.u32()?;
+    if !reader.rest().is_empty() {
+        return Err(TxError::Malformed);
+    }
+
+    let mut legacy = Vec::with_capacity(8 + body.len());
+    legacy.extend_from_slice(&version.to_le_bytes());
+    legacy.extend_from_slice(body);
+    legacy.extend_from_slice(&lock_time.to_le_bytes());
+    Ok(DecodedTx {
+        params: TxParams { version, lock_time },
+        inputs,
+        outputs,
+        txid: double_sha256(&legacy),
+        weight: legacy.len() * 3 + bytes.len(),
+    })
+}
+
+/// Cursor over serialized data; every read fails with [`TxError::Malformed`] past the end.
+pub(crate) struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    pub(crate) fn position(&self) -> usize {
+        self.position
+    }
+
+    pub(crate) fn rest(&self) -> &'a [u8] {
+        &self.bytes[self.position..]
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], TxError> {
+        let end = self.position.checked_add(len).ok_or(TxError::Malformed)?;
+        let taken = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(TxError::Malformed)?;
+        self.position = end;
+        Ok(taken)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N], TxError> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, TxError> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, TxError> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    /// Bitcoin varint (CompactSize), rejecting non-minimal encodings.
+    pub(crate) fn varint(&mut self) -> Result<u64, TxError> {
+        let (value, min) = match self.array::<1>()?[0] {
+            0xfd => (u16::from_le_bytes(self.array()?) as u64, 0xfd),
+            0xfe => (u32::from_le_bytes(self.array()?) as u64, 0x1_0000),
+            0xff => (u64::from_le_bytes(self.array()?), 0x1_0000_0000),
+            byte => return Ok(byte as u64),
+        };
+        if value < min {
+            return Err(TxError::Malformed);
+        }
+        Ok(value)
+    }
+
+    /// Varint length followed by that many bytes.
+    pub(crate) fn var_bytes(&mut self) -> Result<&'a [u8], TxError> {
+        let len = self.varint()?;
+        self.take(usize::try_from(len).map_err(|_| TxError::Malformed)?)
+    }
 }
 
 /// Bitcoin varint encoding as defined in BIP-008.
@@ -153,23 +392,61 @@ pub fn zeld_distribution_payload_length_with_nonce(
         + cbor_nonce_len
 }
 
-fn write_output(buf: &mut Vec<u8>, output: &TxOutput) {
+pub(crate) fn write_input(buf: &mut Vec<u8>, input: &TxInput, script_sig: &[u8]) {
+    buf.extend_from_slice(&input.txid);
+    buf.extend_from_slice(&input.vout.to_le_bytes());
+    buf.extend(encode_varint(script_sig.len() as u64));
+    buf.extend_from_slice(script_sig);
+    buf.extend_from_slice(&input.sequence.to_le_bytes()); // sequence (caller provided)
+}
+
+pub(crate) fn write_output(buf: &mut Vec<u8>, output: &TxOutput) {
     buf.extend_from_slice(&output.amount.to_le_bytes());
     buf.extend(encode_varint(output.script_pubkey.len() as u64));
     buf.extend_from_slice(&output.script_pubkey);
 }
 
 /// Serialize a transaction without witness data (txid serialization).
-pub fn serialize_tx_for_txid(inputs: &[TxInput], outputs: &[TxOutput]) -> Vec<u8> {
+///
+/// Nested SegWit inputs carry their redeem script push, as in the final transaction.
+pub fn serialize_tx_for_txid(
+    inputs: &[TxInput],
+    outputs: &[TxOutput],
+    params: TxParams,
+) -> Vec<u8> {
+    serialize_legacy(inputs, outputs, params, true)
+}
+
+/// Serialize an unsigned transaction in the legacy (non-witness) form.
+///
+/// PSBTs embed the unsigned transaction without the SegWit marker/flag, without any
+/// witness data and with empty scriptSigs (BIP174); redeem scripts travel in the
+/// input maps instead.
+pub fn serialize_unsigned_tx(
+    inputs: &[TxInput],
+    outputs: &[TxOutput],
+    params: TxParams,
+) -> Vec<u8> {
+    serialize_legacy(inputs, outputs, params, false)
+}
+
+fn serialize_legacy(
+    inputs: &[TxInput],
+    outputs: &[TxOutput],
+    params: TxParams,
+    script_sigs: bool,
+) -> Vec<u8> {
     let mut buf = Vec::new();
-    buf.extend_from_slice(&2u32.to_le_bytes()); // version
+    buf.extend_from_slice(&params.version.to_le_bytes());
 
     buf.extend(encode_varint(inputs.len() as u64));
     for input in inputs {
-        buf.extend_from_slice(&input.txid);
-        buf.extend_from_slice(&input.vout.to_le_bytes());
-        buf.push(0x00); // empty scriptSig
-        buf.extend_from_slice(&input.sequence.to_le_bytes()); // sequence (caller provided)
+        let script_sig = if script_sigs {
+            input.script_sig()
+        } else {
+            Vec::new()
+        };
+        write_input(&mut buf, input, &script_sig);
     }
 
     buf.extend(encode_varint(outputs.len() as u64));
@@ -177,18 +454,10 @@ pub fn serialize_tx_for_txid(inputs: &[TxInput], outputs: &[TxOutput]) -> Vec<u8
         write_output(&mut buf, output);
     }
 
-    buf.extend_from_slice(&0u32.to_le_bytes()); // locktime
+    buf.extend_from_slice(&params.lock_time.to_le_bytes());
     buf
 }
 
-/// Serialize an unsigned transaction in the legacy (non-witness) form.
-///
-/// PSBTs embed the unsigned transaction without the SegWit marker/flag and
-/// without any witness data (BIP174).
-pub fn serialize_unsigned_tx(inputs: &[TxInput], outputs: &[TxOutput]) -> Vec<u8> {
-    serialize_tx_for_txid(inputs, outputs)
-}
-
 /// Split a transaction around the OP_RETURN nonce for mining.
 ///
 /// Prefix includes version, inputs, output count, all outputs before the OP_RETURN,
@@ -199,16 +468,14 @@ pub fn split_tx_for_mining(
     outputs_before_opreturn: &[TxOutput],
     outputs_after_opreturn: &[TxOutput],
     nonce_len: usize,
+    params: TxParams,
 ) -> Result<(Vec<u8>, Vec<u8>), TxError> {
     let mut prefix = Vec::new();
-    prefix.extend_from_slice(&2u32.to_le_bytes());
+    prefix.extend_from_slice(&params.version.to_le_bytes());
 
     prefix.extend(encode_varint(inputs.len() as u64));
     for input in inputs {
-        prefix.extend_from_slice(&input.txid);
-        prefix.extend_from_slice(&input.vout.to_le_bytes());
-        prefix.push(0x00);
-        prefix.extend_from_slice(&input.sequence.to_le_bytes());
+        write_input(&mut prefix, input, &input.script_sig());
     }
 
     let total_outputs = outputs_before_opreturn.len() + 1 + outputs_after_opreturn.len();
@@ -231,7 +498,7 @@ pub fn split_tx_for_mining(
     for output in outputs_after_opreturn {
         write_output(&mut suffix, output);
     }
-    suffix.extend_from_slice(&0u32.to_le_bytes()); // locktime
+    suffix.extend_from_slice(&params.lock_time.to_le_bytes());
 
     Ok((prefix, suffix))
 }
@@ -248,6 +515,7 @@ pub fn split_tx_for_mining(
 /// * `outputs_after_opreturn` - Outputs after the OP_RETURN (typically change)
 /// * `distribution` - The ZELD distribution values
 /// * `cbor_nonce_len` - The byte length of the CBOR-encoded nonce (1, 2, 3, 5, or 9)
+/// * `params` - Version and locktime framing the transaction
 ///
 /// # Returns
 ///
@@ -262,6 +530,7 @@ pub fn split_tx_for_distribution_mining(
     outputs_after_opreturn: &[TxOutput],
     distribution: &[u64],
     cbor_nonce_len: usize,
+    params: TxParams,
 ) -> Result<(Vec<u8>, Vec<u8>), TxError> {
     // Validate CBOR nonce length to avoid mismatched script lengths.
     match cbor_nonce_len {
@@ -270,14 +539,11 @@ pub fn split_tx_for_distribution_mining(
     }
 
     let mut prefix = Vec::new();
-    prefix.extend_from_slice(&2u32.to_le_bytes()); // version
+    prefix.extend_from_slice(&params.version.to_le_bytes());
 
     prefix.extend(encode_varint(inputs.len() as u64));
     for input in inputs {
-        prefix.extend_from_slice(&input.txid);
-        prefix.extend_from_slice(&input.vout.to_le_bytes());
-        prefix.push(0x00);
-        prefix.extend_from_slice(&input.sequence.to_le_bytes());
+        write_input(&mut prefix, input, &input.script_sig());
     }
 
     let total_outputs = outputs_before_opreturn.len() + 1 + outputs_after_opreturn.len();
@@ -316,7 +582,7 @@ pub fn split_tx_for_distribution_mining(
     for output in outputs_after_opreturn {
         write_output(&mut suffix, output);
     }
-    suffix.extend_from_slice(&0u32.to_le_bytes()); // locktime
+    suffix.extend_from_slice(&params.lock_time.to_le_bytes());
 
     Ok((prefix, suffix))
 }
@@ -324,11 +590,11 @@ pub fn split_tx_for_distribution_mining(
 #[cfg(test)]
 mod tests {
     use super::{
-        create_op_return_script, create_zeld_distribution_op_return, encode_varint,
+        create_op_return_script, create_zeld_distribution_op_return, decode_tx, encode_varint,
         serialize_tx_for_txid, serialize_unsigned_tx, split_tx_for_distribution_mining,
         split_tx_for_mining, zeld_distribution_payload_length,
-        zeld_distribution_payload_length_with_nonce, TxError, TxInput, TxOutput, DEFAULT_SEQUENCE,
-        ZELD_PREFIX,
+        zeld_distribution_payload_length_with_nonce, TxError, TxInput, TxOutput, TxParams,
+        DEFAULT_SEQUENCE, ZELD_PREFIX,
     };
     use crate::cbor::{cbor_uint_length, encode_cbor_uint};
     use crate::hash::double_sha256;
@@ -353,6 +619,9 @@ mod tests {
             script_pubkey: p2wpkh_script(0x22),
             amount: 50_000,
             sequence: DEFAULT_SEQUENCE,
+            witness_hint: None,
+            redeem_script: None,
+            psbt: Default::default(),
         }
     }
 
@@ -372,10 +641,10 @@ mod tests {
             amount: 50_000,
         }];
 
-        let unsigned = serialize_unsigned_tx(&[input.clone()], &outputs);
+        let unsigned = serialize_unsigned_tx(&[input.clone()], &outputs, TxParams::default());
 
         // PSBT unsigned tx must match the legacy (txid) serialization.
-        let txid_view = serialize_tx_for_txid(&[input], &outputs);
+        let txid_view = serialize_tx_for_txid(&[input], &outputs, TxParams::default());
         assert_eq!(unsigned, txid_view);
 
         // After version, the next byte should be the input count (not a segwit marker).
@@ -393,7 +662,7 @@ mod tests {
             amount: 50_000,
         }];
 
-        let raw = serialize_tx_for_txid(&[input], &outputs);
+        let raw = serialize_tx_for_txid(&[input], &outputs, TxParams::default());
         assert_eq!(
             hex::encode(raw),
             "020000000111111111111111111111111111111111111111111111111111111111111111110100000000fdffffff0150c3000000000000160014222222222222222222222222222222222222222200000000"
@@ -441,6 +710,9 @@ mod tests {
                 script_pubkey: Vec::new(),
                 amount: 0,
                 sequence: i.sequence.to_consensus_u32(),
+                witness_hint: None,
+                redeem_script: None,
+                psbt: Default::default(),
             })
             .collect();
 
@@ -453,7 +725,7 @@ mod tests {
             })
             .collect();
 
-        let ours = serialize_tx_for_txid(&inputs, &outputs);
+        let ours = serialize_tx_for_txid(&inputs, &outputs, TxParams::default());
         let ours_txid = sha256d::Hash::hash(&ours);
 
         assert_eq!(ours_txid, tx.compute_txid().to_raw_hash());
@@ -491,10 +763,15 @@ mod tests {
             list
         };
 
-        let full_tx = serialize_tx_for_txid(&[input.clone()], &full_outputs);
-        let (prefix, suffix) =
-            split_tx_for_mining(&[input], &outputs_before, &outputs_after, nonce_bytes.len())
-                .unwrap();
+        let full_tx = serialize_tx_for_txid(&[input.clone()], &full_outputs, TxParams::default());
+        let (prefix, suffix) = split_tx_for_mining(
+            &[input],
+            &outputs_before,
+            &outputs_after,
+            nonce_bytes.len(),
+            TxParams::default(),
+        )
+        .unwrap();
 
         let mut rebuilt = prefix;
         rebuilt.extend_from_slice(&nonce_bytes);
@@ -511,22 +788,129 @@ mod tests {
             amount: 50_000,
         }];
 
-        // Our serialization (txid view, no witnesses).
-        let ours = serialize_tx_for_txid(&[input.clone()], &outputs);
-        let our_txid = double_sha256(&ours);
+        let truc_locked = TxParams {
+            version: 3,
+            lock_time: 850_000,
+        };
+        for params in [TxParams::default(), truc_locked] {
+            // Our serialization (txid view, no witnesses).
+            let ours = serialize_tx_for_txid(&[input.clone()], &outputs, params);
+            let our_txid = double_sha256(&ours);
+
+            // Build the same transaction with the bitcoin crate to cross-check.
+            let btc_tx = Transaction {
+                version: Version(params.version as i32),
+                lock_time: LockTime::from_consensus(params.lock_time),
+                input: vec![TxIn {
+                    previous_output: bitcoin::OutPoint {
+                        txid: Txid::from_raw_hash(bitcoin::hashes::sha256d::Hash::from_byte_array(
+                            input.txid,
+                        )),
+                        vout: input.vout,
+                    },
+                    script_sig: bitcoin::ScriptBuf::new(),
+                    sequence: bitcoin::Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: bitcoin::Witness::new(),
+                }],
+                output: outputs
+                    .iter()
+                    .map(|o| TxOut {
+                        value: Amount::from_sat(o.amount),
+                        script_pubkey: bitcoin::ScriptBuf::from_bytes(o.script_pubkey.clone()),
+                    })
+                    .collect(),
+            };
+
+            let btc_serialized = consensus::encode::serialize(&btc_tx);
+            assert_eq!(ours, btc_serialized);
+
+            let btc_txid = btc_tx.compute_txid().to_raw_hash().to_byte_array();
+            assert_eq!(our_txid, btc_txid);
+        }
+    }
+
+    #[test]
+    fn decodes_transactions_with_and_without_witnesses() {
+        let mut input = sample_input();
+        input.redeem_script = Some(p2wpkh_script(0x33));
+        let outputs = vec![
+            TxOutput {
+                script_pubkey: p2wpkh_script(0x44),
+                amount: 25_000,
+            },
+            TxOutput {
+                script_pubkey: create_op_return_script(&[0xaa; 80]),
+                amount: 0,
+            },
+        ];
+        let params = TxParams {
+            version: 3,
+            lock_time: 850_000,
+        };
+        let legacy = serialize_tx_for_txid(&[input.clone()], &outputs, params);
+
+        let mut btc_tx: Transaction = consensus::deserialize(&legacy).expect("transaction parses");
+        btc_tx.input[0].witness = bitcoin::Witness::from_slice(&[vec![0x30; 72], vec![0x02; 33]]);
+        let with_witness = consensus::encode::serialize(&btc_tx);
+        assert_ne!(with_witness, legacy);
+
+        for raw in [&legacy, &with_witness] {
+            let decoded = decode_tx(raw).expect("transaction decodes");
+            assert_eq!(decoded.params, params);
+            assert_eq!(decoded.outputs, outputs);
+            assert_eq!(decoded.inputs.len(), 1);
+            assert_eq!(decoded.inputs[0].txid, input.txid);
+            assert_eq!(decoded.inputs[0].vout, input.vout);
+            assert_eq!(decoded.inputs[0].script_sig, input.script_sig());
+            assert_eq!(decoded.inputs[0].sequence, input.sequence);
+            assert_eq!(decoded.txid, double_sha256(&legacy));
+        }
+        assert_eq!(decode_tx(&legacy).unwrap().weight, legacy.len() * 4);
+        assert_eq!(
+            decode_tx(&with_witness).unwrap().weight,
+            btc_tx.weight().to_wu() as usize
+        );
+
+        let mut trailing = legacy.clone();
+        trailing.push(0x00);
+        assert_eq!(decode_tx(&trailing), Err(TxError::Malformed));
+        assert_eq!(
+            decode_tx(&legacy[..legacy.len() - 1]),
+            Err(TxError::Malformed)
+        );
+    }
 
-        // Build the same transaction with the bitcoin crate to cross-check.
+    #[test]
+    fn nested_segwit_script_sig_is_part_of_txid() {
+        // P2SH-P2WPKH: the scriptSig pushes the wrapped P2WPKH program.
+        let mut input = sample_input();
+        input.script_pubkey = vec![0xa9, 0x14];
+        input.script_pubkey.extend([0x55; 20]);
+        input.script_pubkey.push(0x87);
+        input.redeem_script = Some(p2wpkh_script(0x22));
+        let outputs_before = vec![TxOutput {
+            script_pubkey: p2wpkh_script(0x33),
+            amount: 40_000,
+        }];
+        let nonce_bytes = [0x01, 0x02];
+        let mut outputs = outputs_before.clone();
+        outputs.push(TxOutput {
+            script_pubkey: create_op_return_script(&nonce_bytes),
+            amount: 0,
+        });
+
+        let ours = serialize_tx_for_txid(&[input.clone()], &outputs, TxParams::default());
+        let mut script_sig = vec![0x16];
+        script_sig.extend(p2wpkh_script(0x22));
         let btc_tx = Transaction {
             version: Version::TWO,
             lock_time: LockTime::ZERO,
             input: vec![TxIn {
                 previous_output: bitcoin::OutPoint {
-                    txid: Txid::from_raw_hash(bitcoin::hashes::sha256d::Hash::from_byte_array(
-                        input.txid,
-                    )),
+                    txid: Txid::from_raw_hash(sha256d::Hash::from_byte_array(input.txid)),
                     vout: input.vout,
                 },
-                script_sig: bitcoin::ScriptBuf::new(),
+                script_sig: bitcoin::ScriptBuf::from_bytes(script_sig),
                 sequence: bitcoin::Sequence::ENABLE_RBF_NO_LOCKTIME,
                 witness: bitcoin::Witness::new(),
             }],
@@ -538,12 +922,28 @@ mod tests {
                 })
                 .collect(),
         };
+        assert_eq!(ours, consensus::encode::serialize(&btc_tx));
 
-        let btc_serialized = consensus::encode::serialize(&btc_tx);
-        assert_eq!(ours, btc_serialized);
+        let (prefix, suffix) = split_tx_for_mining(
+            &[input.clone()],
+            &outputs_before,
+            &[],
+            nonce_bytes.len(),
+            TxParams::default(),
+        )
+        .unwrap();
+        let mut rebuilt = prefix;
+        rebuilt.extend_from_slice(&nonce_bytes);
+        rebuilt.extend_from_slice(&suffix);
+        assert_eq!(rebuilt, ours);
 
-        let btc_txid = btc_tx.compute_txid().to_raw_hash().to_byte_array();
-        assert_eq!(our_txid, btc_txid);
+        // The PSBT view keeps the scriptSig empty, as BIP174 requires.
+        let mut plain = input.clone();
+        plain.redeem_script = None;
+        assert_eq!(
+            serialize_unsigned_tx(&[input], &outputs, TxParams::default()),
+            serialize_tx_for_txid(&[plain], &outputs, TxParams::default())
+        );
     }
 
     #[test]
@@ -635,7 +1035,7 @@ mod tests {
             list.extend(outputs_after.clone());
             list
         };
-        let full_tx = serialize_tx_for_txid(&[input.clone()], &full_outputs);
+        let full_tx = serialize_tx_for_txid(&[input.clone()], &full_outputs, TxParams::default());
 
         // Split and reconstruct
         let (prefix, suffix) = split_tx_for_distribution_mining(
@@ -644,6 +1044,7 @@ mod tests {
             &outputs_after,
             &distribution,
             cbor_nonce_len,
+            TxParams::default(),
         )
         .unwrap();
 
@@ -681,7 +1082,7 @@ mod tests {
             list.extend(outputs_after.clone());
             list
         };
-        let full_tx = serialize_tx_for_txid(&[input.clone()], &full_outputs);
+        let full_tx = serialize_tx_for_txid(&[input.clone()], &full_outputs, TxParams::default());
 
         let (prefix, suffix) = split_tx_for_distribution_mining(
             &[input],
@@ -689,6 +1090,7 @@ mod tests {
             &outputs_after,
             &distribution,
             cbor_nonce.len(),
+            TxParams::default(),
         )
         .unwrap();
 
@@ -715,6 +1117,7 @@ mod tests {
             &outputs_after,
             &distribution,
             cbor_uint_length(0), // 1-byte nonce
+            TxParams::default(),
         )
         .unwrap();
 
@@ -750,6 +1153,7 @@ mod tests {
             &outputs_after,
             &distribution,
             4, // invalid length for CBOR uint
+            TxParams::default(),
         )
         .unwrap_err();
 
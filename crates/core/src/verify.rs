@@ -0,0 +1,524 @@
+//! Checks a signed transaction against the transaction that was mined.
+//!
+//! Wallets may reorder outputs, change sequence numbers or drop the OP_RETURN while
+//! signing, and any of these changes the txid. [`verify_signed_tx`] recomputes the
+//! txid of what was signed, lists what differs from the mined transaction and reports
+//! the size and fee rate with the real witnesses attached.
+
+use alloc::vec::Vec;
+
+use crate::{
+    cbor::encode_cbor_uint_into,
+    fees::FeeRate,
+    hash::count_leading_zero_bits,
+    miner::{encode_nonce_fixed_into, MiningTemplate},
+    policy::is_op_return,
+    psbt::{extract_tx, parse_psbt, PsbtError, PSBT_MAGIC},
+    tx::{decode_tx, serialize_tx_for_txid, DecodedTx, TxError, TxOutput},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum VerifyError {
+    #[error("transaction error: {0}")]
+    Tx(#[from] TxError),
+    #[error("psbt error: {0}")]
+    Psbt(#[from] PsbtError),
+    #[error("nonce {nonce} does not encode to the template's {nonce_len}-byte nonce")]
+    NonceLength { nonce: u64, nonce_len: u8 },
+}
+
+/// The transaction that was mined, which signed transactions are checked against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinedTx {
+    pub tx: DecodedTx,
+    /// Amounts of the spent outputs in input order, needed to report the fee.
+    pub input_amounts: Option<Vec<u64>>,
+}
+
+impl MinedTx {
+    /// Read the unsigned PSBT returned with the mined txid.
+    pub fn from_psbt(psbt: &[u8]) -> Result<Self, VerifyError> {
+        let parsed = parse_psbt(psbt)?;
+        let tx = serialize_tx_for_txid(&parsed.inputs, &parsed.outputs, parsed.tx_params);
+        Ok(Self {
+            tx: decode_tx(&tx)?,
+            input_amounts: Some(parsed.inputs.iter().map(|input| input.amount).collect()),
+        })
+    }
+
+    /// Complete a mining template with the winning nonce. Spent amounts are unknown,
+    /// so verifying a raw transaction against it reports no fee.
+    pub fn from_template(
+        template: &MiningTemplate,
+        nonce: u64,
+        use_cbor_nonce: bool,
+    ) -> Result<Self, VerifyError> {
+        let mut nonce_buf = [0u8; 9];
+        let written = if use_cbor_nonce {
+            encode_cbor_uint_into(nonce, &mut nonce_buf)
+        } else {
+            encode_nonce_fixed_into(nonce, template.nonce_len, &mut nonce_buf).unwrap_or(0)
+        };
+        if written != template.nonce_len as usize {
+            return Err(VerifyError::NonceLength {
+                nonce,
+                nonce_len: template.nonce_len,
+            });
+        }
+
+        let mut tx = template.prefix.clone();
+        tx.extend_from_slice(&nonce_buf[..written]);
+        tx.extend_from_slice(&template.suffix);
+        Ok(Self {
+            tx: decode_tx(&tx)?,
+            input_amounts: None,
+        })
+    }
+
+    /// The OP_RETURN carrying the nonce: the last OP_RETURN output.
+    fn payload(&self) -> Option<&TxOutput> {
+        self.tx
+            .outputs
+            .iter()
+            .rev()
+            .find(|output| is_op_return(&output.script_pubkey))
+    }
+}
+
+/// A field of the signed transaction that differs from the mined one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxMismatch {
+    Version {
+        expected: u32,
+        actual: u32,
+    },
+    LockTime {
+        expected: u32,
+        actual: u32,
+    },
+    InputCount {
+        expected: usize,
+        actual: usize,
+    },
+    /// The input spends another outpoint, e.g. because inputs were reordered.
+    Outpoint {
+        index: usize,
+    },
+    Sequence {
+        index: usize,
+        expected: u32,
+        actual: u32,
+    },
+    /// The scriptSig differs, e.g. a nested SegWit input lost its redeem script push.
+    ScriptSig {
+        index: usize,
+    },
+    /// The signed PSBT claims another spent amount than the mined one. The txid is
+    /// unchanged, but SegWit signatures commit to the amount.
+    InputAmount {
+        index: usize,
+        expected: u64,
+        actual: u64,
+    },
+    OutputCount {
+        expected: usize,
+        actual: usize,
+    },
+    /// Amount or scriptPubKey differs, e.g. because outputs were reordered.
+    Output {
+        index: usize,
+    },
+}
+
+/// Outcome of [`verify_signed_tx`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    /// Txid of the signed transaction, in internal byte order.
+    pub txid: [u8; 32],
+    /// Whether the signed transaction keeps the mined txid.
+    pub txid_matches: bool,
+    pub leading_zero_bits: u16,
+    /// Whether the mined OP_RETURN, nonce and ZELD payload included, is still among
+    /// the outputs.
+    pub payload_intact: bool,
+    /// Empty when `txid_matches`, unless a signed PSBT disagrees on spent amounts.
+    pub mismatches: Vec<TxMismatch>,
+    pub weight: usize,
+    pub vsize: usize,
+    /// Fee paid, when the spent amounts are known and cover the outputs.
+    pub fee: Option<u64>,
+    pub fee_rate: Option<FeeRate>,
+}
+
+/// Check a signed transaction, given as a finalized PSBT or as raw bytes, against
+/// the mined one.
+pub fn verify_signed_tx(mined: &MinedTx, signed: &[u8]) -> Result<Verification, VerifyError> {
+    let (raw, signed_amounts) = if signed.starts_with(PSBT_MAGIC) {
+        let psbt = parse_psbt(signed)?;
+        let amounts: Vec<u64> = psbt.inputs.iter().map(|input| input.amount).collect();
+        (extract_tx(&psbt)?, Some(amounts))
+    } else {
+        (signed.to_vec(), None)
+    };
+    let tx = decode_tx(&raw)?;
+    let mut mismatches = compare(&mined.tx, &tx);
+
+    let same_inputs = !mismatches.iter().any(|mismatch| {
+        matches!(
+            mismatch,
+            TxMismatch::InputCount { .. } | TxMismatch::Outpoint { .. }
+        )
+    });
+    // The mined amounts are what was planned; a signed PSBT is only trusted for
+    // inputs the mined transaction does not describe.
+    let input_amounts = match mined.input_amounts.as_ref().filter(|_| same_inputs) {
+        Some(expected) => {
+            for (index, (expected, actual)) in expected
+                .iter()
+                .zip(signed_amounts.iter().flatten())
+                .enumerate()
+            {
+                if expected != actual {
+                    mismatches.push(TxMismatch::InputAmount {
+                        index,
+                        expected: *expected,
+                        actual: *actual,
+                    });
+                }
+            }
+            Some(expected.clone())
+        }
+        None => signed_amounts,
+    };
+    let fee = input_amounts.and_then(|amounts| {
+        let input_total = amounts
+            .iter()
+            .try_fold(0u64, |total, amount| total.checked_add(*amount))?;
+        let output_total = tx
+            .outputs
+            .iter()
+            .try_fold(0u64, |total, output| total.checked_add(output.amount))?;
+        input_total.checked_sub(output_total)
+    });
+    let vsize = tx.weight.div_ceil(4);
+
+    Ok(Verification {
+        txid: tx.txid,
+        txid_matches: tx.txid == mined.tx.txid,
+        leading_zero_bits: count_leading_zero_bits(&tx.txid),
+        payload_intact: mined
+            .payload()
+            .is_some_and(|payload| tx.outputs.contains(payload)),
+        mismatches,
+        weight: tx.weight,
+        vsize,
+        fee,
+        fee_rate: fee.map(|fee| FeeRate::from_fee_and_vsize(fee, vsize)),
+    })
+}
+
+fn compare(mined: &DecodedTx, signed: &DecodedTx) -> Vec<TxMismatch> {
+    let mut mismatches = Vec::new();
+    if mined.params.version != signed.params.version {
+        mismatches.push(TxMismatch::Version {
+            expected: mined.params.version,
+            actual: signed.params.version,
+        });
+    }
+    if mined.params.lock_time != signed.params.lock_time {
+        mismatches.push(TxMismatch::LockTime {
+            expected: mined.params.lock_time,
+            actual: signed.params.lock_time,
+        });
+    }
+
+    if mined.inputs.len() != signed.inputs.len() {
+        mismatches.push(TxMismatch::InputCount {
+            expected: mined.inputs.len(),
+            actual: signed.inputs.len(),
+        });
+    }
+    for (index, (expected, actual)) in mined.inputs.iter().zip(&signed.inputs).enumerate() {
+        if (expected.txid, expected.vout) != (actual.txid, actual.vout) {
+            mismatches.push(TxMismatch::Outpoint { index });
+            continue;
+        }
+        if expected.sequence != actual.sequence {
+            mismatches.push(TxMismatch::Sequence {
+                index,
+                expected: expected.sequence,
+                actual: actual.sequence,
+            });
+        }
+        if expected.script_sig != actual.script_sig {
+            mismatches.push(TxMismatch::ScriptSig { index });
+        }
+    }
+
+    if mined.outputs.len() != signed.outputs.len() {
+        mismatches.push(TxMismatch::OutputCount {
+            expected: mined.outputs.len(),
+            actual: signed.outputs.len(),
+        });
+    }
+    for (index, (expected, actual)) in mined.outputs.iter().zip(&signed.outputs).enumerate() {
+        if expected != actual {
+            mismatches.push(TxMismatch::Output { index });
+        }
+    }
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        address::Network,
+        nonce::encode_nonce,
+        psbt::{create_psbt, PsbtPair, PsbtVersion},
+        tx::{create_op_return_script, split_tx_for_mining, TxInput, TxParams, DEFAULT_SEQUENCE},
+    };
+    use alloc::vec;
+
+    const NONCE: u64 = 0x01_2345;
+
+    fn p2wpkh(byte: u8) -> Vec<u8> {
+        let mut script = vec![0x00, 0x14];
+        script.extend([byte; 20]);
+        script
+    }
+
+    fn mined_tx() -> (Vec<TxInput>, Vec<TxOutput>, TxParams) {
+        let inputs = (0..2u8)
+            .map(|i| TxInput {
+                txid: [0x10 + i; 32],
+                vout: i as u32,
+                script_pubkey: p2wpkh(0x20 + i),
+                amount: 50_000,
+                sequence: DEFAULT_SEQUENCE,
+                witness_hint: None,
+                redeem_script: None,
+                psbt: Default::default(),
+            })
+            .collect();
+        let outputs = vec![
+            TxOutput {
+                script_pubkey: p2wpkh(0x30),
+                amount: 60_000,
+            },
+            TxOutput {
+                script_pubkey: p2wpkh(0x31),
+                amount: 39_000,
+            },
+            TxOutput {
+                script_pubkey: create_op_return_script(&encode_nonce(NONCE)),
+                amount: 0,
+            },
+        ];
+        (inputs, outputs, TxParams::default())
+    }
+
+    fn mined_psbt() -> Vec<u8> {
+        let (inputs, outputs, params) = mined_tx();
+        create_psbt(
+            &inputs,
+            &outputs,
+            &[],
+            &[],
+            params,
+            Network::Mainnet,
+            PsbtVersion::V0,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn accepts_a_signed_psbt_and_reports_its_real_size() {
+        let mined = MinedTx::from_psbt(&mined_psbt()).unwrap();
+        let (inputs, outputs, params) = mined_tx();
+        assert_eq!(
+            mined.tx.txid,
+            decode_tx(&serialize_tx_for_txid(&inputs, &outputs, params))
+                .unwrap()
+                .txid
+        );
+
+        let mut parsed = parse_psbt(&mined_psbt()).unwrap();
+        // A 71-byte signature and a compressed key per input.
+        let mut witness = vec![0x02, 71];
+        witness.extend([0x30; 71]);
+        witness.push(33);
+        witness.extend([0x02; 33]);
+        for input in &mut parsed.inputs {
+            input.psbt.unknown.push(PsbtPair {
+                key: vec![0x08],
+                value: witness.clone(),
+            });
+        }
+        let signed = create_psbt(
+            &parsed.inputs,
+            &parsed.outputs,
+            &parsed.output_fields,
+            &parsed.global_pairs,
+            parsed.tx_params,
+            Network::Mainnet,
+            PsbtVersion::V0,
+        )
+        .unwrap();
+
+        let verification = verify_signed_tx(&mined, &signed).unwrap();
+        assert!(verification.txid_matches);
+        assert!(verification.payload_intact);
+        assert!(verification.mismatches.is_empty());
+        assert_eq!(verification.txid, mined.tx.txid);
+        assert_eq!(
+            verification.leading_zero_bits,
+            count_leading_zero_bits(&mined.tx.txid)
+        );
+        let base_size = serialize_tx_for_txid(&inputs, &outputs, params).len();
+        let weight = base_size * 4 + 2 + 2 * witness.len();
+        assert_eq!(verification.weight, weight);
+        assert_eq!(verification.vsize, weight.div_ceil(4));
+        assert_eq!(verification.fee, Some(1_000));
+        assert_eq!(
+            verification.fee_rate,
+            Some(FeeRate::from_fee_and_vsize(1_000, weight.div_ceil(4)))
+        );
+
+        assert_eq!(
+            verify_signed_tx(&mined, &mined_psbt()),
+            Err(VerifyError::Psbt(PsbtError::NotFinalized { index: 0 }))
+        );
+    }
+
+    #[test]
+    fn reports_what_a_wallet_changed() {
+        let mined = MinedTx::from_psbt(&mined_psbt()).unwrap();
+        let (inputs, outputs, params) = mined_tx();
+
+        let mut reordered = outputs.clone();
+        reordered.swap(0, 1);
+        let raw = serialize_tx_for_txid(&inputs, &reordered, params);
+        let verification = verify_signed_tx(&mined, &raw).unwrap();
+        assert!(!verification.txid_matches);
+        assert!(verification.payload_intact);
+        assert_eq!(
+            verification.mismatches,
+            vec![
+                TxMismatch::Output { index: 0 },
+                TxMismatch::Output { index: 1 }
+            ]
+        );
+        assert_eq!(verification.fee, Some(1_000));
+
+        let mut final_sequence = inputs.clone();
+        final_sequence[1].sequence = u32::MAX;
+        let raw = serialize_tx_for_txid(&final_sequence, &outputs, params);
+        assert_eq!(
+            verify_signed_tx(&mined, &raw).unwrap().mismatches,
+            vec![TxMismatch::Sequence {
+                index: 1,
+                expected: DEFAULT_SEQUENCE,
+                actual: u32::MAX
+            }]
+        );
+
+        let raw = serialize_tx_for_txid(&inputs, &outputs[..2], params);
+        let verification = verify_signed_tx(&mined, &raw).unwrap();
+        assert!(!verification.payload_intact);
+        assert_eq!(
+            verification.mismatches,
+            vec![TxMismatch::OutputCount {
+                expected: 3,
+                actual: 2
+            }]
+        );
+        assert_eq!(verification.fee, Some(1_000));
+
+        let mut swapped_inputs = inputs.clone();
+        swapped_inputs.swap(0, 1);
+        let raw = serialize_tx_for_txid(&swapped_inputs, &outputs, params);
+        let verification = verify_signed_tx(&mined, &raw).unwrap();
+        assert_eq!(
+            verification.mismatches,
+            vec![
+                TxMismatch::Outpoint { index: 0 },
+                TxMismatch::Outpoint { index: 1 }
+            ]
+        );
+        assert_eq!(verification.fee, None);
+    }
+
+    #[test]
+    fn trusts_the_mined_amounts_over_the_signed_psbt() {
+        let mined = MinedTx::from_psbt(&mined_psbt()).unwrap();
+        let mut parsed = parse_psbt(&mined_psbt()).unwrap();
+        for input in &mut parsed.inputs {
+            input.psbt.unknown.push(PsbtPair {
+                key: vec![0x08],
+                value: vec![0x01, 0x01, 0x00],
+            });
+        }
+        parsed.inputs[1].amount = 150_000;
+        let signed = create_psbt(
+            &parsed.inputs,
+            &parsed.outputs,
+            &parsed.output_fields,
+            &parsed.global_pairs,
+            parsed.tx_params,
+            Network::Mainnet,
+            PsbtVersion::V0,
+        )
+        .unwrap();
+
+        let verification = verify_signed_tx(&mined, &signed).unwrap();
+        assert!(verification.txid_matches);
+        assert_eq!(
+            verification.mismatches,
+            vec![TxMismatch::InputAmount {
+                index: 1,
+                expected: 50_000,
+                actual: 150_000
+            }]
+        );
+        assert_eq!(verification.fee, Some(1_000));
+
+        let unknown = MinedTx {
+            input_amounts: None,
+            ..mined
+        };
+        let verification = verify_signed_tx(&unknown, &signed).unwrap();
+        assert!(verification.mismatches.is_empty());
+        assert_eq!(verification.fee, Some(101_000));
+    }
+
+    #[test]
+    fn rebuilds_the_mined_transaction_from_a_template() {
+        let (inputs, outputs, params) = mined_tx();
+        let nonce_len = encode_nonce(NONCE).len();
+        let (prefix, suffix) =
+            split_tx_for_mining(&inputs, &outputs[..2], &[], nonce_len, params).unwrap();
+        let template = MiningTemplate {
+            prefix,
+            suffix,
+            nonce_len: nonce_len as u8,
+        };
+
+        let mined = MinedTx::from_template(&template, NONCE, false).unwrap();
+        assert_eq!(mined.tx, MinedTx::from_psbt(&mined_psbt()).unwrap().tx);
+        assert_eq!(mined.input_amounts, None);
+        let raw = serialize_tx_for_txid(&inputs, &outputs, params);
+        let verification = verify_signed_tx(&mined, &raw).unwrap();
+        assert!(verification.txid_matches);
+        assert_eq!(verification.fee, None);
+
+        assert_eq!(
+            MinedTx::from_template(&template, 0xff, false),
+            Err(VerifyError::NonceLength {
+                nonce: 0xff,
+                nonce_len: 3
+            })
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::time::Instant;
 
+use zeldhash_miner_core::CompiledPattern;
 use zeldhash_miner_gpu::{
     calibrate_batch_size, dispatch_mining_batch, GpuContext, GpuError, MiningBatch,
 };
@@ -12,12 +13,14 @@ struct BenchSample {
 }
 
 async fn bench_batch(ctx: &GpuContext, batch_size: u32) -> Result<BenchSample, GpuError> {
+    // An all-zero txid is effectively impossible and keeps the kernel busy.
+    let pattern = CompiledPattern::leading_zeros(64);
     let batch = MiningBatch {
         tx_prefix: b"bench-prefix",
         tx_suffix: b"bench-suffix",
         start_nonce: START_NONCE,
         batch_size,
-        target_zeros: 64, // effectively impossible target; keeps the kernel busy
+        pattern: &pattern,
         use_cbor_nonce: false,
     };
 
@@ -35,13 +38,14 @@ async fn bench_batch(ctx: &GpuContext, batch_size: u32) -> Result<BenchSample, G
 async fn memory_pressure_check(ctx: &GpuContext, batch_size: u32) -> Result<(), GpuError> {
     // Use large buffers to validate allocation/dispatch under heavier pressure.
     let blob = vec![0u8; 512 * 1024];
+    let pattern = CompiledPattern::leading_zeros(64);
     for i in 0..3 {
         let batch = MiningBatch {
             tx_prefix: &blob,
             tx_suffix: &blob,
             start_nonce: START_NONCE + (i as u64) * batch_size as u64,
             batch_size,
-            target_zeros: 64,
+            pattern: &pattern,
             use_cbor_nonce: false,
         };
         let _ = dispatch_mining_batch(ctx, &batch).await?;
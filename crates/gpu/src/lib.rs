@@ -21,11 +21,14 @@ use futures::channel::oneshot;
 use bytemuck::{cast_slice, pod_read_unaligned, Pod, Zeroable};
 use thiserror::Error;
 use wgpu::util::DeviceExt;
-use zeldhash_miner_core::encode_nonce;
+use zeldhash_miner_core::{
+    double_sha256, encode_cbor_uint, encode_nonce, CompiledPattern, MAX_PATTERN_ALTERNATIVES,
+};
 
 #[cfg_attr(test, allow(dead_code))]
 const WORKGROUP_SIZE: u32 = 256;
-const MAX_RESULTS: usize = 8;
+/// Most hits a single dispatch reports.
+pub const MAX_RESULTS: usize = 8;
 
 const SHADER_WGSL: &str = include_str!("shader.wgsl");
 
@@ -172,8 +175,8 @@ pub struct MiningBatch<'a> {
     pub start_nonce: u64,
     /// Number of attempts.
     pub batch_size: u32,
-    /// Target leading zeros (txid view).
-    pub target_zeros: u8,
+    /// Txid pattern a nonce must produce (see [`CompiledPattern::leading_zeros`]).
+    pub pattern: &'a CompiledPattern,
     /// When true, encode the nonce as CBOR (major type 0) rather than raw big-endian bytes.
     pub use_cbor_nonce: bool,
 }
@@ -184,7 +187,7 @@ struct MiningParams {
     start_nonce_lo: u32,
     start_nonce_hi: u32,
     batch_size: u32,
-    target_zeros: u32,
+    pattern_count: u32, // alternatives in use; 0 never matches
     prefix_len: u32,
     suffix_len: u32,
     nonce_len: u32,
@@ -193,6 +196,37 @@ struct MiningParams {
     _pad3: u32,
     _pad4: u32,
     _pad5: u32,
+    // Per alternative: two vec4 rows covering the eight big-endian digest words.
+    pattern_mask: [[u32; 4]; 2 * MAX_PATTERN_ALTERNATIVES],
+    pattern_value: [[u32; 4]; 2 * MAX_PATTERN_ALTERNATIVES],
+}
+
+impl MiningParams {
+    fn set_pattern(&mut self, pattern: &CompiledPattern) {
+        let alternatives = pattern.alternatives();
+        self.pattern_count = alternatives.len().min(MAX_PATTERN_ALTERNATIVES) as u32;
+        for (idx, alt) in alternatives
+            .iter()
+            .take(MAX_PATTERN_ALTERNATIVES)
+            .enumerate()
+        {
+            let mask = digest_words(alt.mask());
+            let value = digest_words(alt.value());
+            for row in 0..2 {
+                self.pattern_mask[idx * 2 + row].copy_from_slice(&mask[row * 4..row * 4 + 4]);
+                self.pattern_value[idx * 2 + row].copy_from_slice(&value[row * 4..row * 4 + 4]);
+            }
+        }
+    }
+}
+
+/// Pack hash-order bytes into the big-endian words the shader's digest uses.
+fn digest_words(bytes: &[u8; 32]) -> [u32; 8] {
+    let mut words = [0u32; 8];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    words
 }
 
 #[repr(C, align(16))]
@@ -209,7 +243,7 @@ struct ResultEntry {
 #[derive(Clone, Copy, Pod, Zeroable)]
 struct ResultBuffer {
     found_count: u32,
-    _pad: u32, // alignment to 8-byte boundary for following array
+    lowest_inv: u32, // !offset of the lowest hit, 0 when nothing matched
     _align_pad: [u32; 2],
     results: [ResultEntry; MAX_RESULTS],
     _tail_pad: [u32; 2],
@@ -631,20 +665,17 @@ fn create_buffers(
             .write_buffer(&buffers.suffix, 0, cast_slice(&suffix_words));
     }
 
-    let params = MiningParams {
+    let mut params = MiningParams {
         start_nonce_lo: batch.start_nonce as u32,
         start_nonce_hi: (batch.start_nonce >> 32) as u32,
         batch_size: batch.batch_size,
-        target_zeros: batch.target_zeros as u32,
         prefix_len: batch.tx_prefix.len() as u32,
         suffix_len: batch.tx_suffix.len() as u32,
         nonce_len,
         use_cbor_nonce: batch.use_cbor_nonce as u32,
-        _pad2: 0,
-        _pad3: 0,
-        _pad4: 0,
-        _pad5: 0,
+        ..MiningParams::zeroed()
     };
+    params.set_pattern(batch.pattern);
     ctx.queue.write_buffer(
         &buffers.params,
         0,
@@ -677,11 +708,22 @@ fn create_buffers(
     Ok(bind_group)
 }
 
+/// Hits copied back from the result buffer.
 #[cfg_attr(test, allow(dead_code))]
-fn parse_results(mapped: &[u8]) -> Vec<MineResult> {
+struct ParsedResults {
+    hits: Vec<MineResult>,
+    /// Batch offset of the lowest qualifying nonce, even when its slot overflowed.
+    lowest_offset: Option<u32>,
+}
+
+#[cfg_attr(test, allow(dead_code))]
+fn parse_results(mapped: &[u8]) -> ParsedResults {
     let required = std::mem::size_of::<ResultBuffer>();
     if mapped.len() < required {
-        return Vec::new();
+        return ParsedResults {
+            hits: Vec::new(),
+            lowest_offset: None,
+        };
     }
 
     // Browser WebGPU can return a mapped slice that is not aligned to the
@@ -691,15 +733,60 @@ fn parse_results(mapped: &[u8]) -> Vec<MineResult> {
     let found = buffer.found_count as usize;
     let take = found.min(MAX_RESULTS);
 
-    let mut out = Vec::with_capacity(take);
+    let mut hits = Vec::with_capacity(take);
     for entry in buffer.results.iter().take(take) {
         let nonce = ((entry.nonce_hi as u64) << 32) | entry.nonce_lo as u64;
-        out.push(MineResult {
+        hits.push(MineResult {
             nonce,
             txid: to_u8_bytes(&entry.txid),
         });
     }
-    out
+    let lowest_offset = (found > 0).then_some(!buffer.lowest_inv);
+    ParsedResults {
+        hits,
+        lowest_offset,
+    }
+}
+
+/// Make sure the lowest qualifying nonce is part of `hits`.
+///
+/// When more than `MAX_RESULTS` threads match, the slots keep whichever threads
+/// won the race; the lowest hit is then re-hashed on the CPU and replaces the
+/// highest stored nonce so callers can always pick the minimum.
+#[cfg_attr(test, allow(dead_code))]
+fn ensure_lowest_hit(batch: &MiningBatch<'_>, parsed: ParsedResults) -> Vec<MineResult> {
+    let mut hits = parsed.hits;
+    let Some(offset) = parsed.lowest_offset else {
+        return hits;
+    };
+    let Some(lowest) = batch.start_nonce.checked_add(offset as u64) else {
+        return hits;
+    };
+    if hits.iter().any(|hit| hit.nonce == lowest) {
+        return hits;
+    }
+
+    let nonce_bytes = if batch.use_cbor_nonce {
+        encode_cbor_uint(lowest)
+    } else {
+        encode_nonce(lowest)
+    };
+    let mut message =
+        Vec::with_capacity(batch.tx_prefix.len() + nonce_bytes.len() + batch.tx_suffix.len());
+    message.extend_from_slice(batch.tx_prefix);
+    message.extend_from_slice(&nonce_bytes);
+    message.extend_from_slice(batch.tx_suffix);
+    let hit = MineResult {
+        nonce: lowest,
+        txid: double_sha256(&message),
+    };
+
+    if hits.len() < MAX_RESULTS {
+        hits.push(hit);
+    } else if let Some(highest) = hits.iter_mut().max_by_key(|hit| hit.nonce) {
+        *highest = hit;
+    }
+    hits
 }
 
 async fn dispatch_gpu(
@@ -766,10 +853,13 @@ async fn dispatch_gpu(
     let parsed = parse_results(&data);
     drop(data);
     fixed.staging.unmap();
-    Ok(parsed)
+    Ok(ensure_lowest_hit(batch, parsed))
 }
 
-/// Dispatch a mining batch on the GPU and return all matching nonces found.
+/// Dispatch a mining batch on the GPU and return the matching nonces found.
+///
+/// At most `MAX_RESULTS` (8) hits are returned; the lowest qualifying nonce of the
+/// batch is always among them.
 pub async fn dispatch_mining_batch(
     ctx: &GpuContext,
     batch: &MiningBatch<'_>,
@@ -798,20 +888,15 @@ pub async fn calibrate_batch_size(ctx: &GpuContext) -> Result<u32, GpuError> {
 
     let prefix_words = pad_bytes_to_words(DUMMY);
     let suffix_words = pad_bytes_to_words(DUMMY);
-    let params_template = MiningParams {
-        start_nonce_lo: 0,
-        start_nonce_hi: 0,
+    let mut params_template = MiningParams {
         batch_size: 1,
-        target_zeros: 64, // effectively impossible, keeps kernel busy
         prefix_len: DUMMY.len() as u32,
         suffix_len: DUMMY.len() as u32,
         nonce_len: 1,
-        use_cbor_nonce: 0,
-        _pad2: 0,
-        _pad3: 0,
-        _pad4: 0,
-        _pad5: 0,
+        ..MiningParams::zeroed()
     };
+    // An all-zero txid is effectively impossible and keeps the kernel busy.
+    params_template.set_pattern(&CompiledPattern::leading_zeros(64));
 
     let prefix_buf = ctx
         .device
@@ -975,7 +1060,7 @@ mod tests {
                 }
                 buf.extend_from_slice(batch.tx_suffix);
                 let hash = zeldhash_miner_core::double_sha256(&buf);
-                if zeldhash_miner_core::hash_meets_target(&hash, batch.target_zeros) {
+                if batch.pattern.matches(&hash) {
                     out.push(MineResult { nonce, txid: hash });
                 }
             }
@@ -1000,6 +1085,85 @@ mod tests {
         assert_eq!(bytes[3], 0x44);
     }
 
+    #[test]
+    fn pattern_params_use_digest_word_layout() {
+        let pattern = zeldhash_miner_core::TxidPattern::AnyOf(vec![
+            zeldhash_miner_core::TxidPattern::Prefix("ca".into()),
+            zeldhash_miner_core::TxidPattern::Suffix("fe".into()),
+        ])
+        .compile()
+        .unwrap();
+        let mut params = MiningParams::zeroed();
+        params.set_pattern(&pattern);
+
+        assert_eq!(params.pattern_count, 2);
+        // The txid prefix is the last digest byte: low byte of word 7.
+        assert_eq!(params.pattern_mask[1], [0, 0, 0, 0x0000_00ff]);
+        assert_eq!(params.pattern_value[1], [0, 0, 0, 0x0000_00ca]);
+        // The txid suffix is the first digest byte: high byte of word 0.
+        assert_eq!(params.pattern_mask[2], [0xff00_0000, 0, 0, 0]);
+        assert_eq!(params.pattern_value[2], [0xfe00_0000, 0, 0, 0]);
+    }
+
+    #[test]
+    fn bit_targets_mask_partial_nibbles() {
+        let mut params = MiningParams::zeroed();
+        params.set_pattern(&CompiledPattern::leading_zero_bits(12));
+
+        assert_eq!(params.pattern_count, 1);
+        // Last digest byte fully, then the high nibble of the one before it.
+        assert_eq!(params.pattern_mask[1], [0, 0, 0, 0x0000_f0ff]);
+        assert_eq!(params.pattern_value[1], [0; 4]);
+    }
+
+    #[test]
+    fn overflowed_results_keep_the_lowest_hit() {
+        let pattern = CompiledPattern::leading_zeros(0);
+        let batch = MiningBatch {
+            tx_prefix: b"a",
+            tx_suffix: b"b",
+            start_nonce: 100,
+            batch_size: 64,
+            pattern: &pattern,
+            use_cbor_nonce: true,
+        };
+        let expected = cpu_mine(&batch);
+
+        // Slots filled by threads that raced ahead of the lowest matching index.
+        let hits: Vec<MineResult> = expected[10..10 + MAX_RESULTS].to_vec();
+        let parsed = ParsedResults {
+            hits,
+            lowest_offset: Some(0),
+        };
+        let kept = ensure_lowest_hit(&batch, parsed);
+
+        assert_eq!(kept.len(), MAX_RESULTS);
+        let lowest = kept.iter().min_by_key(|hit| hit.nonce).unwrap();
+        assert_eq!(lowest, &expected[0]);
+        assert!(!kept
+            .iter()
+            .any(|hit| hit.nonce == expected[10 + MAX_RESULTS - 1].nonce));
+    }
+
+    #[test]
+    fn lowest_hit_already_present_is_left_alone() {
+        let pattern = CompiledPattern::leading_zeros(0);
+        let batch = MiningBatch {
+            tx_prefix: b"hello",
+            tx_suffix: b"world",
+            start_nonce: 0,
+            batch_size: 16,
+            pattern: &pattern,
+            use_cbor_nonce: false,
+        };
+        let hits = cpu_mine(&batch)[..3].to_vec();
+        let parsed = ParsedResults {
+            hits: hits.clone(),
+            lowest_offset: Some(0),
+        };
+        assert_eq!(ensure_lowest_hit(&batch, parsed), hits);
+    }
+
     #[test]
     fn gpu_matches_cpu_when_available() {
         let ctx = pollster::block_on(GpuContext::init());
@@ -1008,12 +1172,13 @@ mod tests {
             Err(_) => return, // Skip if WebGPU not available in CI environment.
         };
 
+        let pattern = CompiledPattern::leading_zeros(1);
         let batch = MiningBatch {
             tx_prefix: b"hello",
             tx_suffix: b"world",
             start_nonce: 0,
             batch_size: 64,
-            target_zeros: 1,
+            pattern: &pattern,
             use_cbor_nonce: false,
         };
 
@@ -1025,6 +1190,34 @@ mod tests {
         assert_eq!(cpu, gpu);
     }
 
+    #[test]
+    fn gpu_matches_cpu_for_vanity_pattern_when_available() {
+        let ctx = pollster::block_on(GpuContext::init());
+        let ctx = match ctx {
+            Ok(c) => c,
+            Err(_) => return, // Skip if WebGPU not available in CI environment.
+        };
+
+        let pattern = zeldhash_miner_core::TxidPattern::Masked("c?".into())
+            .compile()
+            .unwrap();
+        let batch = MiningBatch {
+            tx_prefix: b"hello",
+            tx_suffix: b"world",
+            start_nonce: 256,
+            batch_size: 512,
+            pattern: &pattern,
+            use_cbor_nonce: false,
+        };
+
+        let cpu = cpu_mine(&batch);
+        let gpu = pollster::block_on(dispatch_mining_batch(&ctx, &batch)).unwrap();
+        assert_eq!(
+            gpu.iter().map(|r| r.nonce).min(),
+            cpu.first().map(|r| r.nonce)
+        );
+    }
+
     #[test]
     fn gpu_collects_multiple_results_up_to_max_when_available() {
         let ctx = pollster::block_on(GpuContext::init());
@@ -1033,12 +1226,13 @@ mod tests {
             Err(_) => return, // Skip if WebGPU not available in CI environment.
         };
 
+        let pattern = CompiledPattern::leading_zeros(0); // every hash counts
         let batch = MiningBatch {
             tx_prefix: b"a",
             tx_suffix: b"b",
             start_nonce: 0,
             batch_size: (MAX_RESULTS as u32) + 2,
-            target_zeros: 0, // every hash counts
+            pattern: &pattern,
             use_cbor_nonce: false,
         };
 
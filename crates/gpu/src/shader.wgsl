@@ -3,12 +3,13 @@
 
 const WORKGROUP_SIZE: u32 = 256u;
 const MAX_RESULTS: u32 = 8u;
+const MAX_PATTERN_ALTERNATIVES: u32 = 8u;
 
 struct MiningParams {
     start_nonce_lo: u32,
     start_nonce_hi: u32,
     batch_size: u32,
-    target_zeros: u32,
+    pattern_count: u32,
     prefix_len: u32,
     suffix_len: u32,
     nonce_len: u32,
@@ -17,6 +18,9 @@ struct MiningParams {
     _pad3: u32,
     _pad4: u32,
     _pad5: u32,
+    // Two vec4 rows per alternative, matching the big-endian digest words.
+    pattern_mask: array<vec4<u32>, 16>,
+    pattern_value: array<vec4<u32>, 16>,
 };
 
 struct ResultEntry {
@@ -29,7 +33,8 @@ struct ResultEntry {
 
 struct ResultBuffer {
     found_count: atomic<u32>,
-    _pad: u32,
+    // Bitwise NOT of the lowest hit index (0 = no hit); survives result overflow.
+    lowest_inv: atomic<u32>,
     _align_pad_0: u32,
     _align_pad_1: u32,
     results: array<ResultEntry, MAX_RESULTS>,
@@ -397,51 +402,26 @@ fn double_sha(nonce: vec2<u32>, nonce_len_val: u32, msg_len: u32) -> array<u32,
     return sha256_bytes(bytes, 32u);
 }
 
-fn hash_to_txid_bytes(hash_words: array<u32, 8>) -> array<u32, 32> {
-    // Copy to a mutable array to allow runtime indexing (Naga requires a
-    // variable, not a value parameter, for dynamic indices).
-    var words = hash_words;
-    var bytes: array<u32, 32>;
-    // Convert to txid view (little-endian overall, i.e., reverse of big-end digest).
-    for (var i: u32 = 0u; i < 8u; i = i + 1u) {
-        let word = words[i];
-        let base = (7u - i) * 4u;
-        bytes[base + 0u] = word & 0xffu;
-        bytes[base + 1u] = (word >> 8u) & 0xffu;
-        bytes[base + 2u] = (word >> 16u) & 0xffu;
-        bytes[base + 3u] = (word >> 24u) & 0xffu;
-    }
-    return bytes;
-}
-
-fn leading_zeros(hash_words: array<u32, 8>) -> u32 {
+fn meets_pattern(hash_words: array<u32, 8>) -> bool {
     // Copy to a mutable array so we can use dynamic indexing (Naga requires a
     // var when the index is not a compile-time constant).
-    var bytes = hash_to_txid_bytes(hash_words);
-
-    var zeros: u32 = 0u;
-    for (var i: u32 = 0u; i < 32u; i = i + 1u) {
-        let byte = bytes[i];
-        if byte == 0u {
-            zeros = zeros + 2u;
-            continue;
+    var words = hash_words;
+    let count = min(PARAMS.pattern_count, MAX_PATTERN_ALTERNATIVES);
+    for (var alt: u32 = 0u; alt < count; alt = alt + 1u) {
+        var ok = true;
+        for (var i: u32 = 0u; i < 8u; i = i + 1u) {
+            let row = alt * 2u + i / 4u;
+            let mask = PARAMS.pattern_mask[row][i % 4u];
+            if (words[i] & mask) != PARAMS.pattern_value[row][i % 4u] {
+                ok = false;
+                break;
+            }
         }
-        if (byte >> 4u) == 0u {
-            zeros = zeros + 1u;
+        if ok {
+            return true;
         }
-        break;
-    }
-    return zeros;
-}
-
-fn meets_target(hash_words: array<u32, 8>, target_zeros: u32) -> bool {
-    if target_zeros == 0u {
-        return true;
-    }
-    if target_zeros > 64u {
-        return false;
     }
-    return leading_zeros(hash_words) >= target_zeros;
+    return false;
 }
 
 fn store_result(slot: u32, nonce: vec2<u32>, hash_words: array<u32, 8>) {
@@ -481,7 +461,8 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let msg_len = PARAMS.prefix_len + nonce_len_val + PARAMS.suffix_len;
 
     let hash_words = double_sha(nonce, nonce_len_val, msg_len);
-    if meets_target(hash_words, PARAMS.target_zeros) {
+    if meets_pattern(hash_words) {
+        atomicMax(&RESULTS.lowest_inv, ~idx);
         let pos = atomicAdd(&RESULTS.found_count, 1u);
         if pos < MAX_RESULTS {
             store_result(pos, nonce, hash_words);
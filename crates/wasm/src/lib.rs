@@ -7,9 +7,16 @@ use std::cell::RefCell;
 
 use zeldhash_miner_core::{
     build_mining_template as core_build_mining_template, build_psbt_from_plan, double_sha256,
-    encode_cbor_uint, encode_nonce, mine_batch_with_cbor, nonce_len_for_range,
-    parse_address_for_network, split_nonce_segments, split_nonce_segments_cbor, txid_to_hex,
-    Network, OutputRequest, TransactionPlan, TxInput,
+    encode_cbor_uint, encode_nonce, mine_batch_with_pattern, nonce_len_for_range,
+    parse_address_for_network, parse_derivation_path, split_nonce_segments,
+    split_nonce_segments_cbor, txid_to_hex, Bip32Derivation, CompiledPattern, Fee, FeeRate,
+    KeySource, LockTime, MiningOdds, Network, OutputKind, OutputRequest, PlanOptions,
+    PsbtInputFields, PsbtOutputFields, PsbtVersion, TapKeyDerivation, TransactionPlan, TxInput,
+    TxidPattern, WitnessHint,
+};
+use zeldhash_miner_core::{
+    count_leading_zeros, psbt_from_base64, verify_signed_tx as core_verify_signed_tx, MinedTx,
+    MiningTemplate, TxMismatch,
 };
 
 #[cfg(test)]
@@ -27,12 +34,7 @@ thread_local! {
 }
 
 fn network_from_str(network: &str) -> Option<Network> {
-    match network.to_ascii_lowercase().as_str() {
-        "mainnet" => Some(Network::Mainnet),
-        "testnet" | "signet" => Some(Network::Testnet),
-        "regtest" => Some(Network::Regtest),
-        _ => None,
-    }
+    network.to_ascii_lowercase().parse().ok()
 }
 
 fn default_sequence() -> u32 {
@@ -52,13 +54,183 @@ struct JsInput {
     amount: u64,
     #[serde(default = "default_sequence")]
     sequence: u32,
+    #[serde(default)]
+    witness: Option<JsWitnessHint>,
+    #[serde(default, rename = "redeemScript")]
+    redeem_script: Option<String>,
+    /// Hex of the whole previous transaction (`PSBT_IN_NON_WITNESS_UTXO`).
+    #[serde(default, rename = "nonWitnessUtxo")]
+    non_witness_utxo: Option<String>,
+    #[serde(default, rename = "sighashType")]
+    sighash_type: Option<u32>,
+    #[serde(default, rename = "bip32Derivations")]
+    bip32_derivations: Vec<JsBip32Derivation>,
+    #[serde(default, rename = "tapInternalKey")]
+    tap_internal_key: Option<String>,
+    #[serde(default, rename = "tapKeyDerivations")]
+    tap_key_derivations: Vec<JsTapKeyDerivation>,
+}
+
+/// `{ pubkey, fingerprint, path }` with hex key and fingerprint and a path like
+/// `"m/84'/0'/0'/1/0"`.
+#[derive(Debug, Deserialize, Serialize)]
+struct JsBip32Derivation {
+    pubkey: String,
+    fingerprint: String,
+    path: String,
+}
+
+/// `{ xOnlyPubkey, leafHashes?, fingerprint, path }`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsTapKeyDerivation {
+    x_only_pubkey: String,
+    #[serde(default)]
+    leaf_hashes: Vec<String>,
+    fingerprint: String,
+    path: String,
+}
+
+/// Witness size hint, e.g. `{ size: 250 }` or `{ multisig: { required: 2, keys: 3 } }`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+enum JsWitnessHint {
+    Size(usize),
+    Multisig {
+        required: u8,
+        keys: u8,
+    },
+    TapScript {
+        signatures: u8,
+        script_len: usize,
+        depth: u8,
+    },
+}
+
+impl From<JsWitnessHint> for WitnessHint {
+    fn from(value: JsWitnessHint) -> Self {
+        match value {
+            JsWitnessHint::Size(size) => WitnessHint::Size(size),
+            JsWitnessHint::Multisig { required, keys } => WitnessHint::Multisig { required, keys },
+            JsWitnessHint::TapScript {
+                signatures,
+                script_len,
+                depth,
+            } => WitnessHint::TapScript {
+                signatures,
+                script_len,
+                depth,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 struct JsOutput {
+    /// Ignored for anchor and script outputs.
+    #[serde(default)]
     address: String,
     amount: Option<u64>,
     change: bool,
+    #[serde(default)]
+    kind: JsOutputKind,
+    #[serde(default, rename = "bip32Derivations")]
+    bip32_derivations: Vec<JsBip32Derivation>,
+    #[serde(default, rename = "tapInternalKey")]
+    tap_internal_key: Option<String>,
+    #[serde(default, rename = "tapKeyDerivations")]
+    tap_key_derivations: Vec<JsTapKeyDerivation>,
+}
+
+/// `"address"` (default), `"anchor"` for a pay-to-anchor output, or
+/// `{ script: "<hex>" }` for a raw scriptPubKey.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum JsOutputKind {
+    #[default]
+    Address,
+    Anchor,
+    Script(String),
+}
+
+/// Optional transaction framing and PSBT format, e.g.
+/// `{ version: 3, lockTime: { antiFeeSniping: 850000 }, psbtVersion: 2 }`.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsTxOptions {
+    /// 1, 2 (default) or 3 for TRUC.
+    version: Option<u32>,
+    lock_time: JsLockTime,
+    /// 0 (default) for BIP174 or 2 for BIP370.
+    psbt_version: Option<u32>,
+}
+
+/// `"none"` (default), `{ height }`, `{ time }` or `{ antiFeeSniping: tipHeight }`.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum JsLockTime {
+    #[default]
+    None,
+    Height(u32),
+    Time(u32),
+    AntiFeeSniping(u32),
+}
+
+impl From<JsLockTime> for LockTime {
+    fn from(value: JsLockTime) -> Self {
+        match value {
+            JsLockTime::None => LockTime::None,
+            JsLockTime::Height(height) => LockTime::Height(height),
+            JsLockTime::Time(time) => LockTime::Time(time),
+            JsLockTime::AntiFeeSniping(tip_height) => LockTime::AntiFeeSniping { tip_height },
+        }
+    }
+}
+
+/// Txid pattern object, e.g. `{ prefix: "cafe" }` or `{ anyOf: [...] }`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum JsPattern {
+    LeadingZeros(u8),
+    LeadingZeroBits(u16),
+    Prefix(String),
+    Suffix(String),
+    Masked(String),
+    AnyOf(Vec<JsPattern>),
+}
+
+impl From<JsPattern> for TxidPattern {
+    fn from(value: JsPattern) -> Self {
+        match value {
+            JsPattern::LeadingZeros(zeros) => TxidPattern::LeadingZeros(zeros),
+            JsPattern::LeadingZeroBits(bits) => TxidPattern::LeadingZeroBits(bits),
+            JsPattern::Prefix(hex) => TxidPattern::Prefix(hex),
+            JsPattern::Suffix(hex) => TxidPattern::Suffix(hex),
+            JsPattern::Masked(hex) => TxidPattern::Masked(hex),
+            JsPattern::AnyOf(patterns) => {
+                TxidPattern::AnyOf(patterns.into_iter().map(TxidPattern::from).collect())
+            }
+        }
+    }
+}
+
+/// Accept either a leading-zero count (number, in hex digits) or a pattern object;
+/// `{ leadingZeroBits: n }` gives bit-granular difficulty.
+fn parse_target(js: JsValue) -> Result<CompiledPattern, JsValue> {
+    if let Some(num) = js.as_f64() {
+        if !num.is_finite() || num.fract() != 0.0 || !(0.0..=255.0).contains(&num) {
+            return Err(js_error(
+                "target zeros must be an integer between 0 and 255",
+            ));
+        }
+        return Ok(CompiledPattern::leading_zeros(num as u8));
+    }
+
+    let pattern: JsPattern = serde_wasm_bindgen::from_value(js)
+        .map_err(|e| js_error(format!("invalid txid pattern: {e}")))?;
+    TxidPattern::from(pattern)
+        .compile()
+        .map_err(|e| js_error(format!("invalid txid pattern: {e}")))
 }
 
 fn decode_txid_hex(txid: &str) -> Result<[u8; 32], String> {
@@ -76,6 +248,58 @@ fn decode_hex_bytes(data: &str) -> Result<Vec<u8>, String> {
     hex::decode(data).map_err(|_| "invalid hex string".to_string())
 }
 
+fn decode_hex_array<const N: usize>(data: &str, field: &str) -> Result<[u8; N], String> {
+    hex::decode(data)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("{field} must be {N} bytes of hex"))
+}
+
+fn parse_key_source(fingerprint: &str, path: &str) -> Result<KeySource, String> {
+    Ok(KeySource {
+        fingerprint: decode_hex_array(fingerprint, "fingerprint")?,
+        path: parse_derivation_path(path).map_err(|err| err.to_string())?,
+    })
+}
+
+fn parse_bip32_derivations(
+    derivations: Vec<JsBip32Derivation>,
+) -> Result<Vec<Bip32Derivation>, String> {
+    derivations
+        .into_iter()
+        .map(|derivation| {
+            Ok(Bip32Derivation {
+                pubkey: decode_hex_bytes(&derivation.pubkey)?,
+                source: parse_key_source(&derivation.fingerprint, &derivation.path)?,
+            })
+        })
+        .collect()
+}
+
+fn parse_tap_key_derivations(
+    derivations: Vec<JsTapKeyDerivation>,
+) -> Result<Vec<TapKeyDerivation>, String> {
+    derivations
+        .into_iter()
+        .map(|derivation| {
+            Ok(TapKeyDerivation {
+                x_only_pubkey: decode_hex_array(&derivation.x_only_pubkey, "xOnlyPubkey")?,
+                leaf_hashes: derivation
+                    .leaf_hashes
+                    .iter()
+                    .map(|hash| decode_hex_array(hash, "leaf hash"))
+                    .collect::<Result<_, _>>()?,
+                source: parse_key_source(&derivation.fingerprint, &derivation.path)?,
+            })
+        })
+        .collect()
+}
+
+fn parse_tap_internal_key(key: Option<&str>) -> Result<Option<[u8; 32]>, String> {
+    key.map(|key| decode_hex_array(key, "tapInternalKey"))
+        .transpose()
+}
+
 const JS_MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
 
 #[derive(Debug, Deserialize, Serialize, Clone, Copy)]
@@ -90,6 +314,35 @@ fn parse_nonce_range(range: JsValue) -> Result<NonceRange, JsValue> {
     serde_wasm_bindgen::from_value(range).map_err(|e| js_error(format!("invalid nonce range: {e}")))
 }
 
+/// Fee rate in sat/vB: a BigInt of whole sat/vB, or a number with up to three
+/// decimals (e.g. `1.5`) that is charged per kvB and rounded up like Bitcoin Core.
+fn parse_fee_rate(js: &JsValue) -> Result<FeeRate, JsValue> {
+    if let Some(bi) = js.dyn_ref::<js_sys::BigInt>() {
+        let whole = bi
+            .to_string(10)
+            .ok()
+            .and_then(|s| s.as_string())
+            .and_then(|s| s.parse::<u64>().ok())
+            .ok_or_else(|| js_error("fee rate BigInt must fit in u64"))?;
+        return FeeRate::from_sat_per_vb(whole).ok_or_else(|| js_error("fee rate is too large"));
+    }
+    match js.as_f64() {
+        // Shortest round-trip formatting keeps `1.1` as "1.1" rather than its binary expansion.
+        Some(num) => num
+            .to_string()
+            .parse::<FeeRate>()
+            .map_err(|err| js_error(err.to_string())),
+        None => Err(js_error("fee rate must be a number or BigInt of sat/vB")),
+    }
+}
+
+fn parse_tx_options(js: JsValue) -> Result<JsTxOptions, JsValue> {
+    if js.is_null() || js.is_undefined() {
+        return Ok(JsTxOptions::default());
+    }
+    serde_wasm_bindgen::from_value(js).map_err(|e| js_error(format!("invalid tx options: {e}")))
+}
+
 fn parse_distribution(js: JsValue) -> Result<Option<Vec<u64>>, JsValue> {
     if js.is_null() || js.is_undefined() {
         return Ok(None);
@@ -142,22 +395,67 @@ fn parse_inputs(js_inputs: Vec<JsInput>) -> Result<Vec<TxInput>, String> {
     for input in js_inputs {
         let txid = decode_txid_hex(&input.txid)?;
         let script_pubkey = decode_hex_bytes(&input.script_pubkey)?;
+        let redeem_script = input
+            .redeem_script
+            .as_deref()
+            .map(decode_hex_bytes)
+            .transpose()?;
         inputs.push(TxInput {
             txid,
             vout: input.vout,
             script_pubkey,
             amount: input.amount,
             sequence: input.sequence,
+            witness_hint: input.witness.map(WitnessHint::from),
+            redeem_script,
+            psbt: PsbtInputFields {
+                non_witness_utxo: input
+                    .non_witness_utxo
+                    .as_deref()
+                    .map(decode_hex_bytes)
+                    .transpose()?,
+                sighash_type: input.sighash_type,
+                bip32_derivations: parse_bip32_derivations(input.bip32_derivations)?,
+                tap_internal_key: parse_tap_internal_key(input.tap_internal_key.as_deref())?,
+                tap_key_derivations: parse_tap_key_derivations(input.tap_key_derivations)?,
+                tap_merkle_root: None,
+                unknown: Vec::new(),
+            },
         });
     }
     Ok(inputs)
 }
 
+fn parse_outputs(js_outputs: Vec<JsOutput>) -> Result<Vec<OutputRequest>, String> {
+    let mut outputs = Vec::with_capacity(js_outputs.len());
+    for output in js_outputs {
+        let kind = match output.kind {
+            JsOutputKind::Address => OutputKind::Address,
+            JsOutputKind::Anchor => OutputKind::Anchor,
+            JsOutputKind::Script(script) => OutputKind::Script(decode_hex_bytes(&script)?),
+        };
+        outputs.push(OutputRequest {
+            address: output.address,
+            amount: output.amount,
+            change: output.change,
+            kind,
+            psbt: PsbtOutputFields {
+                bip32_derivations: parse_bip32_derivations(output.bip32_derivations)?,
+                tap_internal_key: parse_tap_internal_key(output.tap_internal_key.as_deref())?,
+                tap_key_derivations: parse_tap_key_derivations(output.tap_key_derivations)?,
+                unknown: Vec::new(),
+            },
+        });
+    }
+    Ok(outputs)
+}
+
 fn plan_transaction(
     inputs: JsValue,
     outputs: JsValue,
     network: &str,
-    sats_per_vbyte: u64,
+    fee_rate: FeeRate,
+    tx_options: JsTxOptions,
     op_return_payload: &[u8],
     distribution: Option<&[u64]>,
 ) -> Result<TransactionPlan, String> {
@@ -169,20 +467,24 @@ fn plan_transaction(
     let network = network_from_str(network).ok_or_else(|| "unsupported network".to_string())?;
 
     let inputs = parse_inputs(js_inputs)?;
-    let output_requests: Vec<OutputRequest> = js_outputs
-        .into_iter()
-        .map(|o| OutputRequest {
-            address: o.address,
-            amount: o.amount,
-            change: o.change,
-        })
-        .collect();
+    let output_requests = parse_outputs(js_outputs)?;
 
-    zeldhash_miner_core::plan_transaction(
+    let mut options = PlanOptions::new(Fee::Rate(fee_rate));
+    if let Some(version) = tx_options.version {
+        options.version = version;
+    }
+    options.lock_time = tx_options.lock_time.into();
+    options.psbt_version = match tx_options.psbt_version {
+        None | Some(0) => PsbtVersion::V0,
+        Some(2) => PsbtVersion::V2,
+        Some(other) => return Err(format!("unsupported psbtVersion {other}")),
+    };
+
+    zeldhash_miner_core::plan_transaction_with_options(
         inputs,
         output_requests,
         network,
-        sats_per_vbyte,
+        &options,
         op_return_payload,
         distribution,
     )
@@ -193,7 +495,8 @@ fn build_psbt_inner(
     inputs: JsValue,
     outputs: JsValue,
     network: &str,
-    sats_per_vbyte: u64,
+    fee_rate: FeeRate,
+    tx_options: JsTxOptions,
     nonce: u64,
     distribution: Option<Vec<u64>>,
 ) -> Result<String, String> {
@@ -207,7 +510,8 @@ fn build_psbt_inner(
         inputs,
         outputs,
         network,
-        sats_per_vbyte,
+        fee_rate,
+        tx_options,
         &nonce_bytes,
         distribution.as_deref(),
     )?;
@@ -253,15 +557,20 @@ pub fn mine_batch_wasm(
     tx_suffix: &[u8],
     start_nonce: u64,
     batch_size: u32,
-    target_zeros: u8,
+    target: JsValue,
     use_cbor_nonce: bool,
 ) -> JsValue {
-    match mine_batch_with_cbor(
+    let pattern = match parse_target(target) {
+        Ok(p) => p,
+        Err(err) => return make_validation_object(false, err.as_string()),
+    };
+
+    match mine_batch_with_pattern(
         tx_prefix,
         tx_suffix,
         start_nonce,
         batch_size,
-        target_zeros,
+        &pattern,
         use_cbor_nonce,
     ) {
         Ok(Some(res)) => make_result_object(res.nonce, &res.txid),
@@ -274,7 +583,8 @@ fn build_template_for_nonce_len(
     inputs: JsValue,
     outputs: JsValue,
     network: &str,
-    sats_per_vbyte: u64,
+    fee_rate: FeeRate,
+    tx_options: JsTxOptions,
     nonce_len: usize,
     distribution: Option<&[u64]>,
 ) -> Result<(Vec<u8>, Vec<u8>), JsValue> {
@@ -283,7 +593,8 @@ fn build_template_for_nonce_len(
         inputs,
         outputs,
         network,
-        sats_per_vbyte,
+        fee_rate,
+        tx_options,
         &placeholder,
         distribution,
     )
@@ -294,26 +605,43 @@ fn build_template_for_nonce_len(
 }
 
 /// Mine across a nonce range, automatically splitting at byte-length boundaries.
+#[allow(clippy::too_many_arguments)]
 #[wasm_bindgen]
 pub fn mine_range_wasm(
     inputs: JsValue,
     outputs: JsValue,
     network: &str,
-    sats_per_vbyte: u64,
+    fee_rate: JsValue,
     range: JsValue,
-    target_zeros: u8,
+    target: JsValue,
     distribution: JsValue,
+    tx_options: JsValue,
 ) -> JsValue {
+    let fee_rate = match parse_fee_rate(&fee_rate) {
+        Ok(rate) => rate,
+        Err(err) => return make_validation_object(false, err.as_string()),
+    };
+
     let range = match parse_nonce_range(range) {
         Ok(r) => r,
         Err(err) => return err,
     };
 
+    let pattern = match parse_target(target) {
+        Ok(p) => p,
+        Err(err) => return make_validation_object(false, err.as_string()),
+    };
+
     let distribution = match parse_distribution(distribution) {
         Ok(d) => d,
         Err(err) => return err,
     };
 
+    let tx_options = match parse_tx_options(tx_options) {
+        Ok(options) => options,
+        Err(err) => return make_validation_object(false, err.as_string()),
+    };
+
     let use_cbor_nonce = distribution.is_some();
     let segments = if use_cbor_nonce {
         match split_nonce_segments_cbor(range.start, range.size) {
@@ -332,7 +660,8 @@ pub fn mine_range_wasm(
             inputs.clone(),
             outputs.clone(),
             network,
-            sats_per_vbyte,
+            fee_rate,
+            tx_options,
             segment.nonce_len as usize,
             distribution.as_deref(),
         ) {
@@ -340,12 +669,12 @@ pub fn mine_range_wasm(
             Err(err) => return err,
         };
 
-        match mine_batch_with_cbor(
+        match mine_batch_with_pattern(
             &prefix,
             &suffix,
             segment.start,
             segment.size,
-            target_zeros,
+            &pattern,
             use_cbor_nonce,
         ) {
             Ok(Some(res)) => return make_result_object(res.nonce, &res.txid),
@@ -372,18 +701,19 @@ pub fn validate_address(addr: &str, network: &str) -> JsValue {
                 &obj,
                 &"addressType".into(),
                 &JsValue::from_str(match parsed.address_type {
+                    zeldhash_miner_core::AddressType::P2PKH => "p2pkh",
+                    zeldhash_miner_core::AddressType::P2SH => "p2sh",
                     zeldhash_miner_core::AddressType::P2WPKH => "p2wpkh",
+                    zeldhash_miner_core::AddressType::P2WSH => "p2wsh",
                     zeldhash_miner_core::AddressType::P2TR => "p2tr",
+                    zeldhash_miner_core::AddressType::P2A => "p2a",
+                    zeldhash_miner_core::AddressType::WitnessUnknown(_) => "witness_unknown",
                 }),
             );
             let _ = Reflect::set(
                 &obj,
                 &"network".into(),
-                &JsValue::from_str(match parsed.network {
-                    Network::Mainnet => "mainnet",
-                    Network::Testnet => "testnet",
-                    Network::Regtest => "regtest",
-                }),
+                &JsValue::from_str(parsed.network.name()),
             );
             obj.into()
         }
@@ -396,34 +726,42 @@ pub fn build_psbt(
     inputs: JsValue,
     outputs: JsValue,
     network: &str,
-    sats_per_vbyte: u64,
+    fee_rate: JsValue,
     nonce: u64,
     distribution: JsValue,
+    tx_options: JsValue,
 ) -> Result<String, JsValue> {
+    let fee_rate = parse_fee_rate(&fee_rate)?;
     let distribution = parse_distribution(distribution)?;
+    let tx_options = parse_tx_options(tx_options)?;
 
     build_psbt_inner(
         inputs,
         outputs,
         network,
-        sats_per_vbyte,
+        fee_rate,
+        tx_options,
         nonce,
         distribution,
     )
     .map_err(js_error)
 }
 
+#[allow(clippy::too_many_arguments)]
 #[wasm_bindgen]
 pub fn build_mining_template(
     inputs: JsValue,
     outputs: JsValue,
     network: &str,
-    sats_per_vbyte: u64,
+    fee_rate: JsValue,
     start_nonce: u64,
     batch_size: u32,
     distribution: JsValue,
+    tx_options: JsValue,
 ) -> Result<JsValue, JsValue> {
+    let fee_rate = parse_fee_rate(&fee_rate)?;
     let distribution = parse_distribution(distribution)?;
+    let tx_options = parse_tx_options(tx_options)?;
     let use_cbor_nonce = distribution.is_some();
 
     let nonce_len = if use_cbor_nonce {
@@ -443,7 +781,8 @@ pub fn build_mining_template(
         inputs,
         outputs,
         network,
-        sats_per_vbyte,
+        fee_rate,
+        tx_options,
         &placeholder,
         distribution.as_deref(),
     )
@@ -478,6 +817,202 @@ pub fn compute_txid(tx_bytes: &[u8]) -> String {
     txid_to_hex(&hash)
 }
 
+/// Outcome of `verify_signed_tx`, returned to JS.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsVerification {
+    txid: String,
+    txid_matches: bool,
+    leading_zeros: u8,
+    leading_zero_bits: u16,
+    payload_intact: bool,
+    mismatches: Vec<JsTxMismatch>,
+    weight: usize,
+    vsize: usize,
+    fee: Option<u64>,
+    sat_per_kvb: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+enum JsTxMismatch {
+    Version {
+        expected: u32,
+        actual: u32,
+    },
+    LockTime {
+        expected: u32,
+        actual: u32,
+    },
+    InputCount {
+        expected: usize,
+        actual: usize,
+    },
+    Outpoint {
+        index: usize,
+    },
+    Sequence {
+        index: usize,
+        expected: u32,
+        actual: u32,
+    },
+    ScriptSig {
+        index: usize,
+    },
+    InputAmount {
+        index: usize,
+        expected: u64,
+        actual: u64,
+    },
+    OutputCount {
+        expected: usize,
+        actual: usize,
+    },
+    Output {
+        index: usize,
+    },
+}
+
+impl From<TxMismatch> for JsTxMismatch {
+    fn from(value: TxMismatch) -> Self {
+        match value {
+            TxMismatch::Version { expected, actual } => JsTxMismatch::Version { expected, actual },
+            TxMismatch::LockTime { expected, actual } => {
+                JsTxMismatch::LockTime { expected, actual }
+            }
+            TxMismatch::InputCount { expected, actual } => {
+                JsTxMismatch::InputCount { expected, actual }
+            }
+            TxMismatch::Outpoint { index } => JsTxMismatch::Outpoint { index },
+            TxMismatch::Sequence {
+                index,
+                expected,
+                actual,
+            } => JsTxMismatch::Sequence {
+                index,
+                expected,
+                actual,
+            },
+            TxMismatch::ScriptSig { index } => JsTxMismatch::ScriptSig { index },
+            TxMismatch::InputAmount {
+                index,
+                expected,
+                actual,
+            } => JsTxMismatch::InputAmount {
+                index,
+                expected,
+                actual,
+            },
+            TxMismatch::OutputCount { expected, actual } => {
+                JsTxMismatch::OutputCount { expected, actual }
+            }
+            TxMismatch::Output { index } => JsTxMismatch::Output { index },
+        }
+    }
+}
+
+/// `signed` is a finalized PSBT in base64 or hex, or a raw transaction in hex.
+fn verify_signed_inner(mined: &MinedTx, signed: &str) -> Result<JsVerification, String> {
+    let signed = match hex::decode(signed.trim()) {
+        Ok(bytes) => bytes,
+        Err(_) => psbt_from_base64(signed).map_err(|e| e.to_string())?,
+    };
+    let verification = core_verify_signed_tx(mined, &signed).map_err(|e| e.to_string())?;
+    Ok(JsVerification {
+        txid: txid_to_hex(&verification.txid),
+        txid_matches: verification.txid_matches,
+        leading_zeros: count_leading_zeros(&verification.txid),
+        leading_zero_bits: verification.leading_zero_bits,
+        payload_intact: verification.payload_intact,
+        mismatches: verification
+            .mismatches
+            .into_iter()
+            .map(JsTxMismatch::from)
+            .collect(),
+        weight: verification.weight,
+        vsize: verification.vsize,
+        fee: verification.fee,
+        sat_per_kvb: verification.fee_rate.map(FeeRate::to_sat_per_kvb),
+    })
+}
+
+fn verification_to_js(verification: JsVerification) -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(&verification).map_err(|e| js_error(e.to_string()))
+}
+
+/// Check a signed transaction against the base64 PSBT `build_psbt` returned for the
+/// mined nonce: txid, OP_RETURN payload, and the vsize and fee with real witnesses.
+#[wasm_bindgen]
+pub fn verify_signed_tx(mined_psbt: &str, signed: &str) -> Result<JsValue, JsValue> {
+    let mined_psbt = psbt_from_base64(mined_psbt).map_err(|e| js_error(e.to_string()))?;
+    let mined = MinedTx::from_psbt(&mined_psbt).map_err(|e| js_error(e.to_string()))?;
+    verification_to_js(verify_signed_inner(&mined, signed).map_err(js_error)?)
+}
+
+/// Like `verify_signed_tx`, against a `build_mining_template` template and the
+/// winning nonce. The fee is only reported when `signed` is a PSBT.
+#[wasm_bindgen]
+pub fn verify_signed_tx_with_template(
+    prefix: &[u8],
+    suffix: &[u8],
+    nonce: u64,
+    use_cbor_nonce: bool,
+    signed: &str,
+) -> Result<JsValue, JsValue> {
+    let mined = mined_from_template(prefix, suffix, nonce, use_cbor_nonce).map_err(js_error)?;
+    verification_to_js(verify_signed_inner(&mined, signed).map_err(js_error)?)
+}
+
+fn mined_from_template(
+    prefix: &[u8],
+    suffix: &[u8],
+    nonce: u64,
+    use_cbor_nonce: bool,
+) -> Result<MinedTx, String> {
+    let nonce_len = if use_cbor_nonce {
+        encode_cbor_uint(nonce).len()
+    } else {
+        encode_nonce(nonce).len()
+    };
+    let template = MiningTemplate {
+        prefix: prefix.to_vec(),
+        suffix: suffix.to_vec(),
+        nonce_len: nonce_len as u8,
+    };
+    MinedTx::from_template(&template, nonce, use_cbor_nonce).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+/// Luck figures for a target (same forms as `mine_*`) after `attempts` hashes.
+pub fn mining_odds(target: JsValue, attempts: u64, hash_rate: f64) -> Result<JsValue, JsValue> {
+    let pattern = parse_target(target)?;
+    let odds = MiningOdds::new(pattern.hit_probability(), attempts as u128, hash_rate);
+
+    let obj = Object::new();
+    let _ = Reflect::set(
+        &obj,
+        &"hitProbability".into(),
+        &JsValue::from_f64(odds.hit_probability),
+    );
+    let _ = Reflect::set(
+        &obj,
+        &"expectedRemainingAttempts".into(),
+        &JsValue::from_f64(odds.expected_remaining_attempts),
+    );
+    let eta_ms = odds.eta_ms.map_or(JsValue::NULL, JsValue::from_f64);
+    let _ = Reflect::set(&obj, &"etaMs".into(), &eta_ms);
+    let _ = Reflect::set(
+        &obj,
+        &"probabilityFound".into(),
+        &JsValue::from_f64(odds.probability_found),
+    );
+    Ok(obj.into())
+}
+
 // ---------------- GPU bindings (feature-gated) ----------------
 #[cfg(feature = "gpu")]
 #[wasm_bindgen]
@@ -512,9 +1047,14 @@ pub async fn mine_batch_gpu(
     tx_suffix: &[u8],
     start_nonce: u64,
     batch_size: u32,
-    target_zeros: u8,
+    target: JsValue,
     use_cbor_nonce: bool,
 ) -> JsValue {
+    let pattern = match parse_target(target) {
+        Ok(p) => p,
+        Err(err) => return make_validation_object(false, err.as_string()),
+    };
+
     let length_check = if use_cbor_nonce {
         split_nonce_segments_cbor(start_nonce, batch_size)
     } else {
@@ -535,12 +1075,12 @@ pub async fn mine_batch_gpu(
     }
 
     let cpu_fallback = || {
-        mine_batch_with_cbor(
+        mine_batch_with_pattern(
             tx_prefix,
             tx_suffix,
             start_nonce,
             batch_size,
-            target_zeros,
+            &pattern,
             use_cbor_nonce,
         )
         .map(|maybe| match maybe {
@@ -560,7 +1100,7 @@ pub async fn mine_batch_gpu(
         tx_suffix,
         start_nonce,
         batch_size,
-        target_zeros,
+        pattern: &pattern,
         use_cbor_nonce,
     };
 
@@ -585,16 +1125,16 @@ async fn mine_segment_gpu_or_cpu(
     suffix: Vec<u8>,
     start_nonce: u64,
     batch_size: u32,
-    target_zeros: u8,
+    pattern: &CompiledPattern,
     use_cbor_nonce: bool,
 ) -> JsValue {
     let cpu_path = || {
-        mine_batch_with_cbor(
+        mine_batch_with_pattern(
             &prefix,
             &suffix,
             start_nonce,
             batch_size,
-            target_zeros,
+            pattern,
             use_cbor_nonce,
         )
         .map(|maybe| match maybe {
@@ -614,7 +1154,7 @@ async fn mine_segment_gpu_or_cpu(
         tx_suffix: &suffix,
         start_nonce,
         batch_size,
-        target_zeros,
+        pattern,
         use_cbor_nonce,
     };
 
@@ -635,14 +1175,16 @@ fn select_best_result(results: &[GpuMineResult]) -> Option<GpuMineResult> {
     results.iter().min_by_key(|r| r.nonce).cloned()
 }
 
+#[allow(clippy::too_many_arguments)]
 #[cfg(feature = "gpu")]
 async fn mine_range_segments_gpu(
     inputs: JsValue,
     outputs: JsValue,
     network: &str,
-    sats_per_vbyte: u64,
+    fee_rate: FeeRate,
+    tx_options: JsTxOptions,
     range: &NonceRange,
-    target_zeros: u8,
+    pattern: &CompiledPattern,
     distribution: Option<&[u64]>,
 ) -> JsValue {
     let use_cbor_nonce = distribution.is_some();
@@ -665,7 +1207,8 @@ async fn mine_range_segments_gpu(
             inputs.clone(),
             outputs.clone(),
             network,
-            sats_per_vbyte,
+            fee_rate,
+            tx_options,
             segment.nonce_len as usize,
             distribution,
         ) {
@@ -679,7 +1222,7 @@ async fn mine_range_segments_gpu(
             suffix,
             segment.start,
             segment.size,
-            target_zeros,
+            pattern,
             use_cbor_nonce,
         )
         .await;
@@ -692,34 +1235,52 @@ async fn mine_range_segments_gpu(
     JsValue::NULL
 }
 
+#[allow(clippy::too_many_arguments)]
 #[cfg(feature = "gpu")]
 #[wasm_bindgen]
 pub async fn mine_range_gpu(
     inputs: JsValue,
     outputs: JsValue,
     network: &str,
-    sats_per_vbyte: u64,
+    fee_rate: JsValue,
     range: JsValue,
-    target_zeros: u8,
+    target: JsValue,
     distribution: JsValue,
+    tx_options: JsValue,
 ) -> JsValue {
+    let fee_rate = match parse_fee_rate(&fee_rate) {
+        Ok(rate) => rate,
+        Err(err) => return make_validation_object(false, err.as_string()),
+    };
+
     let range = match parse_nonce_range(range) {
         Ok(r) => r,
         Err(err) => return err,
     };
 
+    let pattern = match parse_target(target) {
+        Ok(p) => p,
+        Err(err) => return make_validation_object(false, err.as_string()),
+    };
+
     let distribution = match parse_distribution(distribution) {
         Ok(d) => d,
         Err(err) => return err,
     };
 
+    let tx_options = match parse_tx_options(tx_options) {
+        Ok(options) => options,
+        Err(err) => return make_validation_object(false, err.as_string()),
+    };
+
     mine_range_segments_gpu(
         inputs,
         outputs,
         network,
-        sats_per_vbyte,
+        fee_rate,
+        tx_options,
         &range,
-        target_zeros,
+        &pattern,
         distribution.as_deref(),
     )
     .await
@@ -732,10 +1293,11 @@ pub async fn mine_range_gpu(
     _inputs: JsValue,
     _outputs: JsValue,
     _network: &str,
-    _sats_per_vbyte: u64,
+    _fee_rate: JsValue,
     _range: JsValue,
-    _target_zeros: u8,
+    _target: JsValue,
     _distribution: JsValue,
+    _tx_options: JsValue,
 ) -> JsValue {
     make_validation_object(false, Some("GPU feature not enabled".to_string()))
 }
@@ -762,13 +1324,17 @@ pub async fn calibrate_batch_size() -> Result<u32, JsValue> {
 // ---------------- Tests ----------------
 #[cfg(test)]
 mod tests {
-    use super::{double_sha256, mine_batch, nonce_len_for_range, split_nonce_segments};
+    use super::{
+        double_sha256, mine_batch, mined_from_template, nonce_len_for_range, split_nonce_segments,
+        verify_signed_inner,
+    };
     use bitcoin::hashes::Hash;
     use bitcoin::psbt::Psbt;
     use std::{sync::mpsc, thread, time::Duration};
     use zeldhash_miner_core::{
         count_leading_zeros, create_op_return_script, create_psbt, encode_nonce,
-        split_tx_for_mining, MineResult, TxInput, TxOutput,
+        serialize_tx_for_txid, split_tx_for_mining, MineResult, Network, PsbtVersion, TxInput,
+        TxOutput, TxParams,
     };
 
     fn mine_range_segments(
@@ -889,6 +1455,9 @@ mod tests {
             script_pubkey: spk.clone(),
             amount: 100_000,
             sequence: zeldhash_miner_core::tx::DEFAULT_SEQUENCE,
+            witness_hint: None,
+            redeem_script: None,
+            psbt: Default::default(),
         };
         let user_output = TxOutput {
             script_pubkey: spk.clone(),
@@ -911,9 +1480,14 @@ mod tests {
         let outputs_after = Vec::new();
 
         // Build prefix/suffix around the OP_RETURN placeholder of the known nonce length.
-        let (prefix, suffix) =
-            split_tx_for_mining(&[input.clone()], &outputs_before, &outputs_after, nonce_len)
-                .expect("split succeeds");
+        let (prefix, suffix) = split_tx_for_mining(
+            &[input.clone()],
+            &outputs_before,
+            &outputs_after,
+            nonce_len,
+            TxParams::default(),
+        )
+        .expect("split succeeds");
 
         let mined = mine_batch(&prefix, &suffix, start_nonce, batch_size, 0)
             .expect("mine ok")
@@ -935,7 +1509,16 @@ mod tests {
                 amount: 0,
             },
         ];
-        let psbt_bytes = create_psbt(&[input], &psbt_outputs).expect("psbt builds");
+        let psbt_bytes = create_psbt(
+            &[input],
+            &psbt_outputs,
+            &[],
+            &[],
+            TxParams::default(),
+            Network::Mainnet,
+            PsbtVersion::V0,
+        )
+        .expect("psbt builds");
         let psbt = Psbt::deserialize(&psbt_bytes).expect("psbt parses");
 
         assert_eq!(
@@ -949,6 +1532,48 @@ mod tests {
         assert_eq!(op_return_spk, &[0x6a, 0x01, 0x00]);
     }
 
+    #[test]
+    fn verifies_raw_transactions_against_a_template() {
+        let mut spk = vec![0x00, 0x14];
+        spk.extend_from_slice(&[0x11u8; 20]);
+        let input = TxInput {
+            txid: [0x11u8; 32],
+            vout: 0,
+            script_pubkey: spk.clone(),
+            amount: 100_000,
+            sequence: zeldhash_miner_core::tx::DEFAULT_SEQUENCE,
+            witness_hint: None,
+            redeem_script: None,
+            psbt: Default::default(),
+        };
+        let nonce = 0x1234u64;
+        let outputs = vec![
+            TxOutput {
+                script_pubkey: spk,
+                amount: 99_000,
+            },
+            TxOutput {
+                script_pubkey: create_op_return_script(&encode_nonce(nonce)),
+                amount: 0,
+            },
+        ];
+        let (prefix, suffix) =
+            split_tx_for_mining(&[input.clone()], &outputs[..1], &[], 2, TxParams::default())
+                .expect("split succeeds");
+        let mined = mined_from_template(&prefix, &suffix, nonce, false).expect("template");
+
+        let raw = serialize_tx_for_txid(&[input.clone()], &outputs, TxParams::default());
+        let verification = verify_signed_inner(&mined, &hex::encode(&raw)).expect("verifies");
+        assert!(verification.txid_matches && verification.payload_intact);
+        assert_eq!(verification.vsize, raw.len());
+        assert_eq!(verification.fee, None);
+
+        let stripped = serialize_tx_for_txid(&[input], &outputs[..1], TxParams::default());
+        let verification = verify_signed_inner(&mined, &hex::encode(stripped)).expect("verifies");
+        assert!(!verification.txid_matches && !verification.payload_intact);
+        assert_eq!(verification.mismatches.len(), 1);
+    }
+
     #[test]
     fn coordinates_multi_worker_search_and_prefers_first_match() {
         const TARGET_NONCE: u64 = 63_372;
@@ -1024,11 +1649,19 @@ mod tests {
                     address: addr.clone(),
                     amount: Some(50_000),
                     change: false,
+                    kind: JsOutputKind::Address,
+                    bip32_derivations: Vec::new(),
+                    tap_internal_key: None,
+                    tap_key_derivations: Vec::new(),
                 },
                 JsOutput {
                     address: addr,
                     amount: None,
                     change: true,
+                    kind: JsOutputKind::Address,
+                    bip32_derivations: Vec::new(),
+                    tap_internal_key: None,
+                    tap_key_derivations: Vec::new(),
                 },
             ];
 
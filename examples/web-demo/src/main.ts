@@ -118,6 +118,7 @@ app.innerHTML = `
             <select id="networkSelect">
               <option value="mainnet" selected>Mainnet</option>
               <option value="testnet">Testnet</option>
+              <option value="testnet4">Testnet4</option>
               <option value="signet">Signet</option>
             </select>
             <span class="muted">Affects address validation and fee policies.</span>
@@ -288,8 +289,14 @@ const setStatus = (message: string, tone: StatusTone = "ok"): void => {
 };
 
 const updateNetworkBadge = (): void => {
-  const label = state.network === "mainnet" ? "Mainnet" : state.network === "signet" ? "Signet" : "Testnet";
-  ui.networkBadge.textContent = label;
+  const labels: Record<Network, string> = {
+    mainnet: "Mainnet",
+    testnet: "Testnet",
+    testnet4: "Testnet4",
+    signet: "Signet",
+    regtest: "Regtest",
+  };
+  ui.networkBadge.textContent = labels[state.network];
 };
 
 const renderHashBars = (): void => {
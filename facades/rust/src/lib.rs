@@ -1,31 +1,44 @@
 use std::{
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc, Condvar, Mutex,
+        atomic::{AtomicBool, AtomicU16, AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Condvar, Mutex,
     },
-    time::Instant,
+    thread,
+    time::{Duration, Instant},
 };
 
-#[cfg(not(feature = "rayon"))]
-use std::thread;
-
 #[cfg(feature = "rayon")]
 use rayon::prelude::*;
 use thiserror::Error;
 use zeldhash_miner_core::{
     build_mining_template, build_psbt_from_plan, encode_cbor_uint, encode_nonce,
-    split_nonce_segments, split_nonce_segments_cbor, txid_to_hex, AddressError, FeeError,
-    MinerError, Network, NonceSegment, OutputRequest, TxInput,
+    parse_derivation_path, split_nonce_segments, split_nonce_segments_cbor, txid_to_hex,
+    AddressError, Bip32Derivation, FeeError, KeySource, MinerError, MiningTemplate, Network,
+    NonceSegment, OutputKind, OutputRequest, PsbtInputFields, PsbtOutputFields, PsbtPair,
+    TapKeyDerivation, TransactionPlan, TxInput, WitnessHint,
+};
+use zeldhash_miner_core::{calculate_tx_vsize, is_op_return, parse_psbt, psbt_from_base64};
+use zeldhash_miner_core::{
+    count_leading_zero_bits, count_leading_zeros, encode_cbor_uint_into, CompiledPattern, Fee,
+    FeeMode, HashStats, LaneHasher, LockTime, MiningOdds, PlanOptions, PolicyError, PsbtVersion,
+    TxidPattern, MAX_LANES,
 };
-use zeldhash_miner_core::{double_sha256, hash_meets_target};
+#[cfg(feature = "signer")]
+use zeldhash_miner_core::{parse_private_key, psbt_to_base64, sign_psbt};
+use zeldhash_miner_core::{verify_signed_tx, MinedTx, TxMismatch, VerifyError};
+
+pub use zeldhash_miner_core::{FeeRate, HashBackend, Policy};
 
 #[cfg(feature = "gpu")]
 use zeldhash_miner_gpu::{
     dispatch_mining_batch, GpuContext, MineResult as GpuMineResult, MiningBatch,
+    MAX_RESULTS as GPU_MAX_RESULTS,
 };
 
 // Accept 0 to mirror JS/WASM bindings semantics: 0 means "accept first hash".
 const MAX_TARGET_ZEROS: u8 = 32;
+const MAX_TARGET_BITS: u16 = MAX_TARGET_ZEROS as u16 * 4;
 #[cfg(feature = "gpu")]
 const GPU_MAX_BATCH_SIZE: u32 = 100_000;
 
@@ -33,7 +46,9 @@ const GPU_MAX_BATCH_SIZE: u32 = 100_000;
 #[derive(Debug, Clone, Copy)]
 pub enum NetworkOption {
     Mainnet,
+    /// Testnet3.
     Testnet,
+    Testnet4,
     Signet,
     Regtest,
 }
@@ -42,7 +57,9 @@ impl NetworkOption {
     fn to_core(self) -> Network {
         match self {
             NetworkOption::Mainnet => Network::Mainnet,
-            NetworkOption::Testnet | NetworkOption::Signet => Network::Testnet,
+            NetworkOption::Testnet => Network::Testnet,
+            NetworkOption::Testnet4 => Network::Testnet4,
+            NetworkOption::Signet => Network::Signet,
             NetworkOption::Regtest => Network::Regtest,
         }
     }
@@ -55,7 +72,39 @@ pub struct ZeldMinerOptions {
     pub batch_size: u32,
     pub use_gpu: bool,
     pub worker_threads: usize,
+    /// Whole sat/vB fee rate; ignored when `sat_per_kvb` is set.
     pub sats_per_vbyte: u64,
+    /// Fee rate in sat/kvB (1500 = 1.5 sat/vB), for sub-sat/vB precision.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sat_per_kvb: Option<u64>,
+    /// Relay policy planned transactions must satisfy.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub policy: PolicyDesc,
+}
+
+/// Relay policy preset or custom limits; mirrors the core `Policy`.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PolicyDesc {
+    /// Bitcoin Core up to 29.0: one 83-byte OP_RETURN, 1 sat/vB minimum relay fee.
+    CoreV28,
+    /// Bitcoin Core 29.1: 0.1 sat/vB minimum relay fee.
+    CoreV29_1,
+    /// Bitcoin Core 30: any number of OP_RETURN outputs within a 100 kB limit.
+    #[default]
+    CoreV30,
+    Custom {
+        dust_relay_sat_per_kvb: u64,
+        max_datacarrier_bytes: usize,
+        max_standard_weight: usize,
+        min_relay_sat_per_kvb: u64,
+        #[cfg_attr(feature = "serde", serde(default))]
+        multiple_op_returns: bool,
+    },
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -66,25 +115,296 @@ pub struct TxInputDesc {
     pub script_pubkey: String,
     pub amount: u64,
     pub sequence: Option<u32>,
+    /// Witness size hint; required unless `script_pubkey` is P2WPKH or P2TR key-path.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub witness: Option<WitnessHintDesc>,
+    /// Hex witness program wrapped by a P2SH `script_pubkey` (nested SegWit).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub redeem_script: Option<String>,
+    /// Hex of the full previous transaction, for signers that require
+    /// `PSBT_IN_NON_WITNESS_UTXO`. Must contain the spent output.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub non_witness_utxo: Option<String>,
+    /// `PSBT_IN_SIGHASH_TYPE`, e.g. `0x01` for `SIGHASH_ALL`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sighash_type: Option<u32>,
+    /// Origins of the keys that sign this input.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bip32_derivations: Vec<Bip32DerivationDesc>,
+    /// Hex x-only taproot internal key.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tap_internal_key: Option<String>,
+    /// Origins of the taproot keys that sign this input.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tap_key_derivations: Vec<TapKeyDerivationDesc>,
 }
 
+/// Origin of an ECDSA key; mirrors the core `Bip32Derivation`.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bip32DerivationDesc {
+    /// Hex compressed or uncompressed public key.
+    pub pubkey: String,
+    /// Hex master key fingerprint (4 bytes).
+    pub fingerprint: String,
+    /// Derivation path, e.g. `m/84'/0'/0'/1/5`.
+    pub path: String,
+}
+
+/// Origin of a taproot key; mirrors the core `TapKeyDerivation`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TapKeyDerivationDesc {
+    /// Hex x-only public key.
+    pub x_only_pubkey: String,
+    /// Hex hashes of the leaves the key appears in; empty for the internal key.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub leaf_hashes: Vec<String>,
+    /// Hex master key fingerprint (4 bytes).
+    pub fingerprint: String,
+    /// Derivation path, e.g. `m/86'/0'/0'/1/5`.
+    pub path: String,
+}
+
+/// How an input will be spent, for fee estimation; mirrors the core `WitnessHint`.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WitnessHintDesc {
+    /// Serialized witness size in bytes, item count and length prefixes included.
+    Size(usize),
+    /// P2WSH `OP_CHECKMULTISIG` of `required` signatures over `keys` compressed keys.
+    Multisig { required: u8, keys: u8 },
+    /// Taproot script path: `signatures` Schnorr signatures, a `script_len`-byte leaf
+    /// script and a control block for a leaf at `depth`.
+    TapScript {
+        signatures: u8,
+        script_len: usize,
+        depth: u8,
+    },
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default)]
 pub struct TxOutputDesc {
+    /// Destination address; ignored for anchor and script outputs.
     pub address: String,
     pub amount: Option<u64>,
     pub change: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: OutputKindDesc,
+    /// Origins of the keys that own this output, so signers can verify change.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bip32_derivations: Vec<Bip32DerivationDesc>,
+    /// Hex x-only taproot internal key.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tap_internal_key: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tap_key_derivations: Vec<TapKeyDerivationDesc>,
+}
+
+/// What an output pays to; mirrors the core `OutputKind`.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum OutputKindDesc {
+    /// Pay to `address`.
+    #[default]
+    Address,
+    /// Pay-to-anchor output for CPFP fee bumping. Defaults to the 240 sat dust
+    /// threshold; a lower amount is only allowed with `absolute_fee: Some(0)`.
+    Anchor,
+    /// Hex scriptPubKey of a standard output type, e.g. bare multisig or an extra
+    /// OP_RETURN data carrier.
+    Script(String),
+}
+
+/// nLockTime of the mined transaction; mirrors the core `LockTime`.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LockTimeDesc {
+    /// 0: valid in any block.
+    #[default]
+    None,
+    /// Not valid before this block height (below 500,000,000).
+    Height(u32),
+    /// Not valid before this UNIX timestamp (at or above 500,000,000).
+    Time(u32),
+    /// Lock to the current chain tip, like Bitcoin Core's anti-fee-sniping.
+    AntiFeeSniping { tip_height: u32 },
+}
+
+/// PSBT format of the mining result; mirrors the core `PsbtVersion`.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PsbtVersionDesc {
+    /// BIP174, with the unsigned transaction in the global map.
+    #[default]
+    V0,
+    /// BIP370, marked non-modifiable so signers keep the mined txid.
+    V2,
+}
+
+/// How far `mine_transaction` walks the nonce space before giving up.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Search `[start_nonce, start_nonce + batch_size)` once, then report `NoMatchingNonce`.
+    #[default]
+    Bounded,
+    /// Keep mining consecutive `batch_size` chunks until a hit, a stop request,
+    /// or the end of the u64 nonce space.
+    Unbounded,
 }
 
+/// Which outputs pay the transaction fee; mirrors the core `FeeMode`.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FeeModeDesc {
+    /// Inputs pay the fee; the change output takes what is left.
+    #[default]
+    Change,
+    /// Split the fee equally across these outputs (indices into `outputs`), like
+    /// Bitcoin Core's `subtractfeefromoutputs`.
+    SubtractFromOutputs(Vec<usize>),
+    /// Split the fee across all non-change outputs in proportion to their amounts.
+    SubtractProportionally,
+    /// This output (index into `outputs`) receives everything left after the other
+    /// outputs and the fee; no change output is allowed.
+    SendMax(usize),
+}
+
+/// Keep mining after the target is reached and report every strictly better txid.
+///
+/// Txids are ranked by leading zeros, counted in bits when `target_bits` is set and
+/// in hex digits otherwise. Each improvement is passed to `on_found`; the run ends at
+/// `max_target`, when a `MineParams` budget runs out, on a stop request or at the end
+/// of the search, and returns the best result found.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContinueMining {
+    /// Stop as soon as a txid reaches this many leading zeros.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_target: Option<u16>,
+}
+
+/// Txid shape to mine for, as an alternative to `target_zeros`.
+///
+/// Hex strings are matched against the displayed (reversed) txid, case-insensitively.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxidPatternDesc {
+    /// At least this many leading hex zeros.
+    LeadingZeros(u8),
+    /// At least this many leading zero bits.
+    LeadingZeroBits(u16),
+    /// Txid starts with these hex digits, e.g. `"cafe"`.
+    Prefix(String),
+    /// Txid ends with these hex digits.
+    Suffix(String),
+    /// Hex digits anchored at the start of the txid; `?` matches any digit.
+    Masked(String),
+    /// Any of the nested patterns (at most 8 alternatives overall).
+    AnyOf(Vec<TxidPatternDesc>),
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default)]
 pub struct MineParams {
     pub inputs: Vec<TxInputDesc>,
     pub outputs: Vec<TxOutputDesc>,
     pub target_zeros: u8,
+    /// Leading zero bits to mine for; takes precedence over `target_zeros` when set.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub target_bits: Option<u16>,
     pub start_nonce: Option<u64>,
     pub batch_size: Option<u32>,
     pub distribution: Option<Vec<u64>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub search_mode: SearchMode,
+    /// Always return the smallest qualifying nonce of the searched range, whatever
+    /// the backend and thread timing. Costs some throughput on multi-threaded CPU runs.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub deterministic: bool,
+    /// Mine for this txid pattern instead of `target_zeros`/`target_bits` when set.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pattern: Option<TxidPatternDesc>,
+    /// Keep mining for better txids after the first hit; requires a leading-zero target.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub continue_mining: Option<ContinueMining>,
+    /// Wall-clock budget in milliseconds.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub deadline_ms: Option<u64>,
+    /// Maximum number of nonces to hash.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_attempts: Option<u64>,
+    /// Lowest leading-zero count (in the target's unit) a best-effort result may have.
+    ///
+    /// When a budget runs out before the target is met, the txid with the most leading
+    /// zeros seen so far is returned if it reaches this count (any count when unset).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fallback_target: Option<u16>,
+    /// Report progress every this many milliseconds, also from inside a segment.
+    ///
+    /// When unset, progress is reported once per completed segment.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub progress_interval_ms: Option<u64>,
+    /// Pay exactly this many sats of fee instead of applying the miner's fee rate.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub absolute_fee: Option<u64>,
+    /// Which outputs pay the fee.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fee_mode: FeeModeDesc,
+    /// Transaction version: 1, 2 (default) or 3 for TRUC (BIP431).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub version: Option<u32>,
+    /// nLockTime; a locktime needs at least one input with a non-final sequence.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lock_time: LockTimeDesc,
+    /// Serialize the result as a BIP174 (default) or BIP370 PSBT.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub psbt_version: PsbtVersionDesc,
+}
+
+/// A wallet-built PSBT for [`ZeldMiner::mine_psbt`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct PsbtDesc {
+    /// Base64 BIP174 (v0) or BIP370 (v2) PSBT.
+    pub psbt: String,
+    /// Output paying for the added OP_RETURN; defaults to the only output carrying key
+    /// origins, which is how wallets mark their change.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub change_index: Option<usize>,
+    /// Witness size hints by input index, required like [`TxInputDesc::witness`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub witness_hints: Vec<Option<WitnessHintDesc>>,
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -96,6 +416,97 @@ pub struct MineResult {
     pub attempts: u128,
     pub duration_ms: u128,
     pub hash_rate: f64,
+    /// Leading zeros of the txid: bits when `target_bits` is set, hex digits otherwise.
+    pub leading_zeros: u16,
+    /// A budget ran out before the target was met; this is the best txid seen.
+    pub best_effort: bool,
+}
+
+/// A signed transaction checked against the [`MineResult`] it was signed from.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Verification {
+    /// Txid of the signed transaction.
+    pub txid: String,
+    /// The signer kept the mined txid.
+    pub txid_matches: bool,
+    /// Leading hex zeros of `txid`.
+    pub leading_zeros: u8,
+    pub leading_zero_bits: u16,
+    /// The mined OP_RETURN, nonce and ZELD payload included, is still an output.
+    pub payload_intact: bool,
+    /// What the signer changed; empty when `txid_matches`, unless a signed PSBT
+    /// disagrees on spent amounts.
+    pub mismatches: Vec<TxMismatchDesc>,
+    /// Weight and vsize with the real witnesses attached.
+    pub weight: usize,
+    pub vsize: usize,
+    /// Fee paid, when the spent amounts are known.
+    pub fee: Option<u64>,
+    pub fee_rate_sat_per_kvb: Option<u64>,
+}
+
+/// A [`MineResult`] signed by [`ZeldMiner::sign_result`].
+#[cfg(feature = "signer")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct SignedTransaction {
+    /// Finalized PSBT in base64.
+    pub psbt: String,
+    /// Raw transaction in hex, ready to broadcast.
+    pub tx_hex: String,
+    /// Always the mined txid.
+    pub txid: String,
+}
+
+/// A field of the signed transaction that differs from the mined one; mirrors the
+/// core `TxMismatch`.
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxMismatchDesc {
+    Version {
+        expected: u32,
+        actual: u32,
+    },
+    LockTime {
+        expected: u32,
+        actual: u32,
+    },
+    InputCount {
+        expected: usize,
+        actual: usize,
+    },
+    /// The input spends another outpoint, e.g. because inputs were reordered.
+    Outpoint {
+        index: usize,
+    },
+    Sequence {
+        index: usize,
+        expected: u32,
+        actual: u32,
+    },
+    /// The scriptSig differs, e.g. a nested SegWit input lost its redeem script push.
+    ScriptSig {
+        index: usize,
+    },
+    /// The signed PSBT claims another spent amount than the mined one.
+    InputAmount {
+        index: usize,
+        expected: u64,
+        actual: u64,
+    },
+    OutputCount {
+        expected: usize,
+        actual: usize,
+    },
+    /// Amount or scriptPubKey differs, e.g. because outputs were reordered.
+    Output {
+        index: usize,
+    },
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -105,6 +516,19 @@ pub struct ProgressStats {
     pub hash_rate: f64,
     pub elapsed_ms: u128,
     pub last_nonce: Option<u64>,
+    /// Txid with the most leading zeros seen so far. GPU batches only contribute hits.
+    pub best_txid: Option<String>,
+    /// Leading zeros of `best_txid`: bits when `target_bits` is set, hex digits otherwise.
+    pub best_leading_zeros: Option<u16>,
+    /// Hashes seen per count of leading hex zeros (index = zeros), up to the last
+    /// non-empty bucket.
+    pub zero_histogram: Vec<u64>,
+    /// Expected hashes until the next hit.
+    pub expected_remaining_attempts: f64,
+    /// Milliseconds to the expected hit at the current hash rate.
+    pub eta_ms: Option<f64>,
+    /// Probability that the work done so far would have produced a hit.
+    pub probability_found: f64,
 }
 
 #[cfg_attr(
@@ -149,6 +573,7 @@ impl ZeldMinerError {
 /// need concurrent searches to avoid cross-talk on pause/stop signals.
 pub struct ZeldMiner {
     opts: ZeldMinerOptions,
+    fee_rate: FeeRate,
     control: MiningControl,
     #[cfg(feature = "gpu")]
     gpu_ctx: Option<GpuContext>,
@@ -158,6 +583,10 @@ pub struct ZeldMiner {
 struct MiningControl {
     stop: AtomicBool,
     pause: AtomicBool,
+    /// Set by the deadline watchdog once the run's time budget is spent.
+    expired: AtomicBool,
+    /// Set when the run returns so the watchdog exits early.
+    finished: AtomicBool,
     mutex: Mutex<()>,
     cv: Condvar,
 }
@@ -167,6 +596,8 @@ impl MiningControl {
         Self {
             stop: AtomicBool::new(false),
             pause: AtomicBool::new(false),
+            expired: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
             mutex: Mutex::new(()),
             cv: Condvar::new(),
         }
@@ -175,9 +606,54 @@ impl MiningControl {
     fn reset(&self) {
         self.stop.store(false, Ordering::Release);
         self.pause.store(false, Ordering::Release);
+        self.expired.store(false, Ordering::Release);
+        self.finished.store(false, Ordering::Release);
+        self.cv.notify_all();
+    }
+
+    /// Block until `timeout` elapses or the run finishes; on timeout, abort the run.
+    fn expire_after(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        let mut guard = self
+            .mutex
+            .lock()
+            .expect("pause mutex should not be poisoned");
+        while !self.finished.load(Ordering::Acquire) {
+            let now = Instant::now();
+            if now >= deadline {
+                self.expired.store(true, Ordering::Release);
+                self.cv.notify_all();
+                return;
+            }
+            guard = self
+                .cv
+                .wait_timeout(guard, deadline - now)
+                .expect("deadline condvar wait failed")
+                .0;
+        }
+    }
+
+    fn finish(&self) {
+        let _guard = self
+            .mutex
+            .lock()
+            .expect("pause mutex should not be poisoned");
+        self.finished.store(true, Ordering::Release);
         self.cv.notify_all();
     }
 
+    fn interrupted(&self) -> bool {
+        self.stop.load(Ordering::Acquire) || self.expired.load(Ordering::Acquire)
+    }
+
+    fn interrupted_error(&self) -> ZeldMinerError {
+        if self.expired.load(Ordering::Acquire) {
+            mining_aborted_error("mining deadline reached")
+        } else {
+            mining_aborted_error("mining stopped")
+        }
+    }
+
     fn request_stop(&self) {
         self.stop.store(true, Ordering::Release);
         self.cv.notify_all();
@@ -193,8 +669,8 @@ impl MiningControl {
     }
 
     fn wait_if_paused_or_stopped(&self) -> Result<()> {
-        if self.stop.load(Ordering::Acquire) {
-            return Err(mining_aborted_error("mining stopped"));
+        if self.interrupted() {
+            return Err(self.interrupted_error());
         }
         if !self.pause.load(Ordering::Acquire) {
             return Ok(());
@@ -204,12 +680,12 @@ impl MiningControl {
             .mutex
             .lock()
             .expect("pause mutex should not be poisoned");
-        while self.pause.load(Ordering::Acquire) && !self.stop.load(Ordering::Acquire) {
+        while self.pause.load(Ordering::Acquire) && !self.interrupted() {
             guard = self.cv.wait(guard).expect("pause condvar wait failed");
         }
 
-        if self.stop.load(Ordering::Acquire) {
-            Err(mining_aborted_error("mining stopped"))
+        if self.interrupted() {
+            Err(self.interrupted_error())
         } else {
             Ok(())
         }
@@ -230,12 +706,29 @@ impl ZeldMiner {
                 "worker_threads must be greater than zero",
             ));
         }
-        if opts.sats_per_vbyte == 0 {
-            return Err(ZeldMinerError::new(
-                ZeldMinerErrorCode::InvalidInput,
-                "sats_per_vbyte must be greater than zero",
-            ));
-        }
+        let fee_rate = match opts.sat_per_kvb {
+            Some(0) => {
+                return Err(ZeldMinerError::new(
+                    ZeldMinerErrorCode::InvalidInput,
+                    "sat_per_kvb must be greater than zero",
+                ))
+            }
+            Some(sat_per_kvb) => FeeRate::from_sat_per_kvb(sat_per_kvb),
+            None => {
+                if opts.sats_per_vbyte == 0 {
+                    return Err(ZeldMinerError::new(
+                        ZeldMinerErrorCode::InvalidInput,
+                        "sats_per_vbyte must be greater than zero",
+                    ));
+                }
+                FeeRate::from_sat_per_vb(opts.sats_per_vbyte).ok_or_else(|| {
+                    ZeldMinerError::new(
+                        ZeldMinerErrorCode::InvalidInput,
+                        "sats_per_vbyte is too large",
+                    )
+                })?
+            }
+        };
         #[cfg(feature = "gpu")]
         let gpu_ctx = if opts.use_gpu {
             match pollster::block_on(GpuContext::init()) {
@@ -252,6 +745,7 @@ impl ZeldMiner {
 
         Ok(Self {
             opts,
+            fee_rate,
             control: MiningControl::new(),
             #[cfg(feature = "gpu")]
             gpu_ctx,
@@ -261,6 +755,62 @@ impl ZeldMiner {
     pub fn mine_transaction<F, G>(
         &self,
         params: MineParams,
+        on_progress: Option<F>,
+        on_found: Option<G>,
+    ) -> Result<MineResult>
+    where
+        F: FnMut(ProgressStats),
+        G: FnMut(&MineResult),
+    {
+        let tx = JobTx {
+            inputs: parse_inputs(&params.inputs)?,
+            outputs: parse_outputs(&params.outputs)?,
+            fee: params
+                .absolute_fee
+                .map_or(Fee::Rate(self.fee_rate), Fee::Absolute),
+            fee_mode: params.fee_mode.clone().into(),
+            version: params.version.unwrap_or(2),
+            lock_time: params.lock_time.into(),
+            psbt_version: params.psbt_version.into(),
+            global_pairs: Vec::new(),
+            op_return_fields: None,
+        };
+        self.mine(params, tx, on_progress, on_found)
+    }
+
+    /// Mine a PSBT a wallet already built, keeping its coin selection, change and every
+    /// key-value pair it carries.
+    ///
+    /// The wallet's zero-amount OP_RETURN output, if any, is filled in with the nonce;
+    /// otherwise one is appended. The change output pays for the extra vbytes at the
+    /// PSBT's own fee rate. Inputs, outputs, version, locktime and PSBT version all come
+    /// from `psbt`: `params` must leave `inputs` and `outputs` empty, and its fee,
+    /// version and locktime fields are ignored.
+    pub fn mine_psbt<F, G>(
+        &self,
+        psbt: PsbtDesc,
+        params: MineParams,
+        on_progress: Option<F>,
+        on_found: Option<G>,
+    ) -> Result<MineResult>
+    where
+        F: FnMut(ProgressStats),
+        G: FnMut(&MineResult),
+    {
+        if !params.inputs.is_empty() || !params.outputs.is_empty() {
+            return Err(ZeldMinerError::new(
+                ZeldMinerErrorCode::InvalidInput,
+                "inputs and outputs come from the PSBT; leave them empty",
+            ));
+        }
+        let tx = import_psbt(&psbt, self.opts.network.to_core())?;
+        self.mine(params, tx, on_progress, on_found)
+    }
+
+    fn mine<F, G>(
+        &self,
+        params: MineParams,
+        tx: JobTx,
         mut on_progress: Option<F>,
         mut on_found: Option<G>,
     ) -> Result<MineResult>
@@ -268,13 +818,45 @@ impl ZeldMiner {
         F: FnMut(ProgressStats),
         G: FnMut(&MineResult),
     {
-        if params.target_zeros > MAX_TARGET_ZEROS {
+        if params.continue_mining.is_some() && params.pattern.is_some() {
+            return Err(ZeldMinerError::new(
+                ZeldMinerErrorCode::InvalidInput,
+                "continue_mining requires a leading-zero target, not a pattern",
+            ));
+        }
+        if params.fallback_target.is_some() && params.pattern.is_some() {
             return Err(ZeldMinerError::new(
                 ZeldMinerErrorCode::InvalidInput,
-                "target_zeros must be between 0 and 32",
+                "fallback_target requires a leading-zero target, not a pattern",
             ));
         }
 
+        let pattern = match params.pattern.as_ref() {
+            Some(desc) => TxidPattern::from(desc.clone()).compile().map_err(|err| {
+                ZeldMinerError::new(ZeldMinerErrorCode::InvalidInput, format!("pattern: {err}"))
+            })?,
+            None => match params.target_bits {
+                Some(bits) => {
+                    if bits > MAX_TARGET_BITS {
+                        return Err(ZeldMinerError::new(
+                            ZeldMinerErrorCode::InvalidInput,
+                            "target_bits must be between 0 and 128",
+                        ));
+                    }
+                    CompiledPattern::leading_zero_bits(bits)
+                }
+                None => {
+                    if params.target_zeros > MAX_TARGET_ZEROS {
+                        return Err(ZeldMinerError::new(
+                            ZeldMinerErrorCode::InvalidInput,
+                            "target_zeros must be between 0 and 32",
+                        ));
+                    }
+                    CompiledPattern::leading_zeros(params.target_zeros)
+                }
+            },
+        };
+
         let batch_size = params.batch_size.unwrap_or(self.opts.batch_size);
         if batch_size == 0 {
             return Err(ZeldMinerError::new(
@@ -283,197 +865,427 @@ impl ZeldMiner {
             ));
         }
 
+        let (unit, target) = match params.target_bits {
+            Some(bits) => (ZeroUnit::Bits, bits),
+            None => (ZeroUnit::HexDigits, params.target_zeros as u16),
+        };
+        if let Some(ContinueMining {
+            max_target: Some(max),
+        }) = params.continue_mining
+        {
+            if max < target {
+                return Err(ZeldMinerError::new(
+                    ZeldMinerErrorCode::InvalidInput,
+                    "continue_mining.max_target must not be below the target",
+                ));
+            }
+        }
+        if params
+            .fallback_target
+            .is_some_and(|fallback| fallback > target)
+        {
+            return Err(ZeldMinerError::new(
+                ZeldMinerErrorCode::InvalidInput,
+                "fallback_target must not be above the target",
+            ));
+        }
+        if params.progress_interval_ms == Some(0) {
+            return Err(ZeldMinerError::new(
+                ZeldMinerErrorCode::InvalidInput,
+                "progress_interval_ms must be greater than zero",
+            ));
+        }
+        let deadline = params.deadline_ms.map(Duration::from_millis);
+        let budgeted = deadline.is_some() || params.max_attempts.is_some();
+
         let start_nonce = params.start_nonce.unwrap_or(0);
-        let network = self.opts.network.to_core();
-        let parsed_inputs = parse_inputs(&params.inputs)?;
-        let output_requests: Vec<OutputRequest> = params
-            .outputs
-            .iter()
-            .cloned()
-            .map(OutputRequest::from)
-            .collect();
-        let distribution = params.distribution.as_deref();
+        let chunks = NonceChunks::new(start_nonce, batch_size, params.search_mode)?;
+        let job = MiningJob {
+            tx,
+            network: self.opts.network.to_core(),
+            distribution: params.distribution.as_deref(),
+            search_mode: params.search_mode,
+            deterministic: params.deterministic,
+            unit,
+            continue_mining: params.continue_mining,
+            policy: self.opts.policy.into(),
+            max_attempts: params.max_attempts,
+            progress_interval: params.progress_interval_ms.map(Duration::from_millis),
+            started_at: Instant::now(),
+        };
 
-        let segments = if distribution.is_some() {
-            split_nonce_segments_cbor(start_nonce, batch_size)
-        } else {
-            split_nonce_segments(start_nonce, batch_size)
+        self.control.reset();
+
+        let mut run = RunState {
+            attempts: 0,
+            best: None,
+            // Near misses are only ranked for leading-zero targets.
+            candidate: (budgeted && params.pattern.is_none())
+                .then(|| BestCandidate::new(unit, params.fallback_target.unwrap_or(0))),
+            attempts_spent: false,
+            stats: HashStats::new(),
+        };
+        let outcome = thread::scope(|scope| {
+            if let Some(timeout) = deadline {
+                let control = &self.control;
+                scope.spawn(move || control.expire_after(timeout));
+            }
+            let outcome = self.mine_chunks(
+                &job,
+                chunks,
+                pattern,
+                &mut on_progress,
+                &mut on_found,
+                &mut run,
+            );
+            self.control.finish();
+            outcome
+        });
+
+        let Err(err) = outcome else {
+            return outcome;
+        };
+        if !matches!(
+            err.code,
+            ZeldMinerErrorCode::MiningAborted | ZeldMinerErrorCode::NoMatchingNonce
+        ) {
+            return Err(err);
+        }
+        // Continue mode hands back its best txid however the run ended.
+        if let Some(best) = run.best {
+            return Ok(best);
+        }
+        let budget_spent = run.attempts_spent || self.control.expired.load(Ordering::Acquire);
+        match run.candidate.and_then(|candidate| candidate.take()) {
+            Some(nonce) if budget_spent => {
+                let mut result = self.build_result(&job, nonce, run.attempts)?;
+                result.best_effort = true;
+                Ok(result)
+            }
+            _ => Err(err),
         }
-        .map_err(|e| ZeldMinerError::new(ZeldMinerErrorCode::InvalidInput, e))?;
-        let started_at = Instant::now();
-        let mut attempts: u128 = 0;
+    }
 
-        self.control.reset();
+    fn mine_chunks<F, G>(
+        &self,
+        job: &MiningJob<'_>,
+        mut chunks: NonceChunks,
+        mut pattern: CompiledPattern,
+        on_progress: &mut Option<F>,
+        on_found: &mut Option<G>,
+        run: &mut RunState,
+    ) -> Result<MineResult>
+    where
+        F: FnMut(ProgressStats),
+        G: FnMut(&MineResult),
+    {
+        let use_cbor_nonce = job.distribution.is_some();
+        // Continue mode resumes right after each hit, so it needs the lowest hit of a
+        // segment to avoid skipping nonces other workers have not scanned yet.
+        let deterministic = job.deterministic || job.continue_mining.is_some();
+
+        // Templates only depend on the nonce length, so reuse them across chunks.
+        let mut template_cache: Option<(u8, MiningTemplate)> = None;
+        let mut last_report = job.started_at;
+        let report_interval = job.progress_interval.filter(|_| on_progress.is_some());
+
+        while let Some((chunk_start, chunk_size)) = chunks.next_chunk() {
+            let segments = if use_cbor_nonce {
+                split_nonce_segments_cbor(chunk_start, chunk_size)
+            } else {
+                split_nonce_segments(chunk_start, chunk_size)
+            }
+            .map_err(|e| ZeldMinerError::new(ZeldMinerErrorCode::InvalidInput, e))?;
 
-        let workers = self.opts.worker_threads.max(1);
+            for mut segment in segments {
+                loop {
+                    self.control.wait_if_paused_or_stopped()?;
 
-        for segment in segments {
-            self.control.wait_if_paused_or_stopped()?;
+                    let template = match template_cache {
+                        Some((len, ref template)) if len == segment.nonce_len => template,
+                        _ => {
+                            let placeholder = vec![0u8; segment.nonce_len as usize];
+                            let plan = job.plan(&placeholder)?;
+                            let template = build_mining_template(&plan, segment.nonce_len as usize)
+                                .map_err(map_miner_error)?;
+                            &template_cache.insert((segment.nonce_len, template)).1
+                        }
+                    };
+
+                    // Trim the segment to what is left of the attempt budget.
+                    let mut scan = segment;
+                    if let Some(max) = job.max_attempts {
+                        let left = (max as u128).saturating_sub(run.attempts);
+                        if left == 0 {
+                            run.attempts_spent = true;
+                            return Err(ZeldMinerError::new(
+                                ZeldMinerErrorCode::NoMatchingNonce,
+                                "attempt budget exhausted before a match",
+                            ));
+                        }
+                        scan.size = scan.size.min(left.min(u32::MAX as u128) as u32);
+                    }
 
-            let placeholder = vec![0u8; segment.nonce_len as usize];
-            let plan = zeldhash_miner_core::plan_transaction(
-                parsed_inputs.clone(),
-                output_requests.clone(),
-                network,
-                self.opts.sats_per_vbyte,
-                &placeholder,
-                distribution,
-            )
-            .map_err(map_miner_error)?;
-
-            let template = build_mining_template(&plan, segment.nonce_len as usize)
-                .map_err(map_miner_error)?;
-
-            let mut attempts_this_segment: u128 = 0;
-            let mut mined: Option<(u64, [u8; 32])> = None;
-
-            if self.opts.use_gpu {
-                #[cfg(feature = "gpu")]
-                {
-                    if let Some(ctx) = self.gpu_ctx.as_ref() {
-                        if let Ok(res) = mine_segment_gpu_controlled(
-                            ctx,
-                            &template.prefix,
-                            &template.suffix,
-                            &segment,
-                            params.target_zeros,
-                            distribution.is_some(),
-                            &self.control,
-                        ) {
-                            attempts_this_segment = res.attempts;
-                            mined = res.hit;
-                            if mined.is_none() {
-                                // GPU completed successfully with no hit; do not rerun on CPU.
-                                attempts += attempts_this_segment;
-                                if let Some(cb) = on_progress.as_mut() {
-                                    let elapsed_ms = started_at.elapsed().as_millis();
-                                    cb(ProgressStats {
-                                        hashes_processed: attempts,
-                                        hash_rate: hash_rate(attempts, elapsed_ms),
-                                        elapsed_ms,
-                                        last_nonce: Some(segment.start + segment.size as u64 - 1),
-                                    });
+                    let live = SegmentProgress::new();
+                    let segment_job = SegmentJob {
+                        prefix: &template.prefix,
+                        suffix: &template.suffix,
+                        pattern: &pattern,
+                        use_cbor_nonce,
+                        control: &self.control,
+                        candidate: run.candidate.as_ref(),
+                        record_stats: on_progress.is_some(),
+                        progress: report_interval.map(|_| &live),
+                    };
+                    let mined = match (report_interval, on_progress.as_mut()) {
+                        // Mine on a scoped thread so this one can report while the
+                        // segment is still being scanned.
+                        (Some(interval), Some(cb)) => thread::scope(|scope| {
+                            let (done_tx, done_rx) = mpsc::channel::<()>();
+                            let (segment_job, scan) = (&segment_job, &scan);
+                            let worker = scope.spawn(move || {
+                                let mined = self.mine_segment(segment_job, scan, deterministic);
+                                let _ = done_tx.send(());
+                                mined
+                            });
+                            loop {
+                                let wait = (last_report + interval)
+                                    .saturating_duration_since(Instant::now());
+                                if !matches!(
+                                    done_rx.recv_timeout(wait),
+                                    Err(RecvTimeoutError::Timeout)
+                                ) {
+                                    break;
                                 }
-                                // Honor a stop/pause request before moving to the next segment.
-                                self.control.wait_if_paused_or_stopped()?;
-                                continue;
+                                let (attempts, last_nonce) = live.snapshot();
+                                cb(job.progress(
+                                    run,
+                                    &pattern,
+                                    run.attempts + attempts as u128,
+                                    last_nonce,
+                                ));
+                                last_report = Instant::now();
                             }
-                        } else {
-                            // GPU path failed; fall back to CPU for this segment.
-                        }
+                            worker.join().expect("mining worker panicked")
+                        })?,
+                        _ => self.mine_segment(&segment_job, &scan, deterministic)?,
+                    };
+                    run.attempts += mined.attempts;
+                    if let Some(stats) = mined.stats.as_ref() {
+                        run.stats.merge(stats);
                     }
-                }
-                // If GPU work concluded and a stop was requested, abort before continuing.
-                self.control.wait_if_paused_or_stopped()?;
-            }
 
-            if mined.is_none() {
-                let controlled_result = if workers > 1 {
-                    mine_segment_cpu_parallel(
-                        &template.prefix,
-                        &template.suffix,
-                        &segment,
-                        params.target_zeros,
-                        distribution.is_some(),
-                        &self.control,
-                        workers,
-                    )?
-                } else {
-                    mine_segment_cpu_controlled(
-                        &template.prefix,
-                        &template.suffix,
-                        &segment,
-                        params.target_zeros,
-                        distribution.is_some(),
-                        &self.control,
-                        None,
-                    )?
-                };
-
-                attempts_this_segment = controlled_result.attempts;
-                mined = controlled_result.hit;
-
-                if mined.is_none() {
-                    self.control.wait_if_paused_or_stopped()?;
-                    attempts += attempts_this_segment;
+                    let Some((nonce, hash)) = mined.hit else {
+                        self.control.wait_if_paused_or_stopped()?;
+                        let due = report_interval
+                            .map_or(true, |interval| last_report.elapsed() >= interval);
+                        if let Some(cb) = on_progress.as_mut().filter(|_| due) {
+                            let last_nonce = scan.start + scan.size as u64 - 1;
+                            cb(job.progress(run, &pattern, run.attempts, Some(last_nonce)));
+                            last_report = Instant::now();
+                        }
+                        if scan.size < segment.size {
+                            // The budget check at the top of the loop reports exhaustion.
+                            segment = NonceSegment {
+                                start: scan.start + scan.size as u64,
+                                size: segment.size - scan.size,
+                                nonce_len: segment.nonce_len,
+                            };
+                            continue;
+                        }
+                        break;
+                    };
+
+                    let result = self.build_result(job, nonce, run.attempts)?;
+
                     if let Some(cb) = on_progress.as_mut() {
-                        let elapsed_ms = started_at.elapsed().as_millis();
-                        cb(ProgressStats {
-                            hashes_processed: attempts,
-                            hash_rate: hash_rate(attempts, elapsed_ms),
-                            elapsed_ms,
-                            last_nonce: Some(segment.start + segment.size as u64 - 1),
-                        });
+                        cb(job.progress(run, &pattern, run.attempts, Some(nonce)));
+                        last_report = Instant::now();
                     }
-                    continue;
-                }
-            }
 
-            if let Some((nonce, _txid_bytes)) = mined {
-                attempts += attempts_this_segment;
-                let nonce_bytes = if distribution.is_some() {
-                    encode_cbor_uint(nonce)
-                } else {
-                    encode_nonce(nonce)
-                };
-                let plan_with_nonce = zeldhash_miner_core::plan_transaction(
-                    parsed_inputs,
-                    output_requests,
-                    network,
-                    self.opts.sats_per_vbyte,
-                    &nonce_bytes,
-                    distribution,
-                )
-                .map_err(map_miner_error)?;
-                let (psbt, txid_bytes) =
-                    build_psbt_from_plan(&plan_with_nonce).map_err(map_miner_error)?;
-
-                let duration_ms = started_at.elapsed().as_millis();
-                let hash_rate = hash_rate(attempts, duration_ms);
-
-                let result = MineResult {
-                    psbt,
-                    txid: txid_to_hex(&txid_bytes),
-                    nonce,
-                    attempts,
-                    duration_ms,
-                    hash_rate,
-                };
-
-                if let Some(cb) = on_progress.as_mut() {
-                    cb(ProgressStats {
-                        hashes_processed: attempts,
-                        hash_rate,
-                        elapsed_ms: duration_ms,
-                        last_nonce: Some(nonce),
-                    });
-                }
+                    if let Some(cb) = on_found.as_mut() {
+                        cb(&result);
+                    }
 
-                if let Some(cb) = on_found.as_mut() {
-                    cb(&result);
-                }
+                    let Some(continue_mining) = job.continue_mining else {
+                        return Ok(result);
+                    };
+                    let zeros = job.unit.count(&hash);
+                    run.best = Some(result);
+                    if continue_mining.max_target.is_some_and(|max| zeros >= max) {
+                        return Ok(run.best.take().expect("best result was just recorded"));
+                    }
+                    pattern = job.unit.target(zeros + 1);
 
-                return Ok(result);
+                    // Resume right after the hit with the tightened target.
+                    let scanned = nonce - segment.start + 1;
+                    if scanned >= segment.size as u64 {
+                        break;
+                    }
+                    segment = NonceSegment {
+                        start: nonce + 1,
+                        size: segment.size - scanned as u32,
+                        nonce_len: segment.nonce_len,
+                    };
+                }
             }
         }
 
+        let message = match job.search_mode {
+            SearchMode::Bounded => "no matching nonce found in provided range",
+            SearchMode::Unbounded => "no matching nonce found before exhausting the nonce space",
+        };
         Err(ZeldMinerError::new(
             ZeldMinerErrorCode::NoMatchingNonce,
-            "no matching nonce found in provided range",
+            message,
         ))
     }
 
-    pub fn stop(&self) {
-        self.control.request_stop();
-    }
+    /// Mine one segment on the GPU when available, otherwise (or on GPU failure) on the CPU.
+    fn mine_segment(
+        &self,
+        job: &SegmentJob<'_>,
+        segment: &NonceSegment,
+        deterministic: bool,
+    ) -> Result<ControlledMineResult> {
+        if self.opts.use_gpu {
+            #[cfg(feature = "gpu")]
+            {
+                if let Some(ctx) = self.gpu_ctx.as_ref() {
+                    if let Ok(res) = mine_segment_gpu_controlled(ctx, job, segment) {
+                        return Ok(res);
+                    }
+                    // GPU path failed; fall back to CPU for this segment.
+                }
+            }
+            // If GPU work concluded and a stop was requested, abort before continuing.
+            self.control.wait_if_paused_or_stopped()?;
+        }
 
-    pub fn pause(&self) {
-        self.control.request_pause();
+        let workers = self.opts.worker_threads.max(1);
+        if workers > 1 {
+            mine_segment_cpu_parallel(job, segment, workers, deterministic)
+        } else {
+            mine_segment_cpu_controlled(job, segment, None)
+        }
+    }
+
+    fn build_result(&self, job: &MiningJob<'_>, nonce: u64, attempts: u128) -> Result<MineResult> {
+        let nonce_bytes = if job.distribution.is_some() {
+            encode_cbor_uint(nonce)
+        } else {
+            encode_nonce(nonce)
+        };
+        let plan = job.plan(&nonce_bytes)?;
+        let (psbt, txid_bytes) = build_psbt_from_plan(&plan).map_err(map_miner_error)?;
+
+        let duration_ms = job.started_at.elapsed().as_millis();
+        Ok(MineResult {
+            psbt,
+            txid: txid_to_hex(&txid_bytes),
+            nonce,
+            attempts,
+            duration_ms,
+            hash_rate: hash_rate(attempts, duration_ms),
+            leading_zeros: job.unit.count(&txid_bytes),
+            best_effort: false,
+        })
+    }
+
+    pub fn stop(&self) {
+        self.control.request_stop();
+    }
+
+    pub fn pause(&self) {
+        self.control.request_pause();
     }
 
     pub fn resume(&self) {
         self.control.resume();
     }
+
+    /// Fee rate PSBTs are planned with.
+    pub fn fee_rate(&self) -> FeeRate {
+        self.fee_rate
+    }
+
+    /// SHA-256 backend the CPU path runs on, detected from the host CPU.
+    pub fn cpu_hash_backend(&self) -> HashBackend {
+        HashBackend::detect()
+    }
+
+    /// Sign and finalize `result` with one private key per input, as 64 hex
+    /// characters or WIF for the miner's network. Only P2WPKH (native or nested) and
+    /// P2TR key-path inputs can be signed.
+    #[cfg(feature = "signer")]
+    pub fn sign_result<K: AsRef<str>>(
+        &self,
+        result: &MineResult,
+        keys: &[K],
+    ) -> Result<SignedTransaction> {
+        let invalid =
+            |message: String| ZeldMinerError::new(ZeldMinerErrorCode::InvalidInput, message);
+        let network = self.opts.network.to_core();
+        let psbt = psbt_from_base64(&result.psbt)
+            .and_then(|bytes| parse_psbt(&bytes))
+            .map_err(|err| invalid(format!("psbt: {err}")))?;
+        if let Some(recorded) = psbt.network.filter(|recorded| *recorded != network) {
+            return Err(invalid(format!(
+                "psbt was built for {}, not {}",
+                recorded.name(),
+                network.name()
+            )));
+        }
+        let keys = keys
+            .iter()
+            .enumerate()
+            .map(|(index, key)| {
+                parse_private_key(key.as_ref(), network)
+                    .map_err(|err| invalid(format!("keys[{index}]: {err}")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let signed = sign_psbt(&psbt, network, &keys).map_err(|err| invalid(err.to_string()))?;
+
+        Ok(SignedTransaction {
+            psbt: psbt_to_base64(&signed.psbt),
+            tx_hex: hex::encode(&signed.tx),
+            txid: txid_to_hex(&signed.txid),
+        })
+    }
+}
+
+impl MineResult {
+    /// Check the transaction a wallet signed from `psbt`: a finalized PSBT in base64
+    /// or hex, or the raw transaction in hex.
+    pub fn verify_signed(&self, signed: &str) -> Result<Verification> {
+        let invalid = |err: VerifyError| {
+            ZeldMinerError::new(ZeldMinerErrorCode::InvalidInput, err.to_string())
+        };
+        let mined_psbt = psbt_from_base64(&self.psbt).map_err(|err| invalid(err.into()))?;
+        let mined = MinedTx::from_psbt(&mined_psbt).map_err(invalid)?;
+        let signed = match hex::decode(signed.trim()) {
+            Ok(bytes) => bytes,
+            Err(_) => psbt_from_base64(signed).map_err(|err| invalid(err.into()))?,
+        };
+        let verification = verify_signed_tx(&mined, &signed).map_err(invalid)?;
+
+        Ok(Verification {
+            txid: txid_to_hex(&verification.txid),
+            txid_matches: verification.txid_matches,
+            leading_zeros: count_leading_zeros(&verification.txid),
+            leading_zero_bits: verification.leading_zero_bits,
+            payload_intact: verification.payload_intact,
+            mismatches: verification
+                .mismatches
+                .into_iter()
+                .map(TxMismatchDesc::from)
+                .collect(),
+            weight: verification.weight,
+            vsize: verification.vsize,
+            fee: verification.fee,
+            fee_rate_sat_per_kvb: verification.fee_rate.map(FeeRate::to_sat_per_kvb),
+        })
+    }
 }
 
 fn hash_rate(attempts: u128, duration_ms: u128) -> f64 {
@@ -483,6 +1295,224 @@ fn hash_rate(attempts: u128, duration_ms: u128) -> f64 {
     attempts as f64 / (duration_ms as f64 / 1000.0)
 }
 
+/// Inputs of one `mine_transaction` run that stay fixed across segments.
+struct MiningJob<'a> {
+    tx: JobTx,
+    network: Network,
+    distribution: Option<&'a [u64]>,
+    search_mode: SearchMode,
+    deterministic: bool,
+    unit: ZeroUnit,
+    continue_mining: Option<ContinueMining>,
+    policy: Policy,
+    max_attempts: Option<u64>,
+    progress_interval: Option<Duration>,
+    started_at: Instant,
+}
+
+/// Transaction a run mines, before the nonce OP_RETURN is added.
+struct JobTx {
+    inputs: Vec<TxInput>,
+    outputs: Vec<OutputRequest>,
+    fee: Fee,
+    fee_mode: FeeMode,
+    version: u32,
+    lock_time: LockTime,
+    psbt_version: PsbtVersion,
+    /// Global pairs of an imported PSBT, written back as they were.
+    global_pairs: Vec<PsbtPair>,
+    /// Metadata of the imported PSBT's OP_RETURN placeholder.
+    op_return_fields: Option<PsbtOutputFields>,
+}
+
+/// Progress of one `mine_transaction` run, kept across segments.
+struct RunState {
+    attempts: u128,
+    /// Latest improvement in continue mode.
+    best: Option<MineResult>,
+    /// Closest miss so far, for runs with a budget.
+    candidate: Option<BestCandidate>,
+    /// Set once `max_attempts` nonces have been hashed.
+    attempts_spent: bool,
+    /// Hashes seen so far; only filled when a progress callback is registered.
+    stats: HashStats,
+}
+
+/// Closest miss of a budgeted run, returned when the budget runs out before a hit.
+///
+/// Shared by every worker of a segment; ties go to the lowest nonce.
+struct BestCandidate {
+    unit: ZeroUnit,
+    /// Looser leading-zero pattern GPU batches dispatch with so near misses are reported.
+    #[cfg(feature = "gpu")]
+    gpu_pattern: Option<CompiledPattern>,
+    /// Zero count a hash needs to be considered; starts at the fallback target.
+    threshold: AtomicU16,
+    best: Mutex<Option<(u16, u64)>>,
+}
+
+impl BestCandidate {
+    fn new(unit: ZeroUnit, floor: u16) -> Self {
+        Self {
+            unit,
+            // Without a floor the GPU would report every nonce; it only reports hits then.
+            #[cfg(feature = "gpu")]
+            gpu_pattern: (floor > 0).then(|| unit.target(floor)),
+            threshold: AtomicU16::new(floor),
+            best: Mutex::new(None),
+        }
+    }
+
+    #[inline]
+    fn consider(&self, nonce: u64, hash: &[u8; 32]) {
+        let zeros = self.unit.count(hash);
+        if zeros < self.threshold.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut best = self.best.lock().expect("candidate mutex poisoned");
+        let better = match *best {
+            None => true,
+            Some((best_zeros, best_nonce)) => {
+                zeros > best_zeros || (zeros == best_zeros && nonce < best_nonce)
+            }
+        };
+        if better {
+            *best = Some((zeros, nonce));
+            self.threshold.store(zeros, Ordering::Relaxed);
+        }
+    }
+
+    fn take(&self) -> Option<u64> {
+        self.best
+            .lock()
+            .expect("candidate mutex poisoned")
+            .take()
+            .map(|(_, nonce)| nonce)
+    }
+}
+
+impl MiningJob<'_> {
+    /// Progress after `attempts` hashes; statistics only cover completed segments.
+    fn progress(
+        &self,
+        run: &RunState,
+        pattern: &CompiledPattern,
+        attempts: u128,
+        last_nonce: Option<u64>,
+    ) -> ProgressStats {
+        let elapsed_ms = self.started_at.elapsed().as_millis();
+        let rate = hash_rate(attempts, elapsed_ms);
+        let odds = MiningOdds::new(pattern.hit_probability(), attempts, rate);
+        let best = run.stats.best();
+        let histogram = run.stats.histogram();
+        let used = histogram.iter().rposition(|&n| n != 0).map_or(0, |i| i + 1);
+
+        ProgressStats {
+            hashes_processed: attempts,
+            hash_rate: rate,
+            elapsed_ms,
+            last_nonce,
+            best_txid: best.map(|(_, hash)| txid_to_hex(&hash)),
+            best_leading_zeros: best.map(|(_, hash)| self.unit.count(&hash)),
+            zero_histogram: histogram[..used].to_vec(),
+            expected_remaining_attempts: odds.expected_remaining_attempts,
+            eta_ms: odds.eta_ms,
+            probability_found: odds.probability_found,
+        }
+    }
+
+    fn plan(&self, nonce_bytes: &[u8]) -> Result<TransactionPlan> {
+        let options = PlanOptions {
+            fee: self.tx.fee,
+            fee_mode: self.tx.fee_mode.clone(),
+            policy: self.policy,
+            version: self.tx.version,
+            lock_time: self.tx.lock_time,
+            psbt_version: self.tx.psbt_version,
+        };
+        let mut plan = zeldhash_miner_core::plan_transaction_with_options(
+            self.tx.inputs.clone(),
+            self.tx.outputs.clone(),
+            self.network,
+            &options,
+            nonce_bytes,
+            self.distribution,
+        )
+        .map_err(map_miner_error)?;
+        plan.global_pairs = self.tx.global_pairs.clone();
+        if let Some(fields) = &self.tx.op_return_fields {
+            plan.output_fields.push(fields.clone());
+        }
+        Ok(plan)
+    }
+}
+
+/// Unit leading zeros are counted in: hex digits (`target_zeros`) or bits (`target_bits`).
+#[derive(Debug, Clone, Copy)]
+enum ZeroUnit {
+    HexDigits,
+    Bits,
+}
+
+impl ZeroUnit {
+    fn count(self, hash: &[u8; 32]) -> u16 {
+        match self {
+            ZeroUnit::HexDigits => count_leading_zeros(hash) as u16,
+            ZeroUnit::Bits => count_leading_zero_bits(hash),
+        }
+    }
+
+    fn target(self, zeros: u16) -> CompiledPattern {
+        match self {
+            ZeroUnit::HexDigits => CompiledPattern::leading_zeros(zeros.min(u8::MAX as u16) as u8),
+            ZeroUnit::Bits => CompiledPattern::leading_zero_bits(zeros),
+        }
+    }
+}
+
+/// Walks the nonce space in `batch_size` chunks according to the search mode.
+struct NonceChunks {
+    next_start: Option<u64>,
+    batch_size: u32,
+    mode: SearchMode,
+}
+
+impl NonceChunks {
+    fn new(start_nonce: u64, batch_size: u32, mode: SearchMode) -> Result<Self> {
+        if mode == SearchMode::Bounded {
+            // Bounded runs must fit entirely in the u64 nonce space.
+            start_nonce
+                .checked_add(batch_size as u64 - 1)
+                .ok_or_else(|| {
+                    ZeldMinerError::new(ZeldMinerErrorCode::InvalidInput, "nonce range overflow")
+                })?;
+        }
+        Ok(Self {
+            next_start: Some(start_nonce),
+            batch_size,
+            mode,
+        })
+    }
+
+    fn next_chunk(&mut self) -> Option<(u64, u32)> {
+        let start = self.next_start?;
+        // Clamp the final chunk so it ends exactly at u64::MAX.
+        let remaining = u64::MAX - start;
+        let size = if remaining < self.batch_size as u64 {
+            remaining as u32 + 1
+        } else {
+            self.batch_size
+        };
+        let last = start + (size as u64 - 1);
+
+        self.next_start = match self.mode {
+            SearchMode::Bounded => None,
+            SearchMode::Unbounded => last.checked_add(1),
+        };
+        Some((start, size))
+    }
+}
+
 fn decode_txid_hex(txid: &str) -> Result<[u8; 32]> {
     let mut bytes = hex::decode(txid)
         .map_err(|_| ZeldMinerError::new(ZeldMinerErrorCode::InvalidInput, "invalid txid hex"))?;
@@ -507,12 +1537,79 @@ fn decode_hex_bytes(data: &str) -> Result<Vec<u8>> {
     })
 }
 
+fn decode_hex_array<const N: usize>(data: &str, field: &str) -> Result<[u8; N]> {
+    hex::decode(data)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            ZeldMinerError::new(
+                ZeldMinerErrorCode::InvalidInput,
+                format!("{field} must be {N} bytes of hex"),
+            )
+        })
+}
+
+fn parse_key_source(fingerprint: &str, path: &str) -> Result<KeySource> {
+    Ok(KeySource {
+        fingerprint: decode_hex_array(fingerprint, "fingerprint")?,
+        path: parse_derivation_path(path).map_err(|err| {
+            ZeldMinerError::new(ZeldMinerErrorCode::InvalidInput, err.to_string())
+        })?,
+    })
+}
+
+fn parse_bip32_derivations(derivations: &[Bip32DerivationDesc]) -> Result<Vec<Bip32Derivation>> {
+    derivations
+        .iter()
+        .map(|derivation| {
+            Ok(Bip32Derivation {
+                pubkey: hex::decode(&derivation.pubkey).map_err(|_| {
+                    ZeldMinerError::new(
+                        ZeldMinerErrorCode::InvalidInput,
+                        "pubkey must be valid hex",
+                    )
+                })?,
+                source: parse_key_source(&derivation.fingerprint, &derivation.path)?,
+            })
+        })
+        .collect()
+}
+
+fn parse_tap_key_derivations(
+    derivations: &[TapKeyDerivationDesc],
+) -> Result<Vec<TapKeyDerivation>> {
+    derivations
+        .iter()
+        .map(|derivation| {
+            Ok(TapKeyDerivation {
+                x_only_pubkey: decode_hex_array(&derivation.x_only_pubkey, "x_only_pubkey")?,
+                leaf_hashes: derivation
+                    .leaf_hashes
+                    .iter()
+                    .map(|hash| decode_hex_array(hash, "leaf hash"))
+                    .collect::<Result<_>>()?,
+                source: parse_key_source(&derivation.fingerprint, &derivation.path)?,
+            })
+        })
+        .collect()
+}
+
+fn parse_tap_internal_key(key: Option<&str>) -> Result<Option<[u8; 32]>> {
+    key.map(|key| decode_hex_array(key, "tap_internal_key"))
+        .transpose()
+}
+
 fn parse_inputs(inputs: &[TxInputDesc]) -> Result<Vec<TxInput>> {
     inputs
         .iter()
         .map(|input| {
             let txid = decode_txid_hex(&input.txid)?;
             let script_pubkey = decode_hex_bytes(&input.script_pubkey)?;
+            let redeem_script = input
+                .redeem_script
+                .as_deref()
+                .map(decode_hex_bytes)
+                .transpose()?;
             Ok(TxInput {
                 txid,
                 vout: input.vout,
@@ -521,11 +1618,180 @@ fn parse_inputs(inputs: &[TxInputDesc]) -> Result<Vec<TxInput>> {
                 sequence: input
                     .sequence
                     .unwrap_or(zeldhash_miner_core::tx::DEFAULT_SEQUENCE),
+                witness_hint: input.witness.map(WitnessHint::from),
+                redeem_script,
+                psbt: PsbtInputFields {
+                    non_witness_utxo: input
+                        .non_witness_utxo
+                        .as_deref()
+                        .map(|tx| {
+                            hex::decode(tx).map_err(|_| {
+                                ZeldMinerError::new(
+                                    ZeldMinerErrorCode::InvalidInput,
+                                    "non_witness_utxo must be valid hex",
+                                )
+                            })
+                        })
+                        .transpose()?,
+                    sighash_type: input.sighash_type,
+                    bip32_derivations: parse_bip32_derivations(&input.bip32_derivations)?,
+                    tap_internal_key: parse_tap_internal_key(input.tap_internal_key.as_deref())?,
+                    tap_key_derivations: parse_tap_key_derivations(&input.tap_key_derivations)?,
+                    tap_merkle_root: None,
+                    unknown: Vec::new(),
+                },
+            })
+        })
+        .collect()
+}
+
+fn parse_outputs(outputs: &[TxOutputDesc]) -> Result<Vec<OutputRequest>> {
+    outputs
+        .iter()
+        .map(|output| {
+            let kind = match &output.kind {
+                OutputKindDesc::Address => OutputKind::Address,
+                OutputKindDesc::Anchor => OutputKind::Anchor,
+                OutputKindDesc::Script(script) => OutputKind::Script(decode_hex_bytes(script)?),
+            };
+            Ok(OutputRequest {
+                address: output.address.clone(),
+                amount: output.amount,
+                change: output.change,
+                kind,
+                psbt: PsbtOutputFields {
+                    bip32_derivations: parse_bip32_derivations(&output.bip32_derivations)?,
+                    tap_internal_key: parse_tap_internal_key(output.tap_internal_key.as_deref())?,
+                    tap_key_derivations: parse_tap_key_derivations(&output.tap_key_derivations)?,
+                    unknown: Vec::new(),
+                },
             })
         })
         .collect()
 }
 
+/// Turn a wallet-built PSBT into the transaction to mine.
+fn import_psbt(desc: &PsbtDesc, network: Network) -> Result<JobTx> {
+    let invalid = |message: String| ZeldMinerError::new(ZeldMinerErrorCode::InvalidInput, message);
+    let parsed = psbt_from_base64(&desc.psbt)
+        .and_then(|bytes| parse_psbt(&bytes))
+        .map_err(|err| invalid(format!("psbt: {err}")))?;
+    if let Some(recorded) = parsed.network.filter(|recorded| *recorded != network) {
+        return Err(invalid(format!(
+            "psbt was built for {}, not {}",
+            recorded.name(),
+            network.name()
+        )));
+    }
+    if desc.witness_hints.len() > parsed.inputs.len() {
+        return Err(invalid(
+            "witness_hints has more entries than the PSBT has inputs".into(),
+        ));
+    }
+
+    let mut inputs = parsed.inputs;
+    for (idx, input) in inputs.iter_mut().enumerate() {
+        if input.psbt.is_signed() {
+            return Err(invalid(format!(
+                "inputs[{idx}] is already signed; mining changes the txid"
+            )));
+        }
+        input.witness_hint = desc
+            .witness_hints
+            .get(idx)
+            .copied()
+            .flatten()
+            .map(Into::into);
+    }
+
+    // Keep the fee rate the wallet chose for the transaction it built.
+    let input_total = inputs
+        .iter()
+        .try_fold(0u64, |total, input| total.checked_add(input.amount))
+        .ok_or_else(|| invalid("psbt input amounts overflow".into()))?;
+    let fee = parsed
+        .outputs
+        .iter()
+        .try_fold(0u64, |total, output| total.checked_add(output.amount))
+        .and_then(|output_total| input_total.checked_sub(output_total))
+        .ok_or_else(|| {
+            ZeldMinerError::new(
+                ZeldMinerErrorCode::InsufficientFunds,
+                "psbt outputs exceed its inputs",
+            )
+        })?;
+    let vsize = calculate_tx_vsize(&inputs, &parsed.outputs)
+        .map_err(|err| map_miner_error(MinerError::from(err)))?;
+    let fee_rate = FeeRate::from_fee_and_vsize(fee, vsize);
+
+    // The last zero-amount OP_RETURN is the wallet's placeholder for the nonce.
+    let placeholder = parsed
+        .outputs
+        .iter()
+        .rposition(|output| output.amount == 0 && is_op_return(&output.script_pubkey));
+    let change_index = match desc.change_index {
+        Some(idx) if idx < parsed.outputs.len() && Some(idx) != placeholder => idx,
+        Some(idx) => {
+            return Err(invalid(format!(
+                "change_index {idx} is not a payment output"
+            )))
+        }
+        None => {
+            let mut owned = parsed
+                .output_fields
+                .iter()
+                .enumerate()
+                .filter(|(_, fields)| {
+                    !fields.bip32_derivations.is_empty()
+                        || fields.tap_internal_key.is_some()
+                        || !fields.tap_key_derivations.is_empty()
+                });
+            match (owned.next(), owned.next()) {
+                (Some((idx, _)), None) => idx,
+                _ => {
+                    return Err(invalid(
+                        "no single output carries key origins; set change_index".into(),
+                    ))
+                }
+            }
+        }
+    };
+
+    let mut outputs = Vec::new();
+    let mut op_return_fields = None;
+    for (idx, (output, fields)) in parsed
+        .outputs
+        .into_iter()
+        .zip(parsed.output_fields)
+        .enumerate()
+    {
+        if Some(idx) == placeholder {
+            op_return_fields = Some(fields);
+            continue;
+        }
+        let change = idx == change_index;
+        outputs.push(OutputRequest {
+            address: String::new(),
+            amount: (!change).then_some(output.amount),
+            change,
+            kind: OutputKind::Script(output.script_pubkey),
+            psbt: fields,
+        });
+    }
+
+    Ok(JobTx {
+        inputs,
+        outputs,
+        fee: Fee::Rate(fee_rate),
+        fee_mode: FeeMode::Change,
+        version: parsed.tx_params.version,
+        lock_time: LockTime::from_consensus(parsed.tx_params.lock_time),
+        psbt_version: parsed.version,
+        global_pairs: parsed.global_pairs,
+        op_return_fields,
+    })
+}
+
 fn map_miner_error(err: MinerError) -> ZeldMinerError {
     match err {
         MinerError::InvalidInput(msg) => ZeldMinerError::new(ZeldMinerErrorCode::InvalidInput, msg),
@@ -545,6 +1811,13 @@ fn map_miner_error(err: MinerError) -> ZeldMinerError {
                 FeeError::InsufficientFunds => {
                     ZeldMinerError::new(ZeldMinerErrorCode::InsufficientFunds, fee_err.to_string())
                 }
+                _ => ZeldMinerError::new(ZeldMinerErrorCode::InvalidInput, fee_err.to_string()),
+            },
+            zeldhash_miner_core::ZeldError::Policy(policy_err) => match policy_err {
+                PolicyError::Dust { .. } => {
+                    ZeldMinerError::new(ZeldMinerErrorCode::DustOutput, policy_err.to_string())
+                }
+                _ => ZeldMinerError::new(ZeldMinerErrorCode::InvalidInput, policy_err.to_string()),
             },
             zeldhash_miner_core::ZeldError::Tx(_) | zeldhash_miner_core::ZeldError::Psbt(_) => {
                 ZeldMinerError::new(ZeldMinerErrorCode::WorkerError, core.to_string())
@@ -560,63 +1833,136 @@ fn mining_aborted_error(message: &str) -> ZeldMinerError {
 struct ControlledMineResult {
     attempts: u128,
     hit: Option<(u64, [u8; 32])>,
+    /// Statistics of the hashes checked, when the segment records them.
+    stats: Option<HashStats>,
+}
+
+/// Inputs shared by every worker mining a segment.
+#[derive(Clone, Copy)]
+struct SegmentJob<'a> {
+    prefix: &'a [u8],
+    suffix: &'a [u8],
+    pattern: &'a CompiledPattern,
+    use_cbor_nonce: bool,
+    control: &'a MiningControl,
+    candidate: Option<&'a BestCandidate>,
+    /// Keep a leading-zero histogram and best txid for progress reports.
+    record_stats: bool,
+    /// Live hash count for progress reports issued before the segment completes.
+    progress: Option<&'a SegmentProgress>,
+}
+
+/// Hashes checked so far in the segment being mined, summed over its workers.
+struct SegmentProgress {
+    attempts: AtomicU64,
+    /// Highest nonce a worker has reported as checked.
+    last_nonce: AtomicU64,
+}
+
+impl SegmentProgress {
+    /// Hashes a CPU worker checks between updates, keeping the counters uncontended.
+    const CPU_FLUSH_EVERY: u32 = 4096;
+
+    fn new() -> Self {
+        Self {
+            attempts: AtomicU64::new(0),
+            last_nonce: AtomicU64::new(0),
+        }
+    }
+
+    fn add(&self, attempts: u64, last_nonce: u64) {
+        self.last_nonce.fetch_max(last_nonce, Ordering::Relaxed);
+        self.attempts.fetch_add(attempts, Ordering::Relaxed);
+    }
+
+    /// Hashes checked so far and the highest nonce reached, if any.
+    fn snapshot(&self) -> (u64, Option<u64>) {
+        let attempts = self.attempts.load(Ordering::Relaxed);
+        let last_nonce = (attempts > 0).then(|| self.last_nonce.load(Ordering::Relaxed));
+        (attempts, last_nonce)
+    }
 }
 
 fn mine_segment_cpu_controlled(
-    prefix: &[u8],
-    suffix: &[u8],
+    job: &SegmentJob<'_>,
     segment: &NonceSegment,
-    target_zeros: u8,
-    use_cbor_nonce: bool,
-    control: &MiningControl,
-    found_flag: Option<&AtomicBool>,
+    shared_hit: Option<&SharedHit>,
 ) -> Result<ControlledMineResult> {
-    let nonce_len = segment.nonce_len as usize;
-    let mut buffer = Vec::with_capacity(prefix.len() + suffix.len() + nonce_len);
+    let mut hasher = LaneHasher::new(job.prefix, job.suffix, segment.nonce_len as usize);
+    let lanes = hasher.lanes() as u32;
     let mut nonce_buf = [0u8; 9];
+    let mut hashes = [[0u8; 32]; MAX_LANES];
+    let mut stats = job.record_stats.then(HashStats::new);
+    let mut offset = 0u32;
+    let mut unreported = 0u32;
 
-    for offset in 0..segment.size {
-        control.wait_if_paused_or_stopped()?;
+    while offset < segment.size {
+        job.control.wait_if_paused_or_stopped()?;
 
-        if let Some(flag) = found_flag {
-            if flag.load(Ordering::Acquire) {
+        if let Some(shared) = shared_hit {
+            if shared.should_stop(segment.start.saturating_add(offset as u64)) {
                 return Ok(ControlledMineResult {
                     attempts: offset as u128,
                     hit: None,
+                    stats,
                 });
             }
         }
 
-        let nonce = match segment.start.checked_add(offset as u64) {
-            Some(n) => n,
-            None => {
-                return Err(ZeldMinerError::new(
-                    ZeldMinerErrorCode::InvalidInput,
-                    "nonce range overflow",
-                ))
-            }
-        };
-
-        let written =
-            encode_nonce_for_segment(nonce, segment.nonce_len, use_cbor_nonce, &mut nonce_buf)?;
+        let filled = lanes.min(segment.size - offset);
+        for lane in 0..filled {
+            let nonce = match segment.start.checked_add((offset + lane) as u64) {
+                Some(n) => n,
+                None => {
+                    return Err(ZeldMinerError::new(
+                        ZeldMinerErrorCode::InvalidInput,
+                        "nonce range overflow",
+                    ))
+                }
+            };
+
+            let written = encode_nonce_for_segment(
+                nonce,
+                segment.nonce_len,
+                job.use_cbor_nonce,
+                &mut nonce_buf,
+            )?;
+            hasher.set_nonce(lane as usize, &nonce_buf[..written]);
+        }
 
-        buffer.clear();
-        buffer.extend_from_slice(prefix);
-        buffer.extend_from_slice(&nonce_buf[..written]);
-        buffer.extend_from_slice(suffix);
+        hasher.hash_lanes(&mut hashes);
+        // Lanes are checked in nonce order so the first hit matches a sequential scan.
+        for (lane, hash) in hashes.iter().take(filled as usize).enumerate() {
+            let nonce = segment.start + (offset + lane as u32) as u64;
+            if let Some(stats) = stats.as_mut() {
+                stats.record(nonce, hash);
+            }
+            if job.pattern.matches(hash) {
+                return Ok(ControlledMineResult {
+                    attempts: (offset + lane as u32) as u128 + 1,
+                    hit: Some((nonce, *hash)),
+                    stats,
+                });
+            }
+            if let Some(candidate) = job.candidate {
+                candidate.consider(nonce, hash);
+            }
+        }
+        offset += filled;
 
-        let hash = double_sha256(&buffer);
-        if hash_meets_target(&hash, target_zeros) {
-            return Ok(ControlledMineResult {
-                attempts: offset as u128 + 1,
-                hit: Some((nonce, hash)),
-            });
+        if let Some(progress) = job.progress {
+            unreported += filled;
+            if unreported >= SegmentProgress::CPU_FLUSH_EVERY {
+                progress.add(unreported as u64, segment.start + offset as u64 - 1);
+                unreported = 0;
+            }
         }
     }
 
     Ok(ControlledMineResult {
         attempts: segment.size as u128,
         hit: None,
+        stats,
     })
 }
 
@@ -648,104 +1994,82 @@ fn split_segment_for_workers(segment: &NonceSegment, workers: usize) -> Result<V
 }
 
 fn mine_segment_cpu_parallel(
-    prefix: &[u8],
-    suffix: &[u8],
+    job: &SegmentJob<'_>,
     segment: &NonceSegment,
-    target_zeros: u8,
-    use_cbor_nonce: bool,
-    control: &MiningControl,
     workers: usize,
+    deterministic: bool,
 ) -> Result<ControlledMineResult> {
     if workers <= 1 || segment.size <= 1 {
-        return mine_segment_cpu_controlled(
-            prefix,
-            suffix,
-            segment,
-            target_zeros,
-            use_cbor_nonce,
-            control,
-            None,
-        );
+        return mine_segment_cpu_controlled(job, segment, None);
     }
 
     let subs = split_segment_for_workers(segment, workers)?;
     if subs.len() == 1 {
-        return mine_segment_cpu_controlled(
-            prefix,
-            suffix,
-            segment,
-            target_zeros,
-            use_cbor_nonce,
-            control,
-            None,
-        );
+        return mine_segment_cpu_controlled(job, segment, None);
     }
 
     #[cfg(feature = "rayon")]
     {
-        let found = Arc::new(AtomicBool::new(false));
-        let attempts_acc = Arc::new(Mutex::new(0u128));
-        let result = Arc::new(Mutex::new(None));
+        let shared = SharedHit::new(deterministic);
+        let attempts_acc = AtomicU64::new(0);
+        let stats_acc = Mutex::new(HashStats::new());
 
         subs.into_par_iter().try_for_each(|sub| {
-            let res = mine_segment_cpu_controlled(
-                prefix,
-                suffix,
-                &sub,
-                target_zeros,
-                use_cbor_nonce,
-                control,
-                Some(found.as_ref()),
-            );
+            let res = mine_segment_cpu_controlled(job, &sub, Some(&shared));
 
             match res {
                 Ok(r) => {
-                    if r.hit.is_some() && !found.swap(true, Ordering::AcqRel) {
-                        *result.lock().expect("result mutex poisoned") = r.hit;
+                    if let Some(hit) = r.hit {
+                        shared.offer(hit);
+                    }
+                    if let Some(stats) = r.stats.as_ref() {
+                        stats_acc.lock().expect("stats mutex poisoned").merge(stats);
                     }
-                    *attempts_acc.lock().expect("attempts mutex poisoned") += r.attempts;
+                    attempts_acc.fetch_add(r.attempts as u64, Ordering::Relaxed);
                     Ok(())
                 }
                 Err(err) => Err(err),
             }
         })?;
 
-        let attempts = *attempts_acc.lock().expect("attempts mutex poisoned");
-        let hit = result.lock().expect("result mutex poisoned").take();
+        let attempts = attempts_acc.into_inner() as u128;
+        let hit = shared.take();
+        let stats = job
+            .record_stats
+            .then(|| stats_acc.into_inner().expect("stats mutex poisoned"));
 
-        Ok(ControlledMineResult { attempts, hit })
+        Ok(ControlledMineResult {
+            attempts,
+            hit,
+            stats,
+        })
     }
 
     #[cfg(not(feature = "rayon"))]
     {
-        let found = Arc::new(AtomicBool::new(false));
-        let attempts_acc = Arc::new(Mutex::new(0u128));
-        let result = Arc::new(Mutex::new(None));
-        let first_err = Arc::new(Mutex::new(None));
+        let shared = SharedHit::new(deterministic);
+        let attempts_acc = AtomicU64::new(0);
+        let stats_acc = Mutex::new(HashStats::new());
+        let first_err = Mutex::new(None);
 
         thread::scope(|scope| {
             for sub in subs {
-                let found = Arc::clone(&found);
-                let attempts_acc = Arc::clone(&attempts_acc);
-                let result = Arc::clone(&result);
-                let first_err = Arc::clone(&first_err);
+                let shared = &shared;
+                let stats_acc = &stats_acc;
+                let attempts_acc = &attempts_acc;
+                let first_err = &first_err;
                 scope.spawn(move || {
-                    let res = mine_segment_cpu_controlled(
-                        prefix,
-                        suffix,
-                        &sub,
-                        target_zeros,
-                        use_cbor_nonce,
-                        control,
-                        Some(found.as_ref()),
-                    );
+                    let res = mine_segment_cpu_controlled(job, &sub, Some(shared));
 
                     match res {
                         Ok(r) => {
-                            if r.hit.is_some() && !found.swap(true, Ordering::AcqRel) {
-                                *result.lock().expect("result mutex poisoned") = r.hit;
+                            if let Some(hit) = r.hit {
+                                shared.offer(hit);
                             }
-                            *attempts_acc.lock().expect("attempts mutex poisoned") += r.attempts;
+                            if let Some(stats) = r.stats.as_ref() {
+                                stats_acc.lock().expect("stats mutex poisoned").merge(stats);
+                            }
+                            attempts_acc.fetch_add(r.attempts as u64, Ordering::Relaxed);
                         }
                         Err(err) => {
                             *first_err.lock().expect("error mutex poisoned") = Some(err);
@@ -759,42 +2083,98 @@ fn mine_segment_cpu_parallel(
             return Err(err);
         }
 
-        let attempts = *attempts_acc.lock().expect("attempts mutex poisoned");
-        let hit = result.lock().expect("result mutex poisoned").take();
+        let attempts = attempts_acc.into_inner() as u128;
+        let hit = shared.take();
+        let stats = job
+            .record_stats
+            .then(|| stats_acc.into_inner().expect("stats mutex poisoned"));
+
+        Ok(ControlledMineResult {
+            attempts,
+            hit,
+            stats,
+        })
+    }
+}
+
+/// Hit shared by the CPU workers of one segment.
+///
+/// By default the first reported hit wins and every worker stops. When
+/// `lowest_nonce_wins` is set, a worker only stops once a hit below its current
+/// nonce exists, and the smallest reported nonce wins.
+struct SharedHit {
+    found: AtomicBool,
+    lowest: AtomicU64,
+    best: Mutex<Option<(u64, [u8; 32])>>,
+    lowest_nonce_wins: bool,
+}
+
+impl SharedHit {
+    fn new(lowest_nonce_wins: bool) -> Self {
+        Self {
+            found: AtomicBool::new(false),
+            lowest: AtomicU64::new(u64::MAX),
+            best: Mutex::new(None),
+            lowest_nonce_wins,
+        }
+    }
+
+    fn should_stop(&self, next_nonce: u64) -> bool {
+        if !self.found.load(Ordering::Acquire) {
+            return false;
+        }
+        !self.lowest_nonce_wins || self.lowest.load(Ordering::Acquire) < next_nonce
+    }
+
+    fn offer(&self, hit: (u64, [u8; 32])) {
+        let mut best = self.best.lock().expect("result mutex poisoned");
+        let replace = match *best {
+            None => true,
+            Some((nonce, _)) => self.lowest_nonce_wins && hit.0 < nonce,
+        };
+        if replace {
+            *best = Some(hit);
+        }
+        self.lowest.fetch_min(hit.0, Ordering::AcqRel);
+        self.found.store(true, Ordering::Release);
+    }
 
-        Ok(ControlledMineResult { attempts, hit })
+    fn take(&self) -> Option<(u64, [u8; 32])> {
+        self.best.lock().expect("result mutex poisoned").take()
     }
 }
 
 #[cfg(feature = "gpu")]
 fn mine_segment_gpu_controlled(
     ctx: &GpuContext,
-    prefix: &[u8],
-    suffix: &[u8],
+    job: &SegmentJob<'_>,
     segment: &NonceSegment,
-    target_zeros: u8,
-    use_cbor_nonce: bool,
-    control: &MiningControl,
 ) -> Result<ControlledMineResult> {
     let mut attempts: u128 = 0;
     let mut remaining = segment.size;
     let mut current_start = segment.start;
+    // The GPU only reports hits, so only those reach the statistics.
+    let mut stats = job.record_stats.then(HashStats::new);
 
     while remaining > 0 {
-        control.wait_if_paused_or_stopped()?;
+        job.control.wait_if_paused_or_stopped()?;
 
         let chunk = remaining.min(GPU_MAX_BATCH_SIZE);
         let batch = MiningBatch {
-            tx_prefix: prefix,
-            tx_suffix: suffix,
+            tx_prefix: job.prefix,
+            tx_suffix: job.suffix,
             start_nonce: current_start,
             batch_size: chunk,
-            target_zeros,
-            use_cbor_nonce,
+            pattern: job.pattern,
+            use_cbor_nonce: job.use_cbor_nonce,
+        };
+        let dispatch = |batch: &MiningBatch| {
+            pollster::block_on(dispatch_mining_batch(ctx, batch)).map_err(|err| {
+                ZeldMinerError::new(ZeldMinerErrorCode::WorkerError, err.to_string())
+            })
         };
 
-        let results = pollster::block_on(dispatch_mining_batch(ctx, &batch))
-            .map_err(|err| ZeldMinerError::new(ZeldMinerErrorCode::WorkerError, err.to_string()))?;
+        let results = gpu_chunk_hits(job, &batch, &mut stats, dispatch)?;
 
         if let Some(best) = select_best_gpu_result(&results) {
             let attempts_to_hit = best
@@ -806,13 +2186,20 @@ fn mine_segment_gpu_controlled(
                 })?;
 
             attempts += attempts_to_hit as u128;
+            if let Some(stats) = stats.as_mut() {
+                stats.record(best.nonce, &best.txid);
+            }
             return Ok(ControlledMineResult {
                 attempts,
                 hit: Some((best.nonce, best.txid)),
+                stats,
             });
         }
 
         attempts += chunk as u128;
+        if let Some(progress) = job.progress {
+            progress.add(chunk as u64, current_start + chunk as u64 - 1);
+        }
         current_start = current_start.checked_add(chunk as u64).ok_or_else(|| {
             ZeldMinerError::new(ZeldMinerErrorCode::InvalidInput, "nonce range overflow")
         })?;
@@ -822,9 +2209,51 @@ fn mine_segment_gpu_controlled(
     Ok(ControlledMineResult {
         attempts,
         hit: None,
+        stats,
     })
 }
 
+/// Hits of one GPU chunk, always including its lowest hit.
+///
+/// Budgeted runs dispatch with the candidate's looser pattern so near misses are
+/// reported too. The GPU only guarantees the lowest match of the pattern it ran, so
+/// once near misses fill every result slot the chunk is dispatched again with the
+/// strict pattern.
+#[cfg(feature = "gpu")]
+fn gpu_chunk_hits(
+    job: &SegmentJob<'_>,
+    batch: &MiningBatch<'_>,
+    stats: &mut Option<HashStats>,
+    mut dispatch: impl FnMut(&MiningBatch<'_>) -> Result<Vec<GpuMineResult>>,
+) -> Result<Vec<GpuMineResult>> {
+    let Some((candidate, loose)) = job
+        .candidate
+        .and_then(|c| c.gpu_pattern.as_ref().map(|p| (c, p)))
+    else {
+        return dispatch(batch);
+    };
+
+    let mut results = dispatch(&MiningBatch {
+        pattern: loose,
+        ..*batch
+    })?;
+    let saturated = results.len() >= GPU_MAX_RESULTS;
+    results.retain(|r| {
+        if let Some(stats) = stats.as_mut() {
+            stats.record(r.nonce, &r.txid);
+        }
+        let hit = job.pattern.matches(&r.txid);
+        if !hit {
+            candidate.consider(r.nonce, &r.txid);
+        }
+        hit
+    });
+    if saturated {
+        results = dispatch(batch)?;
+    }
+    Ok(results)
+}
+
 #[cfg(feature = "gpu")]
 fn select_best_gpu_result(results: &[GpuMineResult]) -> Option<GpuMineResult> {
     results.iter().min_by_key(|r| r.nonce).cloned()
@@ -837,15 +2266,14 @@ fn encode_nonce_for_segment(
     out: &mut [u8; 9],
 ) -> Result<usize> {
     if use_cbor_nonce {
-        let encoded = encode_cbor_uint(nonce);
-        if encoded.len() != nonce_len as usize {
+        let written = encode_cbor_uint_into(nonce, out);
+        if written != nonce_len as usize {
             return Err(ZeldMinerError::new(
                 ZeldMinerErrorCode::InvalidInput,
                 "CBOR nonce length mismatch for segment",
             ));
         }
-        out[..encoded.len()].copy_from_slice(&encoded);
-        return Ok(encoded.len());
+        return Ok(written);
     }
 
     encode_nonce_fixed_into(nonce, nonce_len, out)
@@ -887,43 +2315,169 @@ fn encode_nonce_fixed_into(nonce: u64, nonce_len: u8, out: &mut [u8; 9]) -> Resu
     Ok(target_len)
 }
 
-impl From<TxOutputDesc> for OutputRequest {
-    fn from(value: TxOutputDesc) -> Self {
-        OutputRequest {
-            address: value.address,
-            amount: value.amount,
-            change: value.change,
+impl From<TxidPatternDesc> for TxidPattern {
+    fn from(value: TxidPatternDesc) -> Self {
+        match value {
+            TxidPatternDesc::LeadingZeros(zeros) => TxidPattern::LeadingZeros(zeros),
+            TxidPatternDesc::LeadingZeroBits(bits) => TxidPattern::LeadingZeroBits(bits),
+            TxidPatternDesc::Prefix(hex) => TxidPattern::Prefix(hex),
+            TxidPatternDesc::Suffix(hex) => TxidPattern::Suffix(hex),
+            TxidPatternDesc::Masked(hex) => TxidPattern::Masked(hex),
+            TxidPatternDesc::AnyOf(patterns) => {
+                TxidPattern::AnyOf(patterns.into_iter().map(TxidPattern::from).collect())
+            }
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use bitcoin::bech32::{segwit, Hrp};
-    use bitcoin::psbt::Psbt;
-    use std::str::FromStr;
-    use zeldhash_miner_core::create_zeld_distribution_op_return;
-
-    fn sample_addresses() -> (String, String) {
-        let hrp = Hrp::parse("bc").expect("mainnet hrp");
-        let user_program = [0x33u8; 20];
-        let change_program = [0x22u8; 20];
-        let user = segwit::encode(hrp, segwit::VERSION_0, &user_program).unwrap();
-        let change = segwit::encode(hrp, segwit::VERSION_0, &change_program).unwrap();
-        (user, change)
+impl From<FeeModeDesc> for FeeMode {
+    fn from(value: FeeModeDesc) -> Self {
+        match value {
+            FeeModeDesc::Change => FeeMode::Change,
+            FeeModeDesc::SubtractFromOutputs(indices) => FeeMode::SubtractFromOutputs(indices),
+            FeeModeDesc::SubtractProportionally => FeeMode::SubtractProportionally,
+            FeeModeDesc::SendMax(index) => FeeMode::SendMax(index),
+        }
     }
+}
 
-    fn sample_input_desc(amount: u64) -> TxInputDesc {
-        let mut spk = vec![0x00, 0x14];
-        spk.extend_from_slice(&[0x22u8; 20]);
-
-        TxInputDesc {
-            txid: "11".repeat(32),
-            vout: 0,
-            script_pubkey: hex::encode(spk),
-            amount,
-            sequence: Some(zeldhash_miner_core::tx::DEFAULT_SEQUENCE),
+impl From<LockTimeDesc> for LockTime {
+    fn from(value: LockTimeDesc) -> Self {
+        match value {
+            LockTimeDesc::None => LockTime::None,
+            LockTimeDesc::Height(height) => LockTime::Height(height),
+            LockTimeDesc::Time(time) => LockTime::Time(time),
+            LockTimeDesc::AntiFeeSniping { tip_height } => LockTime::AntiFeeSniping { tip_height },
+        }
+    }
+}
+
+impl From<PsbtVersionDesc> for PsbtVersion {
+    fn from(value: PsbtVersionDesc) -> Self {
+        match value {
+            PsbtVersionDesc::V0 => PsbtVersion::V0,
+            PsbtVersionDesc::V2 => PsbtVersion::V2,
+        }
+    }
+}
+
+impl From<TxMismatch> for TxMismatchDesc {
+    fn from(value: TxMismatch) -> Self {
+        match value {
+            TxMismatch::Version { expected, actual } => {
+                TxMismatchDesc::Version { expected, actual }
+            }
+            TxMismatch::LockTime { expected, actual } => {
+                TxMismatchDesc::LockTime { expected, actual }
+            }
+            TxMismatch::InputCount { expected, actual } => {
+                TxMismatchDesc::InputCount { expected, actual }
+            }
+            TxMismatch::Outpoint { index } => TxMismatchDesc::Outpoint { index },
+            TxMismatch::Sequence {
+                index,
+                expected,
+                actual,
+            } => TxMismatchDesc::Sequence {
+                index,
+                expected,
+                actual,
+            },
+            TxMismatch::ScriptSig { index } => TxMismatchDesc::ScriptSig { index },
+            TxMismatch::InputAmount {
+                index,
+                expected,
+                actual,
+            } => TxMismatchDesc::InputAmount {
+                index,
+                expected,
+                actual,
+            },
+            TxMismatch::OutputCount { expected, actual } => {
+                TxMismatchDesc::OutputCount { expected, actual }
+            }
+            TxMismatch::Output { index } => TxMismatchDesc::Output { index },
+        }
+    }
+}
+
+impl From<WitnessHintDesc> for WitnessHint {
+    fn from(value: WitnessHintDesc) -> Self {
+        match value {
+            WitnessHintDesc::Size(size) => WitnessHint::Size(size),
+            WitnessHintDesc::Multisig { required, keys } => {
+                WitnessHint::Multisig { required, keys }
+            }
+            WitnessHintDesc::TapScript {
+                signatures,
+                script_len,
+                depth,
+            } => WitnessHint::TapScript {
+                signatures,
+                script_len,
+                depth,
+            },
+        }
+    }
+}
+
+impl From<PolicyDesc> for Policy {
+    fn from(value: PolicyDesc) -> Self {
+        match value {
+            PolicyDesc::CoreV28 => Policy::CORE_V28,
+            PolicyDesc::CoreV29_1 => Policy::CORE_V29_1,
+            PolicyDesc::CoreV30 => Policy::CORE_V30,
+            PolicyDesc::Custom {
+                dust_relay_sat_per_kvb,
+                max_datacarrier_bytes,
+                max_standard_weight,
+                min_relay_sat_per_kvb,
+                multiple_op_returns,
+            } => Policy {
+                dust_relay_fee: FeeRate::from_sat_per_kvb(dust_relay_sat_per_kvb),
+                max_datacarrier_bytes,
+                multiple_op_returns,
+                max_standard_weight,
+                min_relay_fee: FeeRate::from_sat_per_kvb(min_relay_sat_per_kvb),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::bech32::{segwit, Hrp};
+    use bitcoin::psbt::Psbt;
+    use std::str::FromStr;
+    use zeldhash_miner_core::create_zeld_distribution_op_return;
+
+    fn sample_addresses() -> (String, String) {
+        let hrp = Hrp::parse("bc").expect("mainnet hrp");
+        let user_program = [0x33u8; 20];
+        let change_program = [0x22u8; 20];
+        let user = segwit::encode(hrp, segwit::VERSION_0, &user_program).unwrap();
+        let change = segwit::encode(hrp, segwit::VERSION_0, &change_program).unwrap();
+        (user, change)
+    }
+
+    fn sample_input_desc(amount: u64) -> TxInputDesc {
+        let mut spk = vec![0x00, 0x14];
+        spk.extend_from_slice(&[0x22u8; 20]);
+
+        TxInputDesc {
+            txid: "11".repeat(32),
+            vout: 0,
+            script_pubkey: hex::encode(spk),
+            amount,
+            sequence: Some(zeldhash_miner_core::tx::DEFAULT_SEQUENCE),
+            witness: None,
+            redeem_script: None,
+            non_witness_utxo: None,
+            sighash_type: None,
+            bip32_derivations: Vec::new(),
+            tap_internal_key: None,
+            tap_key_derivations: Vec::new(),
         }
     }
 
@@ -932,16 +2486,28 @@ mod tests {
             TxOutputDesc {
                 address: user_addr.to_string(),
                 amount: Some(60_000),
-                change: false,
+                ..Default::default()
             },
             TxOutputDesc {
                 address: change_addr.to_string(),
-                amount: None,
                 change: true,
+                ..Default::default()
             },
         ]
     }
 
+    /// One input paying 60,000 sats plus change, searched from nonce 0 in batches of 4.
+    fn sample_params() -> MineParams {
+        let (user_addr, change_addr) = sample_addresses();
+        MineParams {
+            inputs: vec![sample_input_desc(120_000)],
+            outputs: sample_outputs(&user_addr, &change_addr),
+            start_nonce: Some(0),
+            batch_size: Some(4),
+            ..Default::default()
+        }
+    }
+
     fn miner_opts(use_gpu: bool) -> ZeldMinerOptions {
         ZeldMinerOptions {
             network: NetworkOption::Mainnet,
@@ -949,20 +2515,14 @@ mod tests {
             use_gpu,
             worker_threads: 1,
             sats_per_vbyte: 2,
+            sat_per_kvb: None,
+            policy: PolicyDesc::default(),
         }
     }
 
     fn mine_once(use_gpu: bool) -> MineResult {
         let miner = ZeldMiner::new(miner_opts(use_gpu)).expect("miner builds");
-        let (user_addr, change_addr) = sample_addresses();
-        let params = MineParams {
-            inputs: vec![sample_input_desc(120_000)],
-            outputs: sample_outputs(&user_addr, &change_addr),
-            target_zeros: 0,
-            start_nonce: Some(0),
-            batch_size: Some(4),
-            distribution: None,
-        };
+        let params = sample_params();
 
         let mut progress_called = false;
         let mut found_called = false;
@@ -983,6 +2543,55 @@ mod tests {
         result
     }
 
+    #[test]
+    fn shared_hit_prefers_lowest_nonce_when_deterministic() {
+        let shared = SharedHit::new(true);
+        assert!(!shared.should_stop(0));
+        shared.offer((40, [1u8; 32]));
+        shared.offer((12, [2u8; 32]));
+        shared.offer((30, [3u8; 32]));
+        assert!(!shared.should_stop(12));
+        assert!(shared.should_stop(13));
+        assert_eq!(shared.take(), Some((12, [2u8; 32])));
+
+        let first_wins = SharedHit::new(false);
+        first_wins.offer((40, [1u8; 32]));
+        first_wins.offer((12, [2u8; 32]));
+        assert!(first_wins.should_stop(0));
+        assert_eq!(first_wins.take(), Some((40, [1u8; 32])));
+    }
+
+    #[test]
+    fn deterministic_parallel_cpu_matches_sequential_scan() {
+        let params = |deterministic| MineParams {
+            target_zeros: 2,
+            batch_size: Some(8_192),
+            deterministic,
+            ..sample_params()
+        };
+        let no_progress: Option<fn(ProgressStats)> = None;
+        let no_found: Option<fn(&MineResult)> = None;
+
+        let sequential = ZeldMiner::new(miner_opts(false))
+            .expect("miner builds")
+            .mine_transaction(params(false), no_progress, no_found)
+            .expect("sequential run finds a hit");
+
+        let parallel = ZeldMiner::new(ZeldMinerOptions {
+            worker_threads: 4,
+            ..miner_opts(false)
+        })
+        .expect("miner builds");
+        for _ in 0..5 {
+            let result = parallel
+                .mine_transaction(params(true), no_progress, no_found)
+                .expect("parallel run finds a hit");
+            assert_eq!(result.nonce, sequential.nonce);
+            assert_eq!(result.txid, sequential.txid);
+            assert_eq!(result.psbt, sequential.psbt);
+        }
+    }
+
     #[test]
     fn cpu_path_mines_and_builds_psbt() {
         let result = mine_once(false);
@@ -1009,23 +2618,21 @@ mod tests {
                 TxOutputDesc {
                     address: user_addr,
                     amount: Some(60_000),
-                    change: false,
+                    ..Default::default()
                 },
                 TxOutputDesc {
                     address: alt_addr,
                     amount: Some(30_000),
-                    change: false,
+                    ..Default::default()
                 },
                 TxOutputDesc {
                     address: change_addr,
-                    amount: None,
                     change: true,
+                    ..Default::default()
                 },
             ],
-            target_zeros: 0,
-            start_nonce: Some(0),
-            batch_size: Some(4),
             distribution: Some(distribution.clone()),
+            ..sample_params()
         };
 
         let mut progress_called = false;
@@ -1078,19 +2685,611 @@ mod tests {
         assert_eq!(txid, result.txid);
     }
 
+    #[cfg(feature = "gpu")]
+    #[test]
+    fn saturated_gpu_chunks_still_return_the_lowest_hit() {
+        let txid = |zero_bytes: usize| {
+            let mut txid = [0xffu8; 32];
+            txid[32 - zero_bytes..].fill(0);
+            txid
+        };
+        let strict = ZeroUnit::Bits.target(16);
+        let candidate = BestCandidate::new(ZeroUnit::Bits, 8);
+        let control = MiningControl::default();
+        let job = SegmentJob {
+            prefix: &[],
+            suffix: &[],
+            pattern: &strict,
+            use_cbor_nonce: false,
+            control: &control,
+            candidate: Some(&candidate),
+            record_stats: false,
+            progress: None,
+        };
+        let batch = MiningBatch {
+            tx_prefix: &[],
+            tx_suffix: &[],
+            start_nonce: 0,
+            batch_size: 100_000,
+            pattern: &strict,
+            use_cbor_nonce: false,
+        };
+
+        // Near misses fill every loose slot but one, which holds a hit above the
+        // lowest one at nonce 40.
+        let mut dispatches = 0;
+        let hits = gpu_chunk_hits(&job, &batch, &mut None, |dispatched| {
+            dispatches += 1;
+            if std::ptr::eq(dispatched.pattern, &strict) {
+                return Ok(vec![
+                    GpuMineResult {
+                        nonce: 40,
+                        txid: txid(2),
+                    },
+                    GpuMineResult {
+                        nonce: 10_000,
+                        txid: txid(2),
+                    },
+                ]);
+            }
+            let mut results: Vec<_> = (0..GPU_MAX_RESULTS as u64 - 1)
+                .map(|i| GpuMineResult {
+                    nonce: if i == 0 { 0 } else { 100 + i },
+                    txid: txid(1),
+                })
+                .collect();
+            results.push(GpuMineResult {
+                nonce: 10_000,
+                txid: txid(2),
+            });
+            Ok(results)
+        })
+        .expect("chunk dispatches");
+
+        assert_eq!(dispatches, 2);
+        assert_eq!(select_best_gpu_result(&hits).map(|hit| hit.nonce), Some(40));
+        assert_eq!(candidate.take(), Some(0));
+    }
+
+    #[test]
+    fn fractional_fee_rate_rounds_fee_up() {
+        let fee_at = |opts: ZeldMinerOptions| {
+            let miner = ZeldMiner::new(opts).expect("miner builds");
+            let mut params = budget_params();
+            params.target_zeros = 0;
+            let result = miner
+                .mine_transaction(params, None::<fn(ProgressStats)>, None::<fn(&MineResult)>)
+                .expect("mining succeeds");
+            let psbt = Psbt::from_str(&result.psbt).expect("psbt parses");
+            let spent: u64 = psbt
+                .unsigned_tx
+                .output
+                .iter()
+                .map(|o| o.value.to_sat())
+                .sum();
+            120_000 - spent
+        };
+
+        // At 1 sat/vB the fee equals the estimated vsize.
+        let vsize = fee_at(ZeldMinerOptions {
+            sats_per_vbyte: 1,
+            ..miner_opts(false)
+        });
+        let opts = ZeldMinerOptions {
+            sat_per_kvb: Some(1_500),
+            ..miner_opts(false)
+        };
+        assert_eq!(
+            ZeldMiner::new(opts.clone()).unwrap().fee_rate(),
+            "1.5".parse().unwrap()
+        );
+        assert_eq!(fee_at(opts), (vsize * 1_500).div_ceil(1_000));
+
+        let err = ZeldMiner::new(ZeldMinerOptions {
+            sat_per_kvb: Some(0),
+            ..miner_opts(false)
+        })
+        .err()
+        .expect("zero rate is rejected");
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn fee_modes_shape_psbt_outputs() {
+        let miner = ZeldMiner::new(miner_opts(false)).expect("miner builds");
+        let psbt_values = |params: MineParams| {
+            let result = miner
+                .mine_transaction(params, None::<fn(ProgressStats)>, None::<fn(&MineResult)>)
+                .expect("mining succeeds");
+            let psbt = Psbt::from_str(&result.psbt).expect("psbt parses");
+            psbt.unsigned_tx
+                .output
+                .iter()
+                .map(|o| o.value.to_sat())
+                .collect::<Vec<_>>()
+        };
+        let mut params = budget_params();
+        params.target_zeros = 0;
+
+        // Absolute fee: change gets everything but the payment and the fee.
+        let mut absolute = params.clone();
+        absolute.absolute_fee = Some(1_000);
+        assert_eq!(psbt_values(absolute), vec![60_000, 59_000, 0]);
+
+        // Send-max: the single remaining output sweeps the inputs minus the fee.
+        let mut send_max = params.clone();
+        send_max.outputs.truncate(1);
+        send_max.outputs[0].amount = None;
+        send_max.absolute_fee = Some(700);
+        send_max.fee_mode = FeeModeDesc::SendMax(0);
+        assert_eq!(psbt_values(send_max), vec![119_300, 0]);
+
+        // Subtract-fee: the payment pays the fee, the change keeps the rest.
+        let mut subtract = params.clone();
+        subtract.absolute_fee = Some(500);
+        subtract.fee_mode = FeeModeDesc::SubtractFromOutputs(vec![0]);
+        assert_eq!(psbt_values(subtract), vec![59_500, 60_000, 0]);
+
+        // Zero-fee parent: a zero-value anchor leaves the fee to a CPFP child.
+        let mut anchored = params;
+        anchored.absolute_fee = Some(0);
+        anchored.outputs.insert(
+            1,
+            TxOutputDesc {
+                amount: Some(0),
+                kind: OutputKindDesc::Anchor,
+                ..Default::default()
+            },
+        );
+        assert_eq!(psbt_values(anchored), vec![60_000, 0, 60_000, 0]);
+    }
+
+    #[test]
+    fn policy_violations_map_to_error_codes() {
+        let mine = |opts: ZeldMinerOptions, params: MineParams| {
+            ZeldMiner::new(opts)
+                .expect("miner builds")
+                .mine_transaction(params, None::<fn(ProgressStats)>, None::<fn(&MineResult)>)
+                .expect_err("plan is rejected")
+        };
+        let mut params = budget_params();
+        params.target_zeros = 0;
+
+        let mut dusty = params.clone();
+        dusty.outputs[0].amount = Some(293);
+        let err = mine(miner_opts(false), dusty);
+        assert_eq!(err.code, ZeldMinerErrorCode::DustOutput);
+
+        // 2 sat/vB is below a 5 sat/vB relay floor.
+        let strict = ZeldMinerOptions {
+            policy: PolicyDesc::Custom {
+                dust_relay_sat_per_kvb: 3_000,
+                max_datacarrier_bytes: 83,
+                max_standard_weight: 400_000,
+                min_relay_sat_per_kvb: 5_000,
+                multiple_op_returns: false,
+            },
+            ..miner_opts(false)
+        };
+        let err = mine(strict, params.clone());
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+        assert!(err.message.contains("minimum relay fee"));
+
+        // Raw scripts must be hex of a standard output type.
+        let mut scripted = params;
+        scripted.outputs[0].kind = OutputKindDesc::Script("51".to_string());
+        let err = mine(miner_opts(false), scripted.clone());
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+        assert!(err.message.contains("not a standard output type"));
+        scripted.outputs[0].kind = OutputKindDesc::Script("zz".to_string());
+        let err = mine(miner_opts(false), scripted);
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn test_networks_are_recorded_in_the_psbt() {
+        let hrp = Hrp::parse("tb").expect("testnet hrp");
+        let user = segwit::encode(hrp, segwit::VERSION_0, &[0x33u8; 20]).unwrap();
+        let change = segwit::encode(hrp, segwit::VERSION_0, &[0x22u8; 20]).unwrap();
+        let mut params = budget_params();
+        params.target_zeros = 0;
+        params.outputs = sample_outputs(&user, &change);
+
+        for (network, name) in [
+            (NetworkOption::Signet, "signet"),
+            (NetworkOption::Testnet4, "testnet4"),
+        ] {
+            let miner = ZeldMiner::new(ZeldMinerOptions {
+                network,
+                ..miner_opts(false)
+            })
+            .expect("miner builds");
+            let result = miner
+                .mine_transaction(
+                    params.clone(),
+                    None::<fn(ProgressStats)>,
+                    None::<fn(&MineResult)>,
+                )
+                .expect("mining succeeds");
+            let psbt = Psbt::from_str(&result.psbt).expect("psbt parses");
+            let (key, value) = psbt.proprietary.iter().next().expect("network recorded");
+            assert_eq!(key.prefix, zeldhash_miner_core::PSBT_PROPRIETARY_IDENTIFIER);
+            assert_eq!(value, name.as_bytes());
+        }
+    }
+
+    #[test]
+    fn version_and_lock_time_reach_the_psbt() {
+        let miner = ZeldMiner::new(miner_opts(false)).expect("miner builds");
+        let mut params = budget_params();
+        params.target_zeros = 0;
+        params.version = Some(3);
+        params.lock_time = LockTimeDesc::AntiFeeSniping {
+            tip_height: 850_000,
+        };
+        let result = miner
+            .mine_transaction(
+                params.clone(),
+                None::<fn(ProgressStats)>,
+                None::<fn(&MineResult)>,
+            )
+            .expect("mining succeeds");
+        let psbt = Psbt::from_str(&result.psbt).expect("psbt parses");
+        assert_eq!(psbt.unsigned_tx.version.0, 3);
+        assert_eq!(psbt.unsigned_tx.lock_time.to_consensus_u32(), 850_000);
+        assert_eq!(psbt.unsigned_tx.compute_txid().to_string(), result.txid);
+
+        params.version = Some(4);
+        let err = miner
+            .mine_transaction(params, None::<fn(ProgressStats)>, None::<fn(&MineResult)>)
+            .expect_err("version 4 is not standard");
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn signer_metadata_reaches_the_psbt() {
+        const GENERATOR: &str =
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let miner = ZeldMiner::new(miner_opts(false)).expect("miner builds");
+        let mut params = budget_params();
+        params.target_zeros = 0;
+        params.inputs[0].sighash_type = Some(0x01);
+        params.inputs[0].tap_internal_key = Some(GENERATOR[2..].to_string());
+        let change = params.outputs.iter_mut().find(|o| o.change).unwrap();
+        change.bip32_derivations = vec![Bip32DerivationDesc {
+            pubkey: GENERATOR.to_string(),
+            fingerprint: "d34db33f".to_string(),
+            path: "m/84'/0'/0'/1/0".to_string(),
+        }];
+
+        let result = miner
+            .mine_transaction(
+                params.clone(),
+                None::<fn(ProgressStats)>,
+                None::<fn(&MineResult)>,
+            )
+            .expect("mining succeeds");
+        let psbt = Psbt::from_str(&result.psbt).expect("psbt parses");
+        assert_eq!(psbt.inputs[0].sighash_type.map(|s| s.to_u32()), Some(0x01));
+        assert!(psbt.inputs[0].tap_internal_key.is_some());
+        let change_index = psbt
+            .outputs
+            .iter()
+            .position(|o| !o.bip32_derivation.is_empty())
+            .expect("change carries its key origin");
+        let (_, (fingerprint, path)) = psbt.outputs[change_index]
+            .bip32_derivation
+            .iter()
+            .next()
+            .unwrap();
+        assert_eq!(fingerprint.to_string(), "d34db33f");
+        assert_eq!(path.to_string(), "84'/0'/0'/1/0");
+
+        params.outputs[0].bip32_derivations = vec![Bip32DerivationDesc {
+            pubkey: GENERATOR.to_string(),
+            fingerprint: "d34db33f".to_string(),
+            path: "m/84'/x".to_string(),
+        }];
+        let err = miner
+            .mine_transaction(params, None::<fn(ProgressStats)>, None::<fn(&MineResult)>)
+            .expect_err("bad path");
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn mines_a_wallet_built_psbt() {
+        use bitcoin::{
+            absolute, bip32, psbt, secp256k1::PublicKey, transaction, Amount, OutPoint, ScriptBuf,
+            Sequence, Transaction, TxIn, TxOut, Txid, Witness,
+        };
+
+        let miner = ZeldMiner::new(miner_opts(false)).expect("miner builds");
+        let input = sample_input_desc(120_000);
+        let (user_addr, change_addr) = sample_addresses();
+        let script = |addr: &str| {
+            let (_, version, program) = segwit::decode(addr).unwrap();
+            let mut script = vec![version.to_u8(), program.len() as u8];
+            script.extend(program);
+            ScriptBuf::from_bytes(script)
+        };
+        let unsigned = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(Txid::from_str(&input.txid).unwrap(), 0),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            }],
+            output: vec![
+                TxOut {
+                    value: Amount::from_sat(60_000),
+                    script_pubkey: script(&user_addr),
+                },
+                TxOut {
+                    value: Amount::from_sat(59_859),
+                    script_pubkey: script(&change_addr),
+                },
+            ],
+        };
+        let mut wallet_psbt = Psbt::from_unsigned_tx(unsigned).unwrap();
+        wallet_psbt.inputs[0].witness_utxo = Some(TxOut {
+            value: Amount::from_sat(input.amount),
+            script_pubkey: ScriptBuf::from_bytes(hex::decode(&input.script_pubkey).unwrap()),
+        });
+        let proprietary = psbt::raw::ProprietaryKey {
+            prefix: b"wallet".to_vec(),
+            subtype: 0x01,
+            key: Vec::new(),
+        };
+        wallet_psbt
+            .proprietary
+            .insert(proprietary.clone(), b"label".to_vec());
+        let origin = (
+            bip32::Fingerprint::from([0xd3, 0x4d, 0xb3, 0x3f]),
+            bip32::DerivationPath::from_str("m/84'/0'/0'/1/0").unwrap(),
+        );
+        let generator = PublicKey::from_str(
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        )
+        .unwrap();
+        wallet_psbt.outputs[1]
+            .bip32_derivation
+            .insert(generator, origin);
+        let psbt_desc = PsbtDesc {
+            psbt: wallet_psbt.to_string(),
+            change_index: None,
+            witness_hints: Vec::new(),
+        };
+
+        let mut params = budget_params();
+        params.inputs.clear();
+        params.outputs.clear();
+        params.target_zeros = 1;
+        let result = miner
+            .mine_psbt(
+                psbt_desc.clone(),
+                params.clone(),
+                None::<fn(ProgressStats)>,
+                None::<fn(&MineResult)>,
+            )
+            .expect("mining succeeds");
+        let mined = Psbt::from_str(&result.psbt).expect("psbt parses");
+        assert_eq!(mined.unsigned_tx.compute_txid().to_string(), result.txid);
+        assert!(result.txid.starts_with('0'));
+        assert_eq!(
+            mined.proprietary.get(&proprietary),
+            Some(&b"label".to_vec())
+        );
+        let outputs = &mined.unsigned_tx.output;
+        assert_eq!(outputs.len(), 3);
+        assert_eq!(outputs[0], wallet_psbt.unsigned_tx.output[0]);
+        assert!(outputs[2].script_pubkey.is_op_return());
+        assert!(!mined.outputs[1].bip32_derivation.is_empty());
+        // The change pays for the OP_RETURN at the wallet's 1 sat/vB.
+        let op_return_vsize = outputs[2].size() as u64;
+        assert!(outputs[1].value.to_sat() <= 59_859 - op_return_vsize);
+        assert!(outputs[1].value.to_sat() >= 59_859 - op_return_vsize - 2);
+
+        let mut unmarked = wallet_psbt.clone();
+        unmarked.outputs[1].bip32_derivation.clear();
+        let err = miner
+            .mine_psbt(
+                PsbtDesc {
+                    psbt: unmarked.to_string(),
+                    ..psbt_desc.clone()
+                },
+                params.clone(),
+                None::<fn(ProgressStats)>,
+                None::<fn(&MineResult)>,
+            )
+            .expect_err("change is ambiguous");
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+
+        let mut overflowing = wallet_psbt.clone();
+        overflowing.unsigned_tx.output[0].value = Amount::MAX;
+        let err = miner
+            .mine_psbt(
+                PsbtDesc {
+                    psbt: overflowing.to_string(),
+                    ..psbt_desc.clone()
+                },
+                params.clone(),
+                None::<fn(ProgressStats)>,
+                None::<fn(&MineResult)>,
+            )
+            .expect_err("output amounts overflow");
+        assert_eq!(err.code, ZeldMinerErrorCode::InsufficientFunds);
+
+        let mut legacy = wallet_psbt;
+        legacy.inputs[0]
+            .witness_utxo
+            .as_mut()
+            .unwrap()
+            .script_pubkey =
+            ScriptBuf::from_bytes(hex::decode(format!("76a914{}88ac", "44".repeat(20))).unwrap());
+        let err = miner
+            .mine_psbt(
+                PsbtDesc {
+                    psbt: legacy.to_string(),
+                    witness_hints: vec![Some(WitnessHintDesc::Size(0))],
+                    ..psbt_desc
+                },
+                params,
+                None::<fn(ProgressStats)>,
+                None::<fn(&MineResult)>,
+            )
+            .expect_err("legacy inputs cannot be mined");
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn verifies_signed_transactions_against_the_result() {
+        use bitcoin::{consensus, Witness};
+
+        let result = mine_once(false);
+        let mut signed = Psbt::from_str(&result.psbt).expect("psbt parses");
+        signed.inputs[0].final_script_witness =
+            Some(Witness::from_slice(&[vec![0x30; 71], vec![0x02; 33]]));
+        let tx = signed.clone().extract_tx_unchecked_fee_rate();
+
+        let verification = result
+            .verify_signed(&signed.to_string())
+            .expect("signed psbt verifies");
+        assert!(verification.txid_matches && verification.payload_intact);
+        assert!(verification.mismatches.is_empty());
+        assert_eq!(verification.txid, result.txid);
+        assert_eq!(verification.vsize, tx.vsize());
+        // The fee was sized for estimated witnesses at 2 sat/vB.
+        let fee = 120_000 - tx.output.iter().map(|o| o.value.to_sat()).sum::<u64>();
+        assert_eq!(verification.fee, Some(fee));
+        assert!(verification.fee_rate_sat_per_kvb.unwrap() >= 2_000);
+
+        let mut reordered = tx.clone();
+        reordered.output.swap(0, 1);
+        let verification = result
+            .verify_signed(&hex::encode(consensus::encode::serialize(&reordered)))
+            .expect("raw transaction verifies");
+        assert!(!verification.txid_matches);
+        assert_eq!(
+            verification.mismatches,
+            vec![
+                TxMismatchDesc::Output { index: 0 },
+                TxMismatchDesc::Output { index: 1 }
+            ]
+        );
+
+        let err = result.verify_signed("00").unwrap_err();
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+    }
+
+    #[cfg(feature = "signer")]
+    #[test]
+    fn signs_results_without_changing_the_txid() {
+        use bitcoin::{consensus, key::CompressedPublicKey, secp256k1, Transaction};
+
+        let key = [0x11u8; 32];
+        let secp = secp256k1::Secp256k1::new();
+        let pubkey = CompressedPublicKey(secp256k1::PublicKey::from_secret_key(
+            &secp,
+            &secp256k1::SecretKey::from_slice(&key).unwrap(),
+        ));
+        let miner = ZeldMiner::new(miner_opts(false)).expect("miner builds");
+        let mut params = budget_params();
+        params.target_zeros = 0;
+        params.inputs[0].script_pubkey =
+            bitcoin::ScriptBuf::new_p2wpkh(&pubkey.wpubkey_hash()).to_hex_string();
+        let result = miner
+            .mine_transaction(params, None::<fn(ProgressStats)>, None::<fn(&MineResult)>)
+            .expect("mining succeeds");
+
+        let signed = miner
+            .sign_result(&result, &[hex::encode(key)])
+            .expect("input signs");
+        assert_eq!(signed.txid, result.txid);
+        let tx: Transaction =
+            consensus::deserialize(&hex::decode(&signed.tx_hex).unwrap()).expect("tx decodes");
+        assert_eq!(tx.compute_txid().to_string(), result.txid);
+        assert_eq!(tx.input[0].witness.len(), 2);
+        let finalized = Psbt::from_str(&signed.psbt).expect("finalized psbt parses");
+        assert_eq!(finalized.extract_tx_unchecked_fee_rate(), tx);
+        let verification = result.verify_signed(&signed.tx_hex).expect("tx verifies");
+        assert!(verification.txid_matches && verification.payload_intact);
+
+        let err = miner
+            .sign_result(&result, &["22".repeat(32)])
+            .expect_err("wrong key");
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+        let err = miner
+            .sign_result(&result, &[hex::encode(key), hex::encode(key)])
+            .expect_err("one key per input");
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+        let regtest = ZeldMiner::new(ZeldMinerOptions {
+            network: NetworkOption::Regtest,
+            ..miner_opts(false)
+        })
+        .expect("miner builds");
+        let err = regtest
+            .sign_result(&result, &[hex::encode(key)])
+            .expect_err("result was mined for mainnet");
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn unknown_inputs_need_witness_hints() {
+        let miner = ZeldMiner::new(miner_opts(false)).expect("miner builds");
+        let fee_of = |params: MineParams| {
+            miner
+                .mine_transaction(params, None::<fn(ProgressStats)>, None::<fn(&MineResult)>)
+                .map(|result| {
+                    let psbt = Psbt::from_str(&result.psbt).expect("psbt parses");
+                    let spent: u64 = psbt
+                        .unsigned_tx
+                        .output
+                        .iter()
+                        .map(|o| o.value.to_sat())
+                        .sum();
+                    120_000 - spent
+                })
+        };
+        let mut params = budget_params();
+        params.target_zeros = 0;
+        let p2wpkh_fee = fee_of(params.clone()).expect("P2WPKH input plans");
+
+        params.inputs[0].script_pubkey = format!("0020{}", "44".repeat(32));
+        let err = fee_of(params.clone()).expect_err("P2WSH input needs a hint");
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+
+        // 2-of-3 multisig: 254 witness bytes against 108, 36 more vbytes at 2 sat/vB.
+        params.inputs[0].witness = Some(WitnessHintDesc::Multisig {
+            required: 2,
+            keys: 3,
+        });
+        let multisig_fee = fee_of(params.clone()).expect("hinted input plans");
+        assert_eq!(multisig_fee - p2wpkh_fee, 72);
+
+        // A legacy input is signed in its scriptSig, whatever its hint says.
+        params.inputs[0].script_pubkey = format!("76a914{}88ac", "44".repeat(20));
+        params.inputs[0].witness = Some(WitnessHintDesc::Size(0));
+        let err = fee_of(params).expect_err("P2PKH input changes the txid when signed");
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+    }
+
     #[test]
     fn mines_successfully_when_change_is_dust() {
         // Craft amounts so that change ends up below dust limit
-        // P2WPKH dust limit is 310 sats
+        // P2WPKH dust limit is 294 sats
         // For a tx with 1 input, 2 outputs (user + change) + OP_RETURN:
         // vsize ~ 129 vbytes at 2 sats/vB = 258 sats fee
-        // With input=10000, user=9500, fee~258, change would be ~242 (below dust limit of 310)
+        // With input=10000, user=9500, fee~258, change would be ~242 (below dust limit of 294)
         let miner = ZeldMiner::new(ZeldMinerOptions {
             network: NetworkOption::Mainnet,
             batch_size: 4,
             use_gpu: false,
             worker_threads: 1,
             sats_per_vbyte: 2,
+            sat_per_kvb: None,
+            policy: PolicyDesc::default(),
         })
         .expect("miner builds");
 
@@ -1102,18 +3301,15 @@ mod tests {
                 TxOutputDesc {
                     address: user_addr,
                     amount: Some(9_500), // Leave ~500 sats for fee + dust change
-                    change: false,
+                    ..Default::default()
                 },
                 TxOutputDesc {
                     address: change_addr,
-                    amount: None,
                     change: true,
+                    ..Default::default()
                 },
             ],
-            target_zeros: 0,
-            start_nonce: Some(0),
-            batch_size: Some(4),
-            distribution: None,
+            ..sample_params()
         };
 
         let result = miner
@@ -1132,4 +3328,367 @@ mod tests {
         let txid = psbt.unsigned_tx.compute_txid().to_string();
         assert_eq!(txid, result.txid);
     }
+
+    #[test]
+    fn unbounded_search_crosses_nonce_length_boundaries() {
+        let miner = ZeldMiner::new(miner_opts(false)).expect("miner builds");
+        let params = MineParams {
+            target_zeros: 2,
+            start_nonce: Some(250),
+            search_mode: SearchMode::Unbounded,
+            ..sample_params()
+        };
+
+        let mut progress_events = 0usize;
+        let result = miner
+            .mine_transaction(
+                params,
+                Some(|_: ProgressStats| progress_events += 1),
+                None::<fn(&MineResult)>,
+            )
+            .expect("unbounded search keeps going until a hit");
+
+        // The first hit for this template sits past the 1-byte/2-byte boundary.
+        assert!(result.nonce > 0xff);
+        assert!(result.txid.starts_with("00"));
+        assert!(
+            progress_events > 1,
+            "progress should be reported per chunk, not once per run"
+        );
+
+        let psbt = Psbt::from_str(&result.psbt).expect("psbt parses");
+        assert_eq!(psbt.unsigned_tx.compute_txid().to_string(), result.txid);
+    }
+
+    #[test]
+    fn unbounded_search_stops_at_end_of_nonce_space() {
+        let miner = ZeldMiner::new(miner_opts(false)).expect("miner builds");
+        let params = MineParams {
+            target_zeros: MAX_TARGET_ZEROS,
+            start_nonce: Some(u64::MAX - 5),
+            search_mode: SearchMode::Unbounded,
+            ..sample_params()
+        };
+
+        let err = miner
+            .mine_transaction(params, None::<fn(ProgressStats)>, None::<fn(&MineResult)>)
+            .expect_err("nothing can match a 32-zero target in six nonces");
+        assert_eq!(err.code, ZeldMinerErrorCode::NoMatchingNonce);
+    }
+
+    #[test]
+    fn mines_vanity_pattern_instead_of_zero_target() {
+        let miner = ZeldMiner::new(ZeldMinerOptions {
+            worker_threads: 4,
+            ..miner_opts(false)
+        })
+        .expect("miner builds");
+        let params = MineParams {
+            target_zeros: MAX_TARGET_ZEROS, // ignored when a pattern is set
+            batch_size: Some(1_000),
+            search_mode: SearchMode::Unbounded,
+            deterministic: true,
+            pattern: Some(TxidPatternDesc::AnyOf(vec![
+                TxidPatternDesc::Prefix("CA".into()),
+                TxidPatternDesc::Suffix("fe".into()),
+            ])),
+            ..sample_params()
+        };
+
+        let result = miner
+            .mine_transaction(params, None::<fn(ProgressStats)>, None::<fn(&MineResult)>)
+            .expect("a one-byte pattern is found quickly");
+        assert!(result.txid.starts_with("ca") || result.txid.ends_with("fe"));
+
+        let psbt = Psbt::from_str(&result.psbt).expect("psbt parses");
+        assert_eq!(psbt.unsigned_tx.compute_txid().to_string(), result.txid);
+    }
+
+    #[test]
+    fn mines_bit_granular_target() {
+        let miner = ZeldMiner::new(miner_opts(false)).expect("miner builds");
+        let mut params = MineParams {
+            target_zeros: MAX_TARGET_ZEROS, // ignored when target_bits is set
+            batch_size: Some(1_000),
+            search_mode: SearchMode::Unbounded,
+            deterministic: true,
+            target_bits: Some(6),
+            ..sample_params()
+        };
+
+        let result = miner
+            .mine_transaction(
+                params.clone(),
+                None::<fn(ProgressStats)>,
+                None::<fn(&MineResult)>,
+            )
+            .expect("six zero bits are found quickly");
+        let first_byte = u8::from_str_radix(&result.txid[..2], 16).unwrap();
+        assert!(first_byte.leading_zeros() >= 6, "txid {}", result.txid);
+
+        params.target_bits = Some(MAX_TARGET_BITS + 1);
+        let err = miner
+            .mine_transaction(params, None::<fn(ProgressStats)>, None::<fn(&MineResult)>)
+            .expect_err("target_bits above 128 is rejected");
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+    }
+
+    fn continue_params(continue_mining: ContinueMining) -> MineParams {
+        MineParams {
+            target_zeros: 1,
+            continue_mining: Some(continue_mining),
+            ..budget_params()
+        }
+    }
+
+    #[test]
+    fn continue_mining_reports_strictly_better_txids() {
+        let miner = ZeldMiner::new(ZeldMinerOptions {
+            worker_threads: 4,
+            ..miner_opts(false)
+        })
+        .expect("miner builds");
+        let params = continue_params(ContinueMining {
+            max_target: Some(3),
+        });
+
+        let mut found = Vec::new();
+        let result = miner
+            .mine_transaction(
+                params,
+                None::<fn(ProgressStats)>,
+                Some(|r: &MineResult| found.push(r.clone())),
+            )
+            .expect("three zeros are reached");
+
+        let zeros: Vec<usize> = found
+            .iter()
+            .map(|r| r.txid.len() - r.txid.trim_start_matches('0').len())
+            .collect();
+        assert!(zeros[0] >= 1);
+        assert!(zeros.windows(2).all(|w| w[0] < w[1]), "zeros {zeros:?}");
+        assert!(*zeros.last().unwrap() >= 3);
+        assert!(found.windows(2).all(|w| w[0].nonce < w[1].nonce));
+
+        let last = found.last().unwrap();
+        assert_eq!(result.txid, last.txid);
+        let psbt = Psbt::from_str(&result.psbt).expect("psbt parses");
+        assert_eq!(psbt.unsigned_tx.compute_txid().to_string(), result.txid);
+    }
+
+    #[test]
+    fn continue_mining_returns_best_at_deadline() {
+        let miner = ZeldMiner::new(miner_opts(false)).expect("miner builds");
+        let mut params = continue_params(ContinueMining { max_target: None });
+        params.target_zeros = 0;
+        params.deadline_ms = Some(200);
+
+        let mut found = Vec::new();
+        let started = Instant::now();
+        let result = miner
+            .mine_transaction(
+                params,
+                None::<fn(ProgressStats)>,
+                Some(|r: &MineResult| found.push(r.txid.clone())),
+            )
+            .expect("the best txid is returned when the deadline expires");
+
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert_eq!(found.last(), Some(&result.txid));
+    }
+
+    #[test]
+    fn continue_mining_rejects_patterns_and_low_caps() {
+        let miner = ZeldMiner::new(miner_opts(false)).expect("miner builds");
+
+        let mut params = continue_params(ContinueMining::default());
+        params.pattern = Some(TxidPatternDesc::Prefix("ca".into()));
+        let err = miner
+            .mine_transaction(params, None::<fn(ProgressStats)>, None::<fn(&MineResult)>)
+            .expect_err("patterns have no zero count to improve on");
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+
+        let mut params = continue_params(ContinueMining {
+            max_target: Some(2),
+        });
+        params.target_zeros = 3;
+        let err = miner
+            .mine_transaction(params, None::<fn(ProgressStats)>, None::<fn(&MineResult)>)
+            .expect_err("cap below the target");
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+    }
+
+    fn budget_params() -> MineParams {
+        MineParams {
+            target_zeros: MAX_TARGET_ZEROS,
+            batch_size: Some(1_000),
+            search_mode: SearchMode::Unbounded,
+            ..sample_params()
+        }
+    }
+
+    fn count_txid_zeros(txid: &str) -> u16 {
+        (txid.len() - txid.trim_start_matches('0').len()) as u16
+    }
+
+    #[test]
+    fn deadline_returns_best_effort_candidate() {
+        let miner = ZeldMiner::new(ZeldMinerOptions {
+            worker_threads: 4,
+            ..miner_opts(false)
+        })
+        .expect("miner builds");
+        let mut params = budget_params();
+        params.deadline_ms = Some(150);
+
+        let mut found = false;
+        let result = miner
+            .mine_transaction(
+                params,
+                None::<fn(ProgressStats)>,
+                Some(|_: &MineResult| found = true),
+            )
+            .expect("the best candidate is returned at the deadline");
+
+        assert!(result.best_effort);
+        assert!(!found, "best-effort results are not reported as hits");
+        assert_eq!(result.leading_zeros, count_txid_zeros(&result.txid));
+        let psbt = Psbt::from_str(&result.psbt).expect("psbt parses");
+        assert_eq!(psbt.unsigned_tx.compute_txid().to_string(), result.txid);
+    }
+
+    #[test]
+    fn attempt_budget_honors_fallback_target() {
+        let miner = ZeldMiner::new(miner_opts(false)).expect("miner builds");
+        let mut params = budget_params();
+        params.max_attempts = Some(2_500);
+        params.fallback_target = Some(1);
+
+        let result = miner
+            .mine_transaction(
+                params.clone(),
+                None::<fn(ProgressStats)>,
+                None::<fn(&MineResult)>,
+            )
+            .expect("2500 attempts reach one zero");
+        assert!(result.best_effort);
+        assert_eq!(result.attempts, 2_500);
+        assert!(result.nonce < 2_500);
+        assert!(result.leading_zeros >= 1);
+        assert_eq!(result.leading_zeros, count_txid_zeros(&result.txid));
+
+        // Ties go to the lowest nonce, so single-threaded runs are reproducible.
+        let again = miner
+            .mine_transaction(
+                params.clone(),
+                None::<fn(ProgressStats)>,
+                None::<fn(&MineResult)>,
+            )
+            .expect("same budget, same result");
+        assert_eq!(again.nonce, result.nonce);
+
+        params.fallback_target = Some(20);
+        let err = miner
+            .mine_transaction(params, None::<fn(ProgressStats)>, None::<fn(&MineResult)>)
+            .expect_err("nothing reaches the fallback target");
+        assert_eq!(err.code, ZeldMinerErrorCode::NoMatchingNonce);
+    }
+
+    #[test]
+    fn progress_reports_best_txid_histogram_and_odds() {
+        let miner = ZeldMiner::new(miner_opts(false)).expect("miner builds");
+        let mut params = budget_params();
+        params.target_zeros = 2;
+        params.batch_size = Some(64);
+
+        let mut reports = Vec::new();
+        let result = miner
+            .mine_transaction(
+                params,
+                Some(|stats: ProgressStats| reports.push(stats)),
+                None::<fn(&MineResult)>,
+            )
+            .expect("two zeros are found quickly");
+
+        let first = &reports[0];
+        assert_eq!(first.expected_remaining_attempts, 256.0);
+
+        for pair in reports.windows(2) {
+            assert!(pair[0].probability_found <= pair[1].probability_found);
+        }
+
+        // The hit is the first txid with two zeros, so it is also the best so far.
+        let last = reports.last().unwrap();
+        assert_eq!(last.best_txid.as_deref(), Some(result.txid.as_str()));
+        assert_eq!(last.best_leading_zeros, Some(result.leading_zeros));
+        assert_eq!(last.zero_histogram.len(), result.leading_zeros as usize + 1);
+        assert_eq!(
+            last.zero_histogram.iter().sum::<u64>() as u128,
+            last.hashes_processed
+        );
+        assert!(last.probability_found > 0.0 && last.probability_found < 1.0);
+    }
+
+    #[test]
+    fn rejects_invalid_pattern() {
+        let miner = ZeldMiner::new(miner_opts(false)).expect("miner builds");
+        let params = MineParams {
+            pattern: Some(TxidPatternDesc::Prefix("zeld".into())),
+            ..sample_params()
+        };
+
+        let err = miner
+            .mine_transaction(params, None::<fn(ProgressStats)>, None::<fn(&MineResult)>)
+            .expect_err("'z' is not a hex digit");
+        assert_eq!(err.code, ZeldMinerErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn nonce_chunks_clamp_to_u64_max() {
+        let mut chunks = NonceChunks::new(u64::MAX - 5, 4, SearchMode::Unbounded).unwrap();
+        assert_eq!(chunks.next_chunk(), Some((u64::MAX - 5, 4)));
+        assert_eq!(chunks.next_chunk(), Some((u64::MAX - 1, 2)));
+        assert_eq!(chunks.next_chunk(), None);
+
+        let mut bounded = NonceChunks::new(10, 4, SearchMode::Bounded).unwrap();
+        assert_eq!(bounded.next_chunk(), Some((10, 4)));
+        assert_eq!(bounded.next_chunk(), None);
+
+        assert!(NonceChunks::new(u64::MAX - 1, 4, SearchMode::Bounded).is_err());
+    }
+
+    #[test]
+    fn progress_interval_reports_inside_a_segment() {
+        let miner = ZeldMiner::new(ZeldMinerOptions {
+            worker_threads: 2,
+            ..miner_opts(false)
+        })
+        .expect("miner builds");
+        let mut params = budget_params();
+        // One 3-byte nonce segment far too large to finish before the deadline.
+        params.start_nonce = Some(65_536);
+        params.batch_size = Some(16_000_000);
+        params.search_mode = SearchMode::Bounded;
+        params.deadline_ms = Some(300);
+        params.progress_interval_ms = Some(40);
+
+        let mut reports = Vec::new();
+        let result = miner
+            .mine_transaction(
+                params,
+                Some(|stats: ProgressStats| reports.push(stats)),
+                None::<fn(&MineResult)>,
+            )
+            .expect("the best candidate is returned at the deadline");
+
+        assert!(result.best_effort);
+        assert!(reports.len() >= 2, "got {} reports", reports.len());
+        assert!(reports
+            .windows(2)
+            .all(|w| w[0].hashes_processed <= w[1].hashes_processed));
+        let last = reports.last().expect("reports were collected");
+        assert!(last.hashes_processed > 0);
+        assert!(last.hashes_processed < 16_000_000);
+        assert!(last.last_nonce.is_some_and(|nonce| nonce >= 65_536));
+    }
 }
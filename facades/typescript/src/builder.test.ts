@@ -123,6 +123,30 @@ describe("TransactionBuilder validation", () => {
     });
   });
 
+  it("accepts anchors without an address and rejects them below dust", async () => {
+    const builder = new TransactionBuilder("testnet", 1);
+    const anchor: TxOutput = { address: "", change: false, kind: "anchor" };
+
+    await builder.buildMiningTemplate({
+      inputs: baseInputs,
+      outputs: [...validOutputs, anchor],
+      startNonce: 0n,
+      batchSize: 1,
+    });
+    expect(mockWasm.validate_address).toHaveBeenCalledTimes(2);
+
+    await expect(
+      builder.buildMiningTemplate({
+        inputs: baseInputs,
+        outputs: [...validOutputs, { ...anchor, amount: 0 }],
+        startNonce: 0n,
+        batchSize: 1,
+      })
+    ).rejects.toMatchObject({
+      code: ZeldMinerErrorCode.DUST_OUTPUT,
+    });
+  });
+
   it("maps wasm fee errors to INSUFFICIENT_FUNDS", async () => {
     mockWasm.build_mining_template.mockImplementationOnce(() => {
       throw new Error("insufficient funds for outputs and fee");
@@ -166,7 +190,7 @@ describe("TransactionBuilder validation", () => {
     const startNonce = 0x1ffn; // stays within 2-byte length for this batch
     const batchSize = 10;
 
-    let capturedSats: bigint | undefined;
+    let capturedSats: number | bigint | undefined;
     let capturedStart: bigint | undefined;
     let capturedBatch: number | undefined;
 
@@ -186,7 +210,7 @@ describe("TransactionBuilder validation", () => {
       batchSize,
     });
 
-    expect(capturedSats).toBe(3n);
+    expect(capturedSats).toBe(3);
     expect(capturedStart).toBe(startNonce);
     expect(capturedBatch).toBe(batchSize);
     expect(template.nonceLength).toBe(2);
@@ -1,9 +1,12 @@
 import type {
+  AddressType,
   MiningTemplate,
   Network,
   TxInput,
+  TxOptions,
   TxOutput,
   ValidationResult,
+  Verification,
   WasmExports,
 } from "./types";
 import { ZeldMinerErrorCode } from "./types";
@@ -16,12 +19,22 @@ const HEX_REGEX = /^[0-9a-fA-F]+$/;
 const MAX_U64 = (1n << 64n) - 1n;
 const MAX_U32 = 0xffff_ffff;
 
-const dustLimitForAddress = (addressType?: "p2wpkh" | "p2tr"): number => {
-  if (addressType === "p2tr") return 330;
-  if (addressType === "p2wpkh") return 310;
-  return 546; // conservative fallback for unexpected types
+// Bitcoin Core dust thresholds at the default 3 sat/vB dust relay fee.
+const DUST_LIMITS: Record<AddressType, number> = {
+  p2pkh: 546,
+  p2sh: 540,
+  p2wpkh: 294,
+  p2wsh: 330,
+  p2tr: 330,
+  p2a: 240,
+  // Future witness versions scale with program length; this is the 2-byte floor
+  // and the WASM planner applies the exact threshold.
+  witness_unknown: 240,
 };
 
+const dustLimitForAddress = (addressType?: AddressType): number =>
+  (addressType && DUST_LIMITS[addressType]) ?? 546; // conservative fallback for unexpected types
+
 const formatError = (err: unknown): string =>
   err instanceof Error ? err.message : String(err);
 
@@ -58,9 +71,6 @@ const mapWasmError = (
   });
 };
 
-const normalizeNetwork = (network: Network): Network =>
-  network === "signet" ? "testnet" : network;
-
 export class TransactionBuilder {
   private readonly network: Network;
   private readonly satsPerVbyte: number;
@@ -171,6 +181,19 @@ export class TransactionBuilder {
           { index: idx }
         );
       }
+      if (
+        input.redeemScript !== undefined &&
+        (typeof input.redeemScript !== "string" ||
+          input.redeemScript.length === 0 ||
+          input.redeemScript.length % 2 !== 0 ||
+          !HEX_REGEX.test(input.redeemScript))
+      ) {
+        throw createMinerError(
+          ZeldMinerErrorCode.INVALID_INPUT,
+          `inputs[${idx}].redeemScript must be valid hex`,
+          { index: idx }
+        );
+      }
     });
   }
 
@@ -193,11 +216,7 @@ export class TransactionBuilder {
       );
     }
 
-    if (
-      validation.network &&
-      validation.network !== network &&
-      !(validation.network === "testnet" && network === "signet")
-    ) {
+    if (validation.network && validation.network !== network) {
       throw createMinerError(
         ZeldMinerErrorCode.INVALID_ADDRESS,
         `outputs[${idx}].address network mismatch`,
@@ -205,11 +224,7 @@ export class TransactionBuilder {
       );
     }
 
-    if (
-      validation.addressType &&
-      validation.addressType !== "p2tr" &&
-      validation.addressType !== "p2wpkh"
-    ) {
+    if (validation.addressType && !(validation.addressType in DUST_LIMITS)) {
       throw createMinerError(
         ZeldMinerErrorCode.UNSUPPORTED_ADDRESS_TYPE,
         `outputs[${idx}].address uses an unsupported type`,
@@ -236,11 +251,18 @@ export class TransactionBuilder {
     }
 
     const wasm = await this.getWasm();
-    const net = normalizeNetwork(this.network);
 
     outputs.forEach((output, idx) => {
-      const validation = wasm.validate_address(output.address, net);
-      this.validateAddressResult(validation, idx, net);
+      if (output.kind === "anchor") {
+        this.validateAnchor(output, idx);
+        return;
+      }
+      if (typeof output.kind === "object") {
+        this.validateScriptOutput(output, output.kind.script, idx);
+        return;
+      }
+      const validation = wasm.validate_address(output.address, this.network);
+      this.validateAddressResult(validation, idx, this.network);
       const dustLimit = dustLimitForAddress(validation.addressType);
 
       if (output.change) {
@@ -269,6 +291,59 @@ export class TransactionBuilder {
     });
   }
 
+  private validateAnchor(output: TxOutput, idx: number): void {
+    if (output.change) {
+      throw createMinerError(
+        ZeldMinerErrorCode.INVALID_INPUT,
+        `outputs[${idx}] anchor cannot be the change output`,
+        { index: idx }
+      );
+    }
+    // Without an amount the anchor carries its dust threshold. Ephemeral (below
+    // dust) anchors need a zero-fee parent, which fee-rate planning never builds.
+    if (
+      output.amount !== undefined &&
+      (!Number.isInteger(output.amount) ||
+        output.amount < DUST_LIMITS.p2a)
+    ) {
+      throw createMinerError(
+        ZeldMinerErrorCode.DUST_OUTPUT,
+        `outputs[${idx}].amount must be at least ${DUST_LIMITS.p2a} sats`,
+        { index: idx, addressType: "p2a" }
+      );
+    }
+  }
+
+  private validateScriptOutput(
+    output: TxOutput,
+    script: string,
+    idx: number
+  ): void {
+    if (
+      typeof script !== "string" ||
+      script.length === 0 ||
+      script.length % 2 !== 0 ||
+      !HEX_REGEX.test(script)
+    ) {
+      throw createMinerError(
+        ZeldMinerErrorCode.INVALID_INPUT,
+        `outputs[${idx}].kind.script must be valid hex`,
+        { index: idx }
+      );
+    }
+    // Standardness and dust depend on the script type; the WASM planner checks both.
+    if (
+      (output.amount !== undefined || !output.change) &&
+      (!Number.isInteger(output.amount) || (output.amount as number) < 0)
+    ) {
+      throw createMinerError(
+        ZeldMinerErrorCode.INVALID_INPUT,
+        `outputs[${idx}].amount must be a non-negative integer`,
+        { index: idx }
+      );
+    }
+  }
+
   private validateDistribution(
     outputs: TxOutput[],
     distribution?: bigint[]
@@ -314,8 +389,9 @@ export class TransactionBuilder {
     startNonce: bigint;
     batchSize: number;
     distribution?: bigint[];
+    txOptions?: TxOptions;
   }): Promise<MiningTemplate & { nonceLength: number }> {
-    const { inputs, outputs, startNonce, batchSize, distribution } = params;
+    const { inputs, outputs, startNonce, batchSize, distribution, txOptions } = params;
     this.validateInputs(inputs);
     await this.validateOutputs(outputs);
     const validatedDistribution = this.validateDistribution(outputs, distribution);
@@ -327,11 +403,12 @@ export class TransactionBuilder {
       const template = wasm.build_mining_template(
         this.cloneInputs(inputs),
         this.cloneOutputs(outputs),
-        normalizeNetwork(this.network),
-        BigInt(this.satsPerVbyte),
+        this.network,
+        this.satsPerVbyte,
         startNonce,
         batchSize,
-        validatedDistribution ?? null
+        validatedDistribution ?? null,
+        txOptions ?? null
       );
 
       if (
@@ -361,8 +438,9 @@ export class TransactionBuilder {
     outputs: TxOutput[];
     nonce: bigint;
     distribution?: bigint[];
+    txOptions?: TxOptions;
   }): Promise<string> {
-    const { inputs, outputs, nonce, distribution } = params;
+    const { inputs, outputs, nonce, distribution, txOptions } = params;
     this.validateInputs(inputs);
     await this.validateOutputs(outputs);
     const validatedDistribution = this.validateDistribution(outputs, distribution);
@@ -380,14 +458,29 @@ export class TransactionBuilder {
       return wasm.build_psbt(
         this.cloneInputs(inputs),
         this.cloneOutputs(outputs),
-        normalizeNetwork(this.network),
-        BigInt(this.satsPerVbyte),
+        this.network,
+        this.satsPerVbyte,
         nonce,
-        validatedDistribution ?? null
+        validatedDistribution ?? null,
+        txOptions ?? null
       );
     } catch (err) {
       throw mapWasmError(err, "build_psbt", { nonce, distribution: validatedDistribution });
     }
   }
-}
 
+  /**
+   * Check a transaction signed from `minedPsbt` (the PSBT `buildPsbt` returned): a
+   * finalized PSBT in base64 or hex, or the raw transaction in hex.
+   */
+  async verifySignedTx(minedPsbt: string, signed: string): Promise<Verification> {
+    const wasm = await this.getWasm();
+    try {
+      return wasm.verify_signed_tx(minedPsbt, signed);
+    } catch (err) {
+      throw toZeldMinerError(err, ZeldMinerErrorCode.INVALID_INPUT, {
+        context: "verify_signed_tx",
+      });
+    }
+  }
+}
@@ -4,6 +4,7 @@ import type {
   ProgressEvent as MiningProgressEvent,
   Network,
   TxInput,
+  TxOptions,
   TxOutput,
   WorkerMessage,
   WorkerMode,
@@ -47,6 +48,7 @@ interface StartParams {
   startNonce?: bigint;
   signal?: AbortSignal;
   distribution?: bigint[];
+  txOptions?: TxOptions;
 }
 
 const safeBigIntToNumber = (value: bigint): number => {
@@ -85,6 +87,7 @@ export class MiningCoordinator {
   private targetZeros?: number;
   private startNonce: bigint = 0n;
   private txDistribution?: bigint[];
+  private txOptions?: TxOptions;
   private externalAbort?: AbortSignal;
   private terminated = false;
 
@@ -313,12 +316,13 @@ export class MiningCoordinator {
     this.startedAt = performance.now();
     this.txInputs = params.inputs;
     this.txOutputs = params.outputs;
-    this.txNetwork = params.network === "signet" ? "testnet" : params.network;
+    this.txNetwork = params.network;
     this.satsPerVbyte = params.satsPerVbyte;
     this.template = params.template;
     this.targetZeros = params.targetZeros;
     this.startNonce = params.startNonce ?? 0n;
     this.txDistribution = params.distribution;
+    this.txOptions = params.txOptions;
 
     if (this.externalAbort && this.externalAbort !== params.signal) {
       this.cleanupExternalAbort();
@@ -347,6 +351,7 @@ export class MiningCoordinator {
     const template = this.template;
     const targetZeros = this.targetZeros;
     const distribution = this.txDistribution;
+    const txOptions = this.txOptions;
     const stride = this.stride;
 
     this.workers.forEach((state, idx) => {
@@ -370,6 +375,7 @@ export class MiningCoordinator {
         targetZeros,
         nonceStep: stride,
         distribution,
+        txOptions,
       };
 
       state.worker.postMessage(message);
@@ -409,6 +415,7 @@ export class MiningCoordinator {
     const template = this.template;
     const targetZeros = this.targetZeros as number;
     const distribution = this.txDistribution;
+    const txOptions = this.txOptions;
     const stride = this.stride;
 
     this.workers.forEach((state, idx) => {
@@ -430,6 +437,7 @@ export class MiningCoordinator {
         targetZeros,
         nonceStep: stride,
         distribution,
+        txOptions,
       };
 
       state.worker.postMessage(message);
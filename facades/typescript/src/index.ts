@@ -5,6 +5,8 @@ import type {
   MineParams,
   MineResult,
   ProgressStats,
+  TapKeyDerivation,
+  Verification,
   WorkerMode,
   ZeldMinerOptions,
 } from "./types";
@@ -157,6 +159,7 @@ export class ZeldMiner {
     }
 
     const distribution = params.distribution;
+    const txOptions = params.txOptions;
     const useCborNonce = Boolean(distribution && distribution.length > 0);
     let firstSegmentSize = batchSize;
     try {
@@ -186,10 +189,10 @@ export class ZeldMiner {
       startNonce,
       batchSize: firstSegmentSize,
       distribution,
+      txOptions,
     });
 
-    const network =
-      this.options.network === "signet" ? "testnet" : this.options.network;
+    const network = this.options.network;
     const coordinator = new MiningCoordinator({
       mode,
       batchSize,
@@ -241,6 +244,7 @@ export class ZeldMiner {
             outputs: params.outputs,
             nonce: result.nonce,
             distribution,
+            txOptions,
           });
 
           const finalResult: MineResult = { ...result, psbt };
@@ -286,11 +290,17 @@ export class ZeldMiner {
           startNonce,
           signal: params.signal,
           distribution,
+          txOptions,
         })
         .catch(rejectOnce);
     });
   }
 
+  /** Check that the wallet-signed transaction still has the mined txid and payload. */
+  async verifySignedTransaction(result: MineResult, signed: string): Promise<Verification> {
+    return this.builder.verifySignedTx(result.psbt, signed);
+  }
+
   stop(): void {
     if (!this.coordinator) return;
     this.stopRequested = true;
@@ -311,13 +321,21 @@ export class ZeldMiner {
 }
 
 export type {
+  AddressType,
+  Bip32Derivation,
   ZeldMinerOptions,
   MineParams,
   MineResult,
   ProgressStats,
+  LockTime,
   Network,
+  OutputKind,
   TxInput,
+  TxOptions,
+  TxMismatch,
   TxOutput,
+  Verification,
+  WitnessHint,
 } from "./types";
 export { ZeldMinerErrorCode } from "./types";
 export { TransactionBuilder } from "./builder";
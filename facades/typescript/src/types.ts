@@ -12,13 +12,14 @@ export enum ZeldMinerErrorCode {
 
 export type ZeldMinerErrorDetails = Record<string, unknown>;
 
-export type Network = "mainnet" | "testnet" | "signet" | "regtest";
+export type Network = "mainnet" | "testnet" | "testnet4" | "signet" | "regtest";
 
 export interface ZeldMinerOptions {
   network: Network;
   batchSize: number;
   useWebGPU: boolean;
   workerThreads: number;
+  /** Fee rate in sat/vB; up to three decimals (e.g. 1.5) are honored. */
   satsPerVbyte: number;
 }
 
@@ -28,17 +29,95 @@ export interface MiningCoordinatorOptions {
   workerThreads: number;
 }
 
+export type AddressType =
+  | "p2pkh"
+  | "p2sh"
+  | "p2wpkh"
+  | "p2wsh"
+  | "p2tr"
+  | "p2a"
+  | "witness_unknown";
+
+/**
+ * `"anchor"` adds a pay-to-anchor output for CPFP and `{ script }` pays a raw hex
+ * scriptPubKey of a standard type; both ignore `address`.
+ */
+export type OutputKind = "address" | "anchor" | { script: string };
+
 export interface TxInput {
   txid: string;
   vout: number;
   scriptPubKey: string;
   amount: number;
+  /** Witness size hint; required unless scriptPubKey is P2WPKH or P2TR key-path. */
+  witness?: WitnessHint;
+  /** Hex witness program wrapped by a P2SH scriptPubKey (nested SegWit). */
+  redeemScript?: string;
+  /** Hex of the full previous transaction, for signers that require it. */
+  nonWitnessUtxo?: string;
+  /** Sighash type the signer should use (e.g. 0x01 for ALL). */
+  sighashType?: number;
+  /** Key origins of the keys that sign this input. */
+  bip32Derivations?: Bip32Derivation[];
+  /** Hex x-only Taproot internal key. */
+  tapInternalKey?: string;
+  tapKeyDerivations?: TapKeyDerivation[];
+}
+
+/** Origin of a public key: master fingerprint plus derivation path. */
+export interface Bip32Derivation {
+  /** Hex compressed or uncompressed public key. */
+  pubkey: string;
+  /** Hex 4-byte master key fingerprint. */
+  fingerprint: string;
+  /** Derivation path such as `m/84'/0'/0'/0/5`. */
+  path: string;
+}
+
+/** Origin of a Taproot x-only key and the leaves it signs for. */
+export interface TapKeyDerivation {
+  xOnlyPubkey: string;
+  /** Hex leaf hashes; empty for the key path. */
+  leafHashes?: string[];
+  fingerprint: string;
+  path: string;
+}
+
+/** How an input will be spent, for fee estimation. */
+export type WitnessHint =
+  | { size: number }
+  | { multisig: { required: number; keys: number } }
+  | { tapScript: { signatures: number; scriptLen: number; depth: number } };
+
+/**
+ * nLockTime: `"none"`, a block `{ height }`, a UNIX `{ time }`, or
+ * `{ antiFeeSniping: tipHeight }` to lock to the current tip like Bitcoin Core.
+ */
+export type LockTime =
+  | "none"
+  | { height: number }
+  | { time: number }
+  | { antiFeeSniping: number };
+
+/** Transaction framing; defaults to a version 2 PSBTv0 without a locktime. */
+export interface TxOptions {
+  /** 1, 2 or 3 for a TRUC (BIP431) transaction. */
+  version?: 1 | 2 | 3;
+  /** Needs at least one input with a sequence below 0xffffffff. */
+  lockTime?: LockTime;
+  /** 2 for a BIP370 PSBT marked non-modifiable, so signers keep the mined txid. */
+  psbtVersion?: 0 | 2;
 }
 
 export interface TxOutput {
   address: string;
   amount?: number;
   change: boolean;
+  kind?: OutputKind;
+  /** Key origins, so wallets can recognize their own change. */
+  bip32Derivations?: Bip32Derivation[];
+  tapInternalKey?: string;
+  tapKeyDerivations?: TapKeyDerivation[];
 }
 
 export interface MineResult {
@@ -74,6 +153,7 @@ export type WorkerMessage =
       targetZeros: number;
       nonceStep?: bigint;
       distribution?: bigint[];
+      txOptions?: TxOptions;
     }
   | { type: "stop" };
 
@@ -128,10 +208,61 @@ export interface WorkerTemplate extends MiningTemplate {
 export interface ValidationResult {
   ok: boolean;
   error?: string;
-  addressType?: "p2wpkh" | "p2tr";
+  addressType?: AddressType;
   network?: Network;
 }
 
+/** Luck figures computed by the core statistics helper. */
+export interface MiningOdds {
+  hitProbability: number;
+  expectedRemainingAttempts: number;
+  etaMs: number | null;
+  probabilityFound: number;
+}
+
+/** Field of a signed transaction that differs from the mined one. */
+export type TxMismatch =
+  | { kind: "version"; expected: number; actual: number }
+  | { kind: "lockTime"; expected: number; actual: number }
+  | { kind: "inputCount"; expected: number; actual: number }
+  | { kind: "outpoint"; index: number }
+  | { kind: "sequence"; index: number; expected: number; actual: number }
+  | { kind: "scriptSig"; index: number }
+  | { kind: "inputAmount"; index: number; expected: number; actual: number }
+  | { kind: "outputCount"; expected: number; actual: number }
+  | { kind: "output"; index: number };
+
+/** A signed transaction checked against the mined one. */
+export interface Verification {
+  txid: string;
+  /** The signer kept the mined txid. */
+  txidMatches: boolean;
+  leadingZeros: number;
+  leadingZeroBits: number;
+  /** The mined OP_RETURN, nonce and ZELD payload included, is still an output. */
+  payloadIntact: boolean;
+  /**
+   * What the signer changed; empty when `txidMatches`, unless a signed PSBT
+   * disagrees on spent amounts.
+   */
+  mismatches: TxMismatch[];
+  /** Weight and vsize with the real witnesses attached. */
+  weight: number;
+  vsize: number;
+  /** Fee paid, when the spent amounts are known. */
+  fee: number | null;
+  satPerKvb: number | null;
+}
+
+/** Vanity txid pattern accepted by the WASM mining entry points. */
+export type TxidPatternSpec =
+  | { leadingZeros: number }
+  | { leadingZeroBits: number }
+  | { prefix: string }
+  | { suffix: string }
+  | { masked: string }
+  | { anyOf: TxidPatternSpec[] };
+
 export interface WasmExports {
   init_panic_hook: () => void;
   mine_batch_wasm: (
@@ -139,7 +270,7 @@ export interface WasmExports {
     txSuffix: Uint8Array,
     startNonce: bigint,
     batchSize: number,
-    targetZeros: number,
+    target: number | TxidPatternSpec,
     useCborNonce?: boolean
   ) => ValidationResult | MineResult | null;
   mine_batch_gpu?: (
@@ -147,48 +278,65 @@ export interface WasmExports {
     txSuffix: Uint8Array,
     startNonce: bigint,
     batchSize: number,
-    targetZeros: number,
+    target: number | TxidPatternSpec,
     useCborNonce?: boolean
   ) => Promise<ValidationResult | MineResult | null>;
   mine_range_wasm: (
     inputs: TxInput[],
     outputs: TxOutput[],
     network: Network,
-    satsPerVbyte: bigint,
+    satsPerVbyte: number | bigint,
     startNonce: bigint,
     batchSize: number,
-    targetZeros: number,
-    distribution?: bigint[] | null
+    target: number | TxidPatternSpec,
+    distribution?: bigint[] | null,
+    txOptions?: TxOptions | null
   ) => ValidationResult | MineResult | null;
   mine_range_gpu?: (
     inputs: TxInput[],
     outputs: TxOutput[],
     network: Network,
-    satsPerVbyte: bigint,
+    satsPerVbyte: number | bigint,
     startNonce: bigint,
     batchSize: number,
-    targetZeros: number,
-    distribution?: bigint[] | null
+    target: number | TxidPatternSpec,
+    distribution?: bigint[] | null,
+    txOptions?: TxOptions | null
   ) => Promise<ValidationResult | MineResult | null>;
   validate_address: (addr: string, network: Network) => ValidationResult;
   build_psbt: (
     inputs: TxInput[],
     outputs: TxOutput[],
     network: Network,
-    satsPerVbyte: bigint,
+    satsPerVbyte: number | bigint,
     nonce: bigint,
-    distribution?: bigint[] | null
+    distribution?: bigint[] | null,
+    txOptions?: TxOptions | null
   ) => string;
   build_mining_template: (
     inputs: TxInput[],
     outputs: TxOutput[],
     network: Network,
-    satsPerVbyte: bigint,
+    satsPerVbyte: number | bigint,
     startNonce: bigint,
     batchSize: number,
-    distribution?: bigint[] | null
+    distribution?: bigint[] | null,
+    txOptions?: TxOptions | null
   ) => MiningTemplate;
   compute_txid: (txBytes: Uint8Array) => string;
+  verify_signed_tx: (minedPsbt: string, signed: string) => Verification;
+  verify_signed_tx_with_template: (
+    prefix: Uint8Array,
+    suffix: Uint8Array,
+    nonce: bigint,
+    useCborNonce: boolean,
+    signed: string
+  ) => Verification;
+  mining_odds?: (
+    target: number | TxidPatternSpec,
+    attempts: bigint,
+    hashRate: number
+  ) => MiningOdds;
   init_gpu?: () => Promise<unknown>;
   calibrate_batch_size?: () => Promise<number>;
 }
@@ -203,5 +351,6 @@ export interface MineParams {
   batchSize?: number;
   signal?: AbortSignal;
   distribution?: bigint[];
+  txOptions?: TxOptions;
 }
 
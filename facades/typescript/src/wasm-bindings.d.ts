@@ -13,40 +13,62 @@ declare module "../wasm/zeldhash_miner_wasm.js" {
   const init: (moduleOrPath?: InitInput | Promise<InitInput>) => Promise<InitOutput>;
   export default init;
 
+  export type TxidPatternSpec =
+    | { leadingZeros: number }
+    | { leadingZeroBits: number }
+    | { prefix: string }
+    | { suffix: string }
+    | { masked: string }
+    | { anyOf: TxidPatternSpec[] };
+
+  export type LockTime =
+    | "none"
+    | { height: number }
+    | { time: number }
+    | { antiFeeSniping: number };
+
+  export interface TxOptions {
+    version?: 1 | 2 | 3;
+    lockTime?: LockTime;
+    psbtVersion?: 0 | 2;
+  }
+
   export function init_panic_hook(): void;
   export function mine_batch_wasm(
     txPrefix: Uint8Array,
     txSuffix: Uint8Array,
     startNonce: bigint,
     batchSize: number,
-    targetZeros: number,
+    target: number | TxidPatternSpec,
     useCborNonce?: boolean
   ): unknown;
   export function mine_range_wasm(
     inputs: unknown,
     outputs: unknown,
     network: string,
-    satsPerVbyte: bigint,
+    satsPerVbyte: number | bigint,
     range: { startNonce: bigint; batchSize: number },
-    targetZeros: number,
-    distribution?: bigint[] | null
+    target: number | TxidPatternSpec,
+    distribution?: bigint[] | null,
+    txOptions?: TxOptions | null
   ): unknown;
   export function mine_batch_gpu(
     txPrefix: Uint8Array,
     txSuffix: Uint8Array,
     startNonce: bigint,
     batchSize: number,
-    targetZeros: number,
+    target: number | TxidPatternSpec,
     useCborNonce?: boolean
   ): Promise<unknown>;
   export function mine_range_gpu(
     inputs: unknown,
     outputs: unknown,
     network: string,
-    satsPerVbyte: bigint,
+    satsPerVbyte: number | bigint,
     range: { startNonce: bigint; batchSize: number },
-    targetZeros: number,
-    distribution?: bigint[] | null
+    target: number | TxidPatternSpec,
+    distribution?: bigint[] | null,
+    txOptions?: TxOptions | null
   ): Promise<unknown>;
   export function validate_address(
     addr: string,
@@ -56,20 +78,40 @@ declare module "../wasm/zeldhash_miner_wasm.js" {
     inputs: unknown,
     outputs: unknown,
     network: string,
-    satsPerVbyte: bigint,
+    satsPerVbyte: number | bigint,
     nonce: bigint,
-    distribution?: bigint[] | null
+    distribution?: bigint[] | null,
+    txOptions?: TxOptions | null
   ): string;
   export function build_mining_template(
     inputs: unknown,
     outputs: unknown,
     network: string,
-    satsPerVbyte: bigint,
+    satsPerVbyte: number | bigint,
     startNonce: bigint,
     batchSize: number,
-    distribution?: bigint[] | null
+    distribution?: bigint[] | null,
+    txOptions?: TxOptions | null
   ): unknown;
   export function compute_txid(txBytes: Uint8Array): string;
+  export function verify_signed_tx(minedPsbt: string, signed: string): unknown;
+  export function verify_signed_tx_with_template(
+    prefix: Uint8Array,
+    suffix: Uint8Array,
+    nonce: bigint,
+    useCborNonce: boolean,
+    signed: string
+  ): unknown;
+  export function mining_odds(
+    target: number | TxidPatternSpec,
+    attempts: bigint,
+    hashRate: number
+  ): {
+    hitProbability: number;
+    expectedRemainingAttempts: number;
+    etaMs: number | null;
+    probabilityFound: number;
+  };
   export function init_gpu(): Promise<unknown>;
   export function calibrate_batch_size(): Promise<number>;
 }
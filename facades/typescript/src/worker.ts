@@ -90,10 +90,11 @@ const ensureTemplateForSegment = async (
     outputs: MineMessage["outputs"];
     satsPerVbyte: number;
     distribution?: bigint[];
+    txOptions?: MineMessage["txOptions"];
     useCborNonce: boolean;
   },
   segment: { start: bigint; size: number; nonceLength: number },
-  normalizedNetwork: MineMessage["network"]
+  network: MineMessage["network"]
 ): Promise<WorkerTemplate> => {
   const cached = cache.get(segment.nonceLength);
   if (cached) {
@@ -103,11 +104,12 @@ const ensureTemplateForSegment = async (
   const template = wasm.build_mining_template(
     params.inputs,
     params.outputs,
-    normalizedNetwork,
-    BigInt(params.satsPerVbyte),
+    network,
+    params.satsPerVbyte,
     segment.start,
     segment.size,
-    params.distribution ?? null
+    params.distribution ?? null,
+    params.txOptions ?? null
   );
 
   const built: WorkerTemplate = {
@@ -186,7 +188,6 @@ const mineLoop = async (msg: MineMessage, abort: AbortController): Promise<void>
   cacheTemplate(templateCache, { ...msg.template, useCborNonce });
 
   const stride = msg.nonceStep ?? BigInt(msg.batchSize);
-  const normalizedNetwork = msg.network === "signet" ? "testnet" : msg.network;
   let nextNonce = msg.startNonce;
   let hashesProcessed = 0n;
   const startedAt = performance.now();
@@ -224,10 +225,11 @@ const mineLoop = async (msg: MineMessage, abort: AbortController): Promise<void>
             outputs: msg.outputs,
             satsPerVbyte: msg.satsPerVbyte,
             distribution: msg.distribution,
+            txOptions: msg.txOptions,
             useCborNonce,
           },
           segment,
-          normalizedNetwork
+          msg.network
         );
       } catch (err) {
         postError(